@@ -57,10 +57,11 @@ struct DemoApp {
 }
 
 struct DemoState {
+    /// Dropped before `win`, while the GL context still exists.
+    terminal: Terminal<BeamtermBackend>,
     win: GlWindow,
     gl: Rc<glow::Context>,
     gl_state: GlState,
-    terminal: Terminal<BeamtermBackend>,
     app: App<'static>,
 }
 
@@ -145,16 +146,15 @@ impl ApplicationHandler for DemoApp {
                     event_loop.exit();
                 }
             }
-            WindowEvent::Resized(new_size) => {
-                if new_size.width > 0 && new_size.height > 0 {
-                    state.win.resize_surface(new_size);
-                    let _ = state.terminal.backend_mut().grid_mut().resize(
-                        &state.gl,
-                        (new_size.width as i32, new_size.height as i32),
-                        state.win.pixel_ratio(),
-                    );
-                    state.win.window.request_redraw();
-                }
+            WindowEvent::Resized(new_size)
+                if new_size.width > 0 && new_size.height > 0 =>
+            {
+                state.win.resize_surface(new_size);
+                let _ = state.terminal.backend_mut().resize(
+                    (new_size.width as i32, new_size.height as i32),
+                    state.win.pixel_ratio(),
+                );
+                state.win.window.request_redraw();
             }
             WindowEvent::RedrawRequested => {
                 let elapsed = state.app.on_tick();
@@ -1,4 +1,7 @@
 // This example renders animated wave interference patterns using layered oscillators.
+//
+// Pass `--record <path>` to record the animation: a path ending in `.png`
+// becomes an animated PNG, any other path a directory of numbered PNGs.
 
 mod wave_effect;
 
@@ -11,7 +14,7 @@ use beamterm_core::{
 };
 use glutin::surface::GlSurface;
 use ratatui::Terminal;
-use ratbeam::BeamtermBackend;
+use ratbeam::{BeamtermBackend, FrameRecorder};
 use tachyonfx::{EffectRenderer, IntoEffect, Duration};
 use wave_effect::WaveInterference;
 use winit::{
@@ -24,22 +27,40 @@ use winit::{
 
 fn main() {
     let event_loop = EventLoop::new().expect("failed to create event loop");
-    let mut app = WavesApp::default();
+    let mut app = WavesApp {
+        state: None,
+        recorder: recorder_from_args(),
+    };
     event_loop.run_app(&mut app).expect("event loop failed");
 }
 
 // ── Application handler ─────────────────────────────────────────────
 
-#[derive(Default)]
 struct WavesApp {
     state: Option<WavesState>,
+    recorder: Option<FrameRecorder>,
+}
+
+/// Creates a recorder if `--record <path>` was passed, keeping every other
+/// frame at 30 fps.
+fn recorder_from_args() -> Option<FrameRecorder> {
+    let mut args = std::env::args().skip_while(|arg| arg != "--record");
+    let path = args.nth(1)?;
+
+    let recorder = if path.ends_with(".png") {
+        FrameRecorder::apng(path)
+    } else {
+        FrameRecorder::png_sequence(path)
+    };
+    Some(recorder.every(2).frame_rate(30))
 }
 
 struct WavesState {
+    /// Dropped before `win`, while the GL context still exists.
+    terminal: Terminal<BeamtermBackend>,
     win: GlWindow,
     gl: Rc<glow::Context>,
     gl_state: GlState,
-    terminal: Terminal<BeamtermBackend>,
     effect: tachyonfx::Effect,
     last_frame: Instant,
 }
@@ -110,16 +131,15 @@ impl ApplicationHandler for WavesApp {
                     _ => {}
                 }
             }
-            WindowEvent::Resized(new_size) => {
-                if new_size.width > 0 && new_size.height > 0 {
-                    state.win.resize_surface(new_size);
-                    let _ = state.terminal.backend_mut().grid_mut().resize(
-                        &state.gl,
-                        (new_size.width as i32, new_size.height as i32),
-                        state.win.pixel_ratio(),
-                    );
-                    state.win.window.request_redraw();
-                }
+            WindowEvent::Resized(new_size)
+                if new_size.width > 0 && new_size.height > 0 =>
+            {
+                state.win.resize_surface(new_size);
+                let _ = state.terminal.backend_mut().resize(
+                    (new_size.width as i32, new_size.height as i32),
+                    state.win.pixel_ratio(),
+                );
+                state.win.window.request_redraw();
             }
             WindowEvent::RedrawRequested => {
                 let now = Instant::now();
@@ -160,12 +180,25 @@ impl ApplicationHandler for WavesApp {
                 grid.draw(&mut ctx);
                 grid.cleanup(&mut ctx);
 
+                if let Some(recorder) = self.recorder.as_mut() {
+                    recorder
+                        .record(state.terminal.backend())
+                        .expect("failed to record frame");
+                }
+
                 state.win.swap_buffers();
             }
             _ => {}
         }
     }
 
+    fn exiting(&mut self, _event_loop: &ActiveEventLoop) {
+        if let Some(recorder) = self.recorder.take() {
+            let frames = recorder.finish().expect("failed to finish recording");
+            println!("recorded {frames} frames");
+        }
+    }
+
     fn about_to_wait(&mut self, _event_loop: &ActiveEventLoop) {
         if let Some(state) = self.state.as_ref() {
             state.win.window.request_redraw();
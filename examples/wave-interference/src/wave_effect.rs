@@ -1,6 +1,6 @@
 use ratatui::buffer::Buffer;
 use ratatui::layout::Rect;
-use tachyonfx::{color_from_hsl, default_shader_impl, wave_sin, CellFilter, ColorSpace, Duration, FilterProcessor, Interpolation, Shader};
+use tachyonfx::{color_from_hsl, default_shader_impl, CellFilter, ColorSpace, Duration, FilterProcessor, Interpolation, Shader};
 use tachyonfx::wave::{Modulator, Oscillator, SignalSampler, WaveLayer};
 
 /// A shader that creates wave interference patterns.
@@ -0,0 +1,167 @@
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+
+use beamterm_core::{Atlas, Error as BeamtermError, GlyphSlot, GlyphTracker};
+use beamterm_data::LineDecoration;
+use compact_str::CompactString;
+
+/// A font atlas shared by several [`TerminalGrid`](beamterm_core::TerminalGrid)s.
+///
+/// Clones refer to the same atlas and GPU texture, so panes in one GL
+/// context load and upload their glyphs only once. Each clone is passed to
+/// a grid like any other atlas:
+///
+/// ```no_run
+/// # use beamterm_core::{FontAtlasData, GlslVersion, StaticFontAtlas, TerminalGrid};
+/// # use ratbeam::SharedAtlas;
+/// # fn grids(gl: &glow::Context) -> Result<(), beamterm_core::Error> {
+/// let atlas = SharedAtlas::new(StaticFontAtlas::load(gl, FontAtlasData::default())?);
+/// let left = TerminalGrid::new(gl, atlas.clone().into(), (640, 480), 1.0, &GlslVersion::Gl330)?;
+/// let right = TerminalGrid::new(gl, atlas.into(), (640, 480), 1.0, &GlslVersion::Gl330)?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// The texture is deleted along with the last grid using it.
+#[derive(Clone)]
+pub struct SharedAtlas {
+    inner: Rc<SharedInner>,
+}
+
+struct SharedInner {
+    atlas: RefCell<Box<dyn Atlas>>,
+    /// Glyphs missing from the atlas, as reported through any handle.
+    glyph_tracker: GlyphTracker,
+}
+
+impl SharedAtlas {
+    /// Wraps `atlas` for sharing.
+    pub fn new(atlas: impl Atlas + 'static) -> Self {
+        Self {
+            inner: Rc::new(SharedInner {
+                atlas: RefCell::new(Box::new(atlas)),
+                glyph_tracker: GlyphTracker::new(),
+            }),
+        }
+    }
+
+    /// Returns the number of handles to the atlas.
+    pub fn handle_count(&self) -> usize {
+        Rc::strong_count(&self.inner)
+    }
+
+    fn record_missing(&self, key: &str, found: bool) {
+        if !found {
+            self.inner.glyph_tracker.record_missing(key);
+        }
+    }
+}
+
+impl fmt::Debug for SharedAtlas {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SharedAtlas")
+            .field("handle_count", &self.handle_count())
+            .finish_non_exhaustive()
+    }
+}
+
+impl Atlas for SharedAtlas {
+    fn get_glyph_id(&self, key: &str, style_bits: u16) -> Option<u16> {
+        let id = self.inner.atlas.borrow().get_glyph_id(key, style_bits);
+        self.record_missing(key, id.is_some());
+        id
+    }
+
+    fn get_base_glyph_id(&self, key: &str) -> Option<u16> {
+        let id = self.inner.atlas.borrow().get_base_glyph_id(key);
+        self.record_missing(key, id.is_some());
+        id
+    }
+
+    fn cell_size(&self) -> (i32, i32) {
+        self.inner.atlas.borrow().cell_size()
+    }
+
+    fn bind(&self, gl: &glow::Context) {
+        self.inner.atlas.borrow().bind(gl);
+    }
+
+    fn underline(&self) -> LineDecoration {
+        self.inner.atlas.borrow().underline()
+    }
+
+    fn strikethrough(&self) -> LineDecoration {
+        self.inner.atlas.borrow().strikethrough()
+    }
+
+    fn get_symbol(&self, glyph_id: u16) -> Option<CompactString> {
+        self.inner.atlas.borrow().get_symbol(glyph_id)
+    }
+
+    fn get_ascii_char(&self, glyph_id: u16) -> Option<char> {
+        self.inner.atlas.borrow().get_ascii_char(glyph_id)
+    }
+
+    fn glyph_tracker(&self) -> &GlyphTracker {
+        &self.inner.glyph_tracker
+    }
+
+    fn glyph_count(&self) -> u32 {
+        self.inner.atlas.borrow().glyph_count()
+    }
+
+    fn flush(&self, gl: &glow::Context) -> Result<(), BeamtermError> {
+        self.inner.atlas.borrow().flush(gl)
+    }
+
+    /// Recreates the shared texture; after a context loss this happens once
+    /// per grid, which is redundant but harmless.
+    fn recreate_texture(&mut self, gl: &glow::Context) -> Result<(), BeamtermError> {
+        self.inner.atlas.borrow_mut().recreate_texture(gl)
+    }
+
+    fn for_each_symbol(&self, f: &mut dyn FnMut(u16, &str)) {
+        self.inner.atlas.borrow().for_each_symbol(f);
+    }
+
+    fn resolve_glyph_slot(&self, key: &str, style_bits: u16) -> Option<GlyphSlot> {
+        let slot = self
+            .inner
+            .atlas
+            .borrow()
+            .resolve_glyph_slot(key, style_bits);
+        self.record_missing(key, slot.is_some());
+        slot
+    }
+
+    fn base_lookup_mask(&self) -> u32 {
+        self.inner.atlas.borrow().base_lookup_mask()
+    }
+
+    /// Deletes the texture once no other handle uses it.
+    fn delete(&self, gl: &glow::Context) {
+        if self.handle_count() == 1 {
+            self.inner.atlas.borrow().delete(gl);
+        }
+    }
+
+    fn update_pixel_ratio(
+        &mut self,
+        gl: &glow::Context,
+        pixel_ratio: f32,
+    ) -> Result<f32, BeamtermError> {
+        self.inner
+            .atlas
+            .borrow_mut()
+            .update_pixel_ratio(gl, pixel_ratio)
+    }
+
+    fn cell_scale_for_dpr(&self, pixel_ratio: f32) -> f32 {
+        self.inner.atlas.borrow().cell_scale_for_dpr(pixel_ratio)
+    }
+
+    fn texture_cell_size(&self) -> (i32, i32) {
+        self.inner.atlas.borrow().texture_cell_size()
+    }
+}
@@ -1,34 +1,135 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::iter;
 use std::mem::swap;
+#[cfg(feature = "font")]
+use std::ops::Range;
+use std::ops::{Deref, DerefMut};
 use std::rc::Rc;
 
-use beamterm_core::{CellData, TerminalGrid};
+use beamterm_core::{
+    CellData, Drawable, FontAtlas, GlState, GlslVersion, GlyphEffect, RenderContext, SelectionMode,
+    TerminalGrid, select,
+};
+#[cfg(feature = "font")]
+use beamterm_data::FontStyle;
+use compact_str::CompactString;
 use ratatui::{
     backend::{Backend, ClearType, WindowSize},
-    buffer::Cell,
-    layout::{Position, Size},
+    buffer::{Buffer, Cell},
+    layout::{Position, Rect, Size},
     style::Modifier,
 };
+#[cfg(feature = "font")]
+use unicode_width::UnicodeWidthChar;
 
+use crate::capture::{self, CapturedFrame};
+use crate::clipboard::{Clipboard, MemoryClipboard, paste_event};
 use crate::color::to_rgb;
+use crate::detect::{DetectedMatch, LinkDetector};
 use crate::error::Error;
+use crate::event::Event;
+use crate::export;
+#[cfg(feature = "font")]
+use crate::font::{AtlasBuilder, FontFamily, column_key};
+use crate::hyperlink::{Hyperlinks, expand_osc8};
+use crate::image::{ImageFit, ImageId, ImageLayer, InlineImage};
+use crate::input::{Modifiers, MouseButton};
+use crate::target::{self, RenderTarget, TextureTarget};
+use crate::vt::VtScreen;
+
+/// Shaped runs kept by a backend before it forgets them all.
+#[cfg(feature = "font")]
+const SHAPED_RUNS_LIMIT: usize = 4096;
 
 /// A ratatui [`Backend`] that renders via beamterm-core's GPU-accelerated terminal grid.
 ///
-/// The backend does not own the window or GL lifecycle. The application provides
-/// an `Rc<glow::Context>` and a [`TerminalGrid`].
+/// The backend does not own the window or GL context. The application provides
+/// an `Rc<glow::Context>` and a [`TerminalGrid`], and must keep the context
+/// current until the backend is dropped: dropping it deletes the GPU
+/// resources of the grid, its images and its render texture.
+///
+/// A CPU-side shadow [`Buffer`] mirrors the content of the grid; see
+/// [`buffer`](Self::buffer).
 pub struct BeamtermBackend {
-    grid: TerminalGrid,
+    grid: OwnedGrid,
     gl: Rc<glow::Context>,
+    buffer: Buffer,
     cursor_position: Option<Position>,
+    /// Whether the cursor is drawn, see [`Backend::show_cursor`].
+    cursor_visible: bool,
+    clipboard: Box<dyn Clipboard>,
+    events: VecDeque<Event>,
+    /// Missing glyphs already reported as [`Event::MissingGlyph`].
+    reported_glyphs: HashSet<CompactString>,
+    hyperlinks: Hyperlinks,
+    link_detector: Option<LinkDetector>,
+    pointer_position: Option<Position>,
+    hover: Option<Hover>,
+    /// Pixel ratio last passed to [`resize`](Self::resize).
+    pixel_ratio: f32,
+    /// Texture owned by [`render_to_texture`](Self::render_to_texture).
+    texture_target: Option<TextureTarget>,
+    /// Images placed with [`place_image`](Self::place_image).
+    images: ImageLayer,
+    /// Font last passed to [`set_font`](Self::set_font).
+    #[cfg(feature = "font")]
+    font: Option<AtlasBuilder>,
+    /// Family of the atlas rasterized by [`set_font`](Self::set_font) while
+    /// it is on the grid, which ligatures are shaped with.
+    #[cfg(feature = "font")]
+    font_family: Option<Rc<FontFamily>>,
+    /// Ligatures of the runs shaped with `font_family`, by style and text.
+    #[cfg(feature = "font")]
+    shaped_runs: HashMap<(FontStyle, Vec<char>), Vec<Range<usize>>>,
+    /// Whether ligatures are drawn, see [`set_ligatures`](Self::set_ligatures).
+    #[cfg(feature = "font")]
+    ligatures: bool,
+}
+
+/// A link under the mouse pointer, underlined while hovered.
+struct Hover {
+    target: HoverTarget,
+    /// Cells underlined by the hover, restored when the pointer leaves.
+    underlined: Vec<Position>,
+}
+
+/// Something that reacts to hovering and ctrl+clicking.
+#[derive(PartialEq)]
+enum HoverTarget {
+    /// An explicit or OSC 8 hyperlink.
+    Hyperlink(Rc<str>),
+    /// Text recognized by the [`LinkDetector`].
+    Match(DetectedMatch),
 }
 
 impl BeamtermBackend {
     /// Creates a new [`BeamtermBackend`].
     pub fn new(grid: TerminalGrid, gl: Rc<glow::Context>) -> Self {
+        let (cols, rows) = grid.terminal_size();
         Self {
-            grid,
+            grid: OwnedGrid(Some(grid)),
             gl,
+            buffer: Buffer::empty(Rect::new(0, 0, cols, rows)),
             cursor_position: None,
+            cursor_visible: true,
+            clipboard: Box::new(MemoryClipboard::new()),
+            events: VecDeque::new(),
+            reported_glyphs: HashSet::new(),
+            hyperlinks: Hyperlinks::default(),
+            link_detector: None,
+            pointer_position: None,
+            hover: None,
+            pixel_ratio: 1.0,
+            texture_target: None,
+            images: ImageLayer::default(),
+            #[cfg(feature = "font")]
+            font: None,
+            #[cfg(feature = "font")]
+            font_family: None,
+            #[cfg(feature = "font")]
+            shaped_runs: HashMap::new(),
+            #[cfg(feature = "font")]
+            ligatures: false,
         }
     }
 
@@ -41,6 +142,704 @@ impl BeamtermBackend {
     pub fn grid_mut(&mut self) -> &mut TerminalGrid {
         &mut self.grid
     }
+
+    /// Returns the shadow buffer holding the content currently on the grid.
+    ///
+    /// Hyperlinks embedded as OSC 8 escape sequences appear as their plain text.
+    pub fn buffer(&self) -> &Buffer {
+        &self.buffer
+    }
+
+    /// Returns the visible content as plain text, one line per row.
+    pub fn to_plain_text(&self) -> String {
+        export::to_plain_text(&self.buffer)
+    }
+
+    /// Returns the visible content as text with truecolor ANSI escape sequences.
+    pub fn to_ansi(&self) -> String {
+        export::to_ansi(&self.buffer)
+    }
+
+    /// Returns the visible content as a standalone HTML `<pre>` element with inline styles.
+    pub fn to_html(&self) -> String {
+        export::to_html(&self.buffer)
+    }
+
+    /// Renders the grid offscreen and reads back the pixels.
+    ///
+    /// The host's GL state is restored as in [`render_to`](Self::render_to).
+    pub fn capture_frame(&self) -> Result<CapturedFrame, Error> {
+        capture::capture(&self.gl, &self.grid, &self.images)
+    }
+
+    /// Draws the grid and the images placed on it into the bound
+    /// framebuffer, as [`TerminalGrid::render`] draws the grid alone.
+    ///
+    /// The host sets the viewport to the canvas size beforehand.
+    pub fn render(&self, gl_state: &mut GlState) -> Result<(), Error> {
+        let mut ctx = RenderContext {
+            gl: &self.gl,
+            state: gl_state,
+        };
+        self.grid.prepare(&mut ctx)?;
+        self.grid.draw(&mut ctx);
+        self.grid.cleanup(&mut ctx);
+        self.images.draw(&mut ctx, &self.grid);
+        Ok(())
+    }
+
+    /// Draws the grid into a framebuffer or sub-rectangle of a host
+    /// application, such as a panel of an egui window or a game HUD.
+    ///
+    /// The host's framebuffer binding, viewport, scissor, blend, depth and
+    /// stencil state, program, vertex array and texture bindings are restored
+    /// afterwards.
+    pub fn render_to(&self, target: &RenderTarget) -> Result<(), Error> {
+        target::render_to(&self.gl, &self.grid, &self.images, target)
+    }
+
+    /// Renders the grid into a texture of `size` pixels owned by the backend
+    /// and returns it, for compositing or mapping onto 3D surfaces.
+    ///
+    /// The grid is resized to fill the texture, keeping the pixel ratio last
+    /// passed to [`resize`](Self::resize) (1.0 by default); the texture is
+    /// reallocated whenever the size changes, invalidating the previous
+    /// handle. Rows are stored bottom row first, as usual for GL textures.
+    /// The host's GL state is restored as in [`render_to`](Self::render_to).
+    pub fn render_to_texture(&mut self, size: (i32, i32)) -> Result<glow::Texture, Error> {
+        if size.0 <= 0 || size.1 <= 0 {
+            return Err(Error::Other("cannot render to an empty texture".to_string()));
+        }
+
+        if self.grid.canvas_size() != size {
+            self.resize(size, self.pixel_ratio)?;
+        }
+        if self.texture_target.as_ref().map(TextureTarget::size) != Some(size) {
+            if let Some(stale) = self.texture_target.take() {
+                stale.delete(&self.gl);
+            }
+            self.texture_target = Some(TextureTarget::new(&self.gl, size)?);
+        }
+
+        let texture_target = self.texture_target.as_ref().expect("allocated above");
+        target::render_to(
+            &self.gl,
+            &self.grid,
+            &self.images,
+            &texture_target.render_target(),
+        )?;
+        Ok(texture_target.texture())
+    }
+
+    /// Uploads the cells of `screen` changed since the last call, through the
+    /// same path as [`Backend::draw`], and moves the cursor to the screen's.
+    pub fn draw_vt(&mut self, screen: &mut VtScreen) -> Result<(), Error> {
+        let damaged = screen.take_damage();
+        let buffer = screen.buffer();
+        self.draw(damaged.iter().map(|&p| (p.x, p.y, &buffer[p])))?;
+
+        match screen.cursor() {
+            Some(position) => self.set_cursor_position(position),
+            None => self.hide_cursor(),
+        }
+    }
+
+    /// Resizes the grid and the shadow buffer to fit the new canvas dimensions.
+    pub fn resize(&mut self, canvas_size: (i32, i32), pixel_ratio: f32) -> Result<(), Error> {
+        // atlases rasterized at runtime follow the pixel ratio
+        self.grid
+            .atlas_mut()
+            .update_pixel_ratio(&self.gl, pixel_ratio)?;
+        self.grid.resize(&self.gl, canvas_size, pixel_ratio)?;
+        self.pixel_ratio = pixel_ratio;
+        self.sync_buffer_size();
+        Ok(())
+    }
+
+    /// Replaces the font atlas, for instance with one of another size.
+    ///
+    /// The glyphs on the grid are translated to the new atlas, the grid is
+    /// resized to fit the new cell size into the same canvas and the content
+    /// is uploaded again; ratatui picks up the new terminal size on its next
+    /// draw.
+    pub fn replace_atlas(&mut self, atlas: impl Into<FontAtlas>) -> Result<(), Error> {
+        #[cfg(feature = "font")]
+        {
+            self.font_family = None;
+            self.shaped_runs.clear();
+        }
+        self.grid.replace_atlas(&self.gl, atlas.into());
+        self.upload_buffer()
+    }
+
+    /// Rasterizes `font` at the pixel ratio last passed to
+    /// [`resize`](Self::resize) and switches to it, as
+    /// [`replace_atlas`](Self::replace_atlas) does.
+    #[cfg(feature = "font")]
+    pub fn set_font(&mut self, font: AtlasBuilder) -> Result<(), Error> {
+        let atlas = font.clone().with_pixel_ratio(self.pixel_ratio).load(&self.gl)?;
+        self.grid.replace_atlas(&self.gl, atlas.into());
+        let family = font.family().clone();
+        if !self
+            .font_family
+            .as_ref()
+            .is_some_and(|current| Rc::ptr_eq(current, &family))
+        {
+            self.shaped_runs.clear();
+        }
+        self.font_family = Some(family);
+        self.font = Some(font);
+        self.upload_buffer()
+    }
+
+    /// Rasterizes the font last passed to [`set_font`](Self::set_font) at
+    /// `font_size` pixels per em and switches to it.
+    #[cfg(feature = "font")]
+    pub fn set_font_size(&mut self, font_size: f32) -> Result<(), Error> {
+        let font = self
+            .font
+            .clone()
+            .ok_or_else(|| Error::Font("no font to resize, see set_font".to_string()))?;
+        self.set_font(font.with_font_size(font_size))
+    }
+
+    /// Returns the size of the font last passed to [`set_font`](Self::set_font).
+    #[cfg(feature = "font")]
+    pub fn font_size(&self) -> Option<f32> {
+        self.font.as_ref().map(AtlasBuilder::font_size)
+    }
+
+    /// Draws the ligatures of the font set with [`set_font`](Self::set_font),
+    /// such as `->` or `!=` in programming fonts, as single glyphs spanning
+    /// their cells. Off by default.
+    ///
+    /// Runs of adjacent single-width characters of the same style are
+    /// shaped with the `calt`, `liga` and `clig` features of the font; a
+    /// style change or the cell of the cursor splits a run, falling back to
+    /// drawing the characters one by one. Each cell of a ligature draws its
+    /// slice of it and copies as its own character. Atlases passed to
+    /// [`replace_atlas`](Self::replace_atlas) draw no ligatures.
+    #[cfg(feature = "font")]
+    pub fn set_ligatures(&mut self, enabled: bool) -> Result<(), Error> {
+        self.ligatures = enabled;
+        self.upload_buffer()
+    }
+
+    /// Recreates all GPU resources after a context loss and re-uploads the
+    /// content of the shadow buffer.
+    pub fn recreate_resources(&mut self, glsl_version: &GlslVersion) -> Result<(), Error> {
+        // the texture died with the context
+        self.texture_target = None;
+        self.grid.recreate_resources(&self.gl, glsl_version)?;
+        self.grid.recreate_atlas_texture(&self.gl)?;
+        self.images.recreate(&self.gl, glsl_version)?;
+        self.upload_buffer()
+    }
+
+    /// Draws `image` into the cells of `area`, scaled by `fit`, over the
+    /// grid in the same pass, in place of the sixel or kitty graphics of a
+    /// terminal.
+    ///
+    /// The cells are cleared, so the background shows through transparent
+    /// pixels; render an [`ImagePlaceholder`](crate::ImagePlaceholder) into
+    /// the area to keep ratatui from drawing over them. Later images are
+    /// drawn over earlier ones, and follow changes of the cell size. Images
+    /// stay until removed with [`remove_image`](Self::remove_image).
+    pub fn place_image(
+        &mut self,
+        area: Rect,
+        image: InlineImage,
+        fit: ImageFit,
+    ) -> Result<ImageId, Error> {
+        let id = self.images.place(&self.gl, image, area, fit)?;
+        self.clear_cells(area)?;
+        Ok(id)
+    }
+
+    /// Moves image `id` into the cells of `area`, scaled by `fit`, clearing
+    /// them as [`place_image`](Self::place_image) does.
+    ///
+    /// Returns `false` for unknown images.
+    pub fn move_image(&mut self, id: ImageId, area: Rect, fit: ImageFit) -> Result<bool, Error> {
+        if !self.images.move_to(id, area, fit) {
+            return Ok(false);
+        }
+        self.clear_cells(area)?;
+        Ok(true)
+    }
+
+    /// Removes image `id` and releases its texture.
+    ///
+    /// Returns `false` for unknown images.
+    pub fn remove_image(&mut self, id: ImageId) -> bool {
+        self.images.remove(&self.gl, id)
+    }
+
+    /// Resets the cells of the shadow buffer in `area` and uploads them.
+    fn clear_cells(&mut self, area: Rect) -> Result<(), Error> {
+        self.unhighlight_hover();
+        self.sync_buffer_size();
+
+        let area = area.intersection(self.buffer.area);
+        self.hyperlinks.remove(area);
+        for position in area.positions() {
+            self.buffer[position].reset();
+        }
+        self.upload_cells(area.positions().collect())?;
+
+        if let Some(position) = self.pointer_position {
+            self.mouse_moved(position);
+        }
+        Ok(())
+    }
+
+    /// Re-uploads the entire shadow buffer to the grid.
+    fn upload_buffer(&mut self) -> Result<(), Error> {
+        self.unhighlight_hover();
+        self.sync_buffer_size();
+
+        let positions = self.buffer.area.positions().collect();
+        self.upload_cells(positions)?;
+
+        if let Some(position) = self.pointer_position {
+            self.mouse_moved(position);
+        }
+
+        Ok(())
+    }
+
+    /// Uploads the cells of the shadow buffer at `positions` to the grid.
+    fn upload_cells(&mut self, mut positions: Vec<Position>) -> Result<(), Error> {
+        let symbols = self.shape_rows(&mut positions);
+        let buffer = &self.buffer;
+        let cells = positions.iter().map(|&position| {
+            let cell = &buffer[position];
+            let symbol = symbols
+                .get(&position)
+                .map_or(cell.symbol(), CompactString::as_str);
+            (position.x, position.y, cell_data_with_symbol(cell, symbol))
+        });
+        self.grid.update_cells_by_position(cells)?;
+        self.report_missing_glyphs();
+        Ok(())
+    }
+
+    /// Extends `positions` to whole rows if ligatures are drawn, and returns
+    /// the symbols of the cells of ligatures in those rows.
+    #[cfg(feature = "font")]
+    fn shape_rows(&mut self, positions: &mut Vec<Position>) -> HashMap<Position, CompactString> {
+        let mut symbols = HashMap::new();
+        let Some(family) = self.font_family.clone().filter(|_| self.ligatures) else {
+            return symbols;
+        };
+
+        // a changed cell may join or split ligatures anywhere in its row
+        let mut rows: Vec<u16> = positions.iter().map(|position| position.y).collect();
+        rows.sort_unstable();
+        rows.dedup();
+        let width = self.buffer.area.width;
+        let cursor = self.visible_cursor();
+        *positions = rows
+            .iter()
+            .flat_map(|&y| (0..width).map(move |x| Position::new(x, y)))
+            .collect();
+
+        for y in rows {
+            let cells: Vec<&Cell> = (0..width).map(|x| &self.buffer[(x, y)]).collect();
+            let cursor = cursor
+                .filter(|cursor| cursor.y == y)
+                .map(|cursor| cursor.x as usize);
+            for run in ligature_runs(&cells, cursor) {
+                let text: Vec<char> = cells[run.clone()]
+                    .iter()
+                    .filter_map(|cell| cell.symbol().chars().next())
+                    .collect();
+                let style = font_style(cells[run.start].modifier);
+                // shaping parses the face, so runs that redraw unchanged
+                // are looked up instead
+                if self.shaped_runs.len() >= SHAPED_RUNS_LIMIT {
+                    self.shaped_runs.clear();
+                }
+                let spans = self
+                    .shaped_runs
+                    .entry((style, text.clone()))
+                    .or_insert_with(|| family.ligatures(style, &text));
+                for span in spans.iter().cloned() {
+                    let ligature: String = text[span.clone()].iter().collect();
+                    for (column, x) in span.map(|i| run.start + i).enumerate() {
+                        let symbol = column_key(&ligature, column);
+                        symbols.insert(Position::new(x as u16, y), symbol);
+                    }
+                }
+            }
+        }
+        symbols
+    }
+
+    #[cfg(not(feature = "font"))]
+    fn shape_rows(&mut self, _positions: &mut Vec<Position>) -> HashMap<Position, CompactString> {
+        HashMap::new()
+    }
+
+    /// Returns the position the cursor is drawn at, if it is visible.
+    fn visible_cursor(&self) -> Option<Position> {
+        self.cursor_position.filter(|_| self.cursor_visible)
+    }
+
+    /// Uploads the cells of the cursor again after it was drawn at
+    /// `previous`, splitting the ligatures it is on.
+    fn redraw_cursor(&mut self, previous: Option<Position>) -> Result<(), Error> {
+        if previous == self.visible_cursor() {
+            return Ok(());
+        }
+
+        self.unhighlight_hover();
+        self.sync_buffer_size();
+        let area = self.buffer.area;
+        let positions = previous
+            .into_iter()
+            .chain(self.visible_cursor())
+            .filter(|&position| area.contains(position))
+            .collect();
+        self.upload_cells(positions)?;
+        if let Some(position) = self.pointer_position {
+            self.mouse_moved(position);
+        }
+        Ok(())
+    }
+
+    /// Matches the shadow buffer to the grid size, keeping overlapping content.
+    ///
+    /// The grid may have been resized directly through [`grid_mut`](Self::grid_mut).
+    fn sync_buffer_size(&mut self) {
+        let (cols, rows) = self.grid.terminal_size();
+        let area = Rect::new(0, 0, cols, rows);
+        if self.buffer.area == area {
+            return;
+        }
+
+        let mut resized = Buffer::empty(area);
+        for position in area.intersection(self.buffer.area).positions() {
+            resized[position] = self.buffer[position].clone();
+        }
+        self.buffer = resized;
+    }
+
+    /// Replaces the clipboard used for copy and paste.
+    ///
+    /// Defaults to a [`MemoryClipboard`].
+    pub fn set_clipboard(&mut self, clipboard: impl Clipboard + 'static) {
+        self.clipboard = Box::new(clipboard);
+    }
+
+    /// Returns a mutable reference to the clipboard.
+    pub fn clipboard_mut(&mut self) -> &mut dyn Clipboard {
+        self.clipboard.as_mut()
+    }
+
+    /// Starts a selection at the given cell, replacing the active one.
+    ///
+    /// Selected cells are drawn with inverted colors from the next flush.
+    pub fn start_selection(&mut self, position: Position, mode: SelectionMode) {
+        let start = (position.x, position.y);
+        let query = select(mode).start(start).end(start);
+        self.grid.selection_tracker().set_query(query);
+        self.refresh_selection();
+    }
+
+    /// Moves the end of the active selection to the given cell.
+    pub fn extend_selection(&mut self, position: Position) {
+        self.grid
+            .selection_tracker()
+            .update_selection_end((position.x, position.y));
+        self.refresh_selection();
+    }
+
+    /// Completes the active selection; it is cleared once the selected
+    /// content changes.
+    ///
+    /// Returns `false` if there is no active selection.
+    pub fn end_selection(&mut self) -> bool {
+        let tracker = self.grid.selection_tracker();
+        let Some(query) = tracker.get_query() else {
+            return false;
+        };
+
+        tracker.set_content_hash(self.grid.hash_cells(query));
+        true
+    }
+
+    /// Removes the active selection.
+    pub fn clear_selection(&mut self) {
+        self.grid.selection_tracker().clear();
+        self.refresh_selection();
+    }
+
+    /// Schedules a flush of the cell buffer, which redraws the selection.
+    ///
+    /// The grid applies the selection colors only while flushing cells to
+    /// the GPU, and flushes only after a cell update; an empty update marks
+    /// the buffer pending without changing any cell.
+    fn refresh_selection(&mut self) {
+        // with no cells to resolve, the update has nothing to fail on
+        let _ = self.grid.update_cells_by_index(iter::empty());
+    }
+
+    /// Copies the text of the active grid selection to the clipboard.
+    ///
+    /// Returns `false` if there is no active selection.
+    pub fn copy_selection(&mut self) -> Result<bool, Error> {
+        let Some(query) = self.grid.selection_tracker().get_query() else {
+            return Ok(false);
+        };
+
+        let text = self.grid.get_text(query);
+        self.clipboard.set_text(&text)?;
+        Ok(true)
+    }
+
+    /// Queues the clipboard contents as an [`Event::Paste`].
+    ///
+    /// Nothing is queued if the clipboard is empty.
+    pub fn paste(&mut self) -> Result<(), Error> {
+        if let Some(event) = paste_event(self.clipboard.as_mut())? {
+            self.events.push_back(event);
+        }
+        Ok(())
+    }
+
+    /// Returns the next pending [`Event`], if any.
+    pub fn poll_event(&mut self) -> Option<Event> {
+        self.events.pop_front()
+    }
+
+    /// Returns the symbols drawn since the last
+    /// [`clear_missing_glyphs`](Self::clear_missing_glyphs) that the font
+    /// atlas has no glyph for, in sorted order.
+    ///
+    /// Missing symbols are shown as the grid's fallback glyph, a space
+    /// unless changed with [`TerminalGrid::set_fallback_glyph`].
+    pub fn missing_glyphs(&self) -> Vec<String> {
+        let mut missing: Vec<String> = self
+            .grid
+            .atlas()
+            .glyph_tracker()
+            .missing_glyphs()
+            .into_iter()
+            .map(String::from)
+            .collect();
+        missing.sort_unstable();
+        missing
+    }
+
+    /// Forgets the missing glyphs, so they are reported again when drawn.
+    pub fn clear_missing_glyphs(&mut self) {
+        self.grid.atlas().glyph_tracker().clear();
+        self.reported_glyphs.clear();
+    }
+
+    /// Queues an [`Event::MissingGlyph`] for each missing glyph not yet reported.
+    fn report_missing_glyphs(&mut self) {
+        let tracker = self.grid.atlas().glyph_tracker();
+        if tracker.len() == self.reported_glyphs.len() {
+            return;
+        }
+
+        let mut missing: Vec<CompactString> = tracker
+            .missing_glyphs()
+            .into_iter()
+            .filter(|symbol| !self.reported_glyphs.contains(symbol))
+            .collect();
+        missing.sort_unstable();
+        for symbol in missing {
+            self.events
+                .push_back(Event::MissingGlyph(symbol.to_string()));
+            self.reported_glyphs.insert(symbol);
+        }
+    }
+
+    /// Attaches a hyperlink to all cells in `area`.
+    ///
+    /// Cells carrying OSC 8 hyperlinks in their symbols are registered
+    /// automatically during [`draw`](Backend::draw); this is the explicit
+    /// alternative for widgets that don't emit escape sequences. A link is
+    /// dropped from any cell whose content is redrawn.
+    pub fn set_hyperlink(&mut self, area: Rect, uri: &str) {
+        self.hyperlinks.insert(area, uri);
+    }
+
+    /// Removes any hyperlinks from the cells in `area`.
+    pub fn clear_hyperlinks(&mut self, area: Rect) {
+        self.hyperlinks.remove(area);
+    }
+
+    /// Returns the hyperlink URI at the given cell, if any.
+    pub fn hyperlink_at(&self, position: Position) -> Option<&str> {
+        self.hyperlinks.get(position).map(AsRef::as_ref)
+    }
+
+    /// Installs a detector for URLs, file locations and custom patterns in the
+    /// rendered text, or removes it with `None`.
+    ///
+    /// Detected matches behave like hyperlinks: they are underlined on hover and
+    /// ctrl+clicking one queues an [`Event::MatchActivated`].
+    pub fn set_link_detector(&mut self, detector: Option<LinkDetector>) {
+        self.unhighlight_hover();
+        self.link_detector = detector;
+    }
+
+    /// Converts a physical pixel position on the canvas into a cell position.
+    ///
+    /// Returns `None` if the position lies outside the grid.
+    pub fn cell_at_pixel(&self, x: f64, y: f64) -> Option<Position> {
+        let (cell_w, cell_h) = self.grid.cell_size();
+        let (cols, rows) = self.grid.terminal_size();
+        if x < 0.0 || y < 0.0 {
+            return None;
+        }
+
+        let col = (x / cell_w as f64) as u16;
+        let row = (y / cell_h as f64) as u16;
+        (col < cols && row < rows).then_some(Position::new(col, row))
+    }
+
+    /// Notifies the backend that the mouse pointer moved over the given cell.
+    ///
+    /// Hovered links are underlined; the change is uploaded on the next flush.
+    pub fn mouse_moved(&mut self, position: Position) {
+        self.pointer_position = Some(position);
+
+        let target = self.hover_target_at(position);
+        if target.as_ref() == self.hover.as_ref().map(|hover| &hover.target) {
+            return;
+        }
+
+        self.unhighlight_hover();
+        if let Some(target) = target {
+            self.highlight(target);
+        }
+    }
+
+    /// Notifies the backend that the mouse pointer left the canvas.
+    pub fn mouse_left(&mut self) {
+        self.pointer_position = None;
+        self.unhighlight_hover();
+    }
+
+    /// Notifies the backend of a mouse button press over the given cell.
+    ///
+    /// Ctrl+clicking a hyperlink queues an [`Event::LinkActivated`], and
+    /// ctrl+clicking a detected match queues an [`Event::MatchActivated`].
+    pub fn mouse_pressed(&mut self, position: Position, button: MouseButton, modifiers: Modifiers) {
+        if button != MouseButton::Left || !modifiers.ctrl {
+            return;
+        }
+
+        let event = match self.hover_target_at(position) {
+            Some(HoverTarget::Hyperlink(uri)) => Event::LinkActivated {
+                uri: uri.to_string(),
+                position,
+            },
+            Some(HoverTarget::Match(found)) => Event::MatchActivated(found),
+            None => return,
+        };
+
+        self.events.push_back(event);
+    }
+
+    /// Returns the hyperlink or detected match at the given cell.
+    fn hover_target_at(&self, position: Position) -> Option<HoverTarget> {
+        if let Some(uri) = self.hyperlinks.get(position) {
+            return Some(HoverTarget::Hyperlink(uri.clone()));
+        }
+
+        let detector = self.link_detector.as_ref()?;
+        let row = self.row_symbols(position.y);
+        detector
+            .find_at(position.y, &row, position.x)
+            .map(HoverTarget::Match)
+    }
+
+    /// Returns the symbol of each cell in the given row.
+    fn row_symbols(&self, row: u16) -> Vec<&str> {
+        let area = self.buffer.area;
+        if row >= area.height {
+            return Vec::new();
+        }
+
+        (0..area.width)
+            .map(|col| self.buffer[(col, row)].symbol())
+            .collect()
+    }
+
+    /// Underlines all cells belonging to `target`.
+    fn highlight(&mut self, target: HoverTarget) {
+        const UNDERLINE: u16 = GlyphEffect::Underline as u16;
+
+        let cells: Vec<Position> = match &target {
+            HoverTarget::Hyperlink(uri) => self.hyperlinks.cells_with_uri(uri).collect(),
+            HoverTarget::Match(found) => found.area.positions().collect(),
+        };
+
+        let mut underlined = Vec::new();
+        for position in cells {
+            if let Some(cell) = self.grid.cell_data_mut(position.x, position.y) {
+                let style = cell.get_style();
+                if style & UNDERLINE == 0 {
+                    cell.style(style | UNDERLINE);
+                    underlined.push(position);
+                }
+            }
+        }
+
+        self.hover = Some(Hover { target, underlined });
+    }
+
+    /// Restores the cells underlined by [`highlight`](Self::highlight).
+    fn unhighlight_hover(&mut self) {
+        const UNDERLINE: u16 = GlyphEffect::Underline as u16;
+
+        let Some(hover) = self.hover.take() else {
+            return;
+        };
+
+        for position in hover.underlined {
+            if let Some(cell) = self.grid.cell_data_mut(position.x, position.y) {
+                let style = cell.get_style();
+                cell.style(style & !UNDERLINE);
+            }
+        }
+    }
+}
+
+impl Drop for BeamtermBackend {
+    fn drop(&mut self) {
+        if let Some(texture_target) = self.texture_target.take() {
+            texture_target.delete(&self.gl);
+        }
+        self.images.delete(&self.gl);
+        if let Some(grid) = self.grid.0.take() {
+            grid.delete(&self.gl);
+        }
+    }
+}
+
+/// The grid of a backend, taken out only when the backend drops, as
+/// [`TerminalGrid::delete`] consumes it.
+struct OwnedGrid(Option<TerminalGrid>);
+
+impl Deref for OwnedGrid {
+    type Target = TerminalGrid;
+
+    fn deref(&self) -> &TerminalGrid {
+        self.0.as_ref().expect("grid is taken only on drop")
+    }
+}
+
+impl DerefMut for OwnedGrid {
+    fn deref_mut(&mut self) -> &mut TerminalGrid {
+        self.0.as_mut().expect("grid is taken only on drop")
+    }
 }
 
 impl Backend for BeamtermBackend {
@@ -50,8 +849,43 @@ impl Backend for BeamtermBackend {
     where
         I: Iterator<Item = (u16, u16, &'a Cell)>,
     {
-        let cells = content.map(|(x, y, cell)| (x, y, cell_data(cell)));
-        self.grid.update_cells_by_position(cells)?;
+        // drop the hover underline while cells are replaced; reapplied below
+        self.unhighlight_hover();
+        self.sync_buffer_size();
+
+        let mut updated = Vec::new();
+        let mut link_span: Option<Rect> = None;
+        for (x, y, cell) in content {
+            let position = Position::new(x, y);
+            if let Some((uri, cells)) = expand_osc8(cell) {
+                // spread the link text over the cells following this one
+                let span = Rect::new(x, y, cells.len() as u16, 1);
+                for (position, cell) in span.positions().zip(cells) {
+                    if let Some(shadow) = self.buffer.cell_mut(position) {
+                        *shadow = cell;
+                        updated.push(position);
+                    }
+                }
+
+                // the previous link here may have covered more cells
+                self.hyperlinks.remove_link_at(position);
+                self.hyperlinks.insert(span, uri);
+                link_span = Some(span);
+            } else if link_span.is_some_and(|span| span.contains(position)) {
+                // already written as part of the preceding hyperlink text
+            } else if let Some(shadow) = self.buffer.cell_mut(position) {
+                self.hyperlinks.remove(Rect::new(x, y, 1, 1));
+                *shadow = cell.clone();
+                updated.push(position);
+            }
+        }
+
+        self.upload_cells(updated)?;
+
+        if let Some(position) = self.pointer_position {
+            self.mouse_moved(position);
+        }
+
         Ok(())
     }
 
@@ -61,12 +895,16 @@ impl Backend for BeamtermBackend {
     }
 
     fn hide_cursor(&mut self) -> Result<(), Self::Error> {
+        let previous = self.visible_cursor();
         self.cursor_position = None;
-        Ok(())
+        self.cursor_visible = false;
+        self.redraw_cursor(previous)
     }
 
     fn show_cursor(&mut self) -> Result<(), Self::Error> {
-        Ok(())
+        let previous = self.visible_cursor();
+        self.cursor_visible = true;
+        self.redraw_cursor(previous)
     }
 
     fn get_cursor_position(&mut self) -> Result<Position, Self::Error> {
@@ -77,8 +915,9 @@ impl Backend for BeamtermBackend {
     }
 
     fn set_cursor_position<P: Into<Position>>(&mut self, position: P) -> Result<(), Self::Error> {
+        let previous = self.visible_cursor();
         self.cursor_position = Some(position.into());
-        Ok(())
+        self.redraw_cursor(previous)
     }
 
     fn clear(&mut self) -> Result<(), Self::Error> {
@@ -89,6 +928,9 @@ impl Backend for BeamtermBackend {
             .take(cell_count);
 
         self.grid.update_cells(cells)?;
+        self.buffer.reset();
+        self.hyperlinks.clear();
+        self.hover = None;
         Ok(())
     }
 
@@ -127,10 +969,49 @@ fn resolve_fg_bg_colors(cell: &Cell) -> (u32, u32) {
     (fg, bg)
 }
 
-/// Converts a ratatui [`Cell`] into a beamterm [`CellData`].
-fn cell_data(cell: &Cell) -> CellData<'_> {
+/// Converts a ratatui [`Cell`] into a beamterm [`CellData`] drawing
+/// `symbol`, such as a column of a ligature, in place of its own.
+fn cell_data_with_symbol<'a>(cell: &Cell, symbol: &'a str) -> CellData<'a> {
     let (fg, bg) = resolve_fg_bg_colors(cell);
-    CellData::new_with_style_bits(cell.symbol(), into_glyph_bits(cell.modifier), fg, bg)
+    CellData::new_with_style_bits(symbol, into_glyph_bits(cell.modifier), fg, bg)
+}
+
+/// Returns the runs of two or more cells that may be drawn as ligatures:
+/// adjacent single-width characters of the same style, except the cell of
+/// the cursor.
+#[cfg(feature = "font")]
+fn ligature_runs(cells: &[&Cell], cursor: Option<usize>) -> Vec<Range<usize>> {
+    let shapeable = |i: usize| {
+        let mut chars = cells[i].symbol().chars();
+        let single =
+            matches!((chars.next(), chars.next()), (Some(c), None) if c.width() == Some(1));
+        single && Some(i) != cursor
+    };
+
+    let mut runs: Vec<Range<usize>> = Vec::new();
+    for i in (0..cells.len()).filter(|&i| shapeable(i)) {
+        match runs.last_mut() {
+            Some(run) if run.end == i && cells[run.start].style() == cells[i].style() => {
+                run.end = i + 1;
+            }
+            _ => runs.push(i..i + 1),
+        }
+    }
+    runs.retain(|run| run.len() > 1);
+    runs
+}
+
+/// Returns the face style of cells with `modifier`.
+#[cfg(feature = "font")]
+fn font_style(modifier: Modifier) -> FontStyle {
+    let bold = modifier.contains(Modifier::BOLD);
+    let italic = modifier.contains(Modifier::ITALIC);
+    match (bold, italic) {
+        (false, false) => FontStyle::Normal,
+        (true, false) => FontStyle::Bold,
+        (false, true) => FontStyle::Italic,
+        (true, true) => FontStyle::BoldItalic,
+    }
 }
 
 /// Extracts glyph styling bits from cell modifiers.
@@ -244,12 +1125,33 @@ mod tests {
         assert_eq!(bg, 0xAABBCC);
     }
 
+    #[cfg(feature = "font")]
+    #[test]
+    fn ligature_runs_split_at_style_changes_and_the_cursor() {
+        let cells: Vec<Cell> = ["-", ">", "=", ">", "e\u{301}", "!", "=", "中", "="]
+            .into_iter()
+            .enumerate()
+            .map(|(i, symbol)| {
+                let mut cell = Cell::new(symbol);
+                if (2..4).contains(&i) {
+                    cell.set_style(Style::default().add_modifier(Modifier::BOLD));
+                }
+                cell
+            })
+            .collect();
+        let cells: Vec<&Cell> = cells.iter().collect();
+
+        assert_eq!(ligature_runs(&cells, None), vec![0..2, 2..4, 5..7]);
+        assert_eq!(ligature_runs(&cells, Some(6)), vec![0..2, 2..4]);
+        assert_eq!(ligature_runs(&cells, Some(1)), vec![2..4, 5..7]);
+    }
+
     #[test]
     fn cell_data_preserves_symbol() {
         let mut cell = Cell::default();
         cell.set_symbol("A");
         cell.set_style(Style::default().fg(Color::White).bg(Color::Black));
-        let data = cell_data(&cell);
+        let data = cell_data_with_symbol(&cell, cell.symbol());
         // CellData is opaque, but if it constructs without panicking,
         // the style_bits assertion inside new_with_style_bits passed.
         let _ = data;
@@ -266,6 +1168,6 @@ mod tests {
                 .add_modifier(Modifier::BOLD | Modifier::ITALIC),
         );
         // Must not trigger the debug_assert in CellData::new_with_style_bits
-        let _ = cell_data(&cell);
+        let _ = cell_data_with_symbol(&cell, cell.symbol());
     }
 }
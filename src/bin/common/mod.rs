@@ -0,0 +1,5 @@
+//! Window setup shared by the bundled binaries.
+
+mod window;
+
+pub use window::GlWindow;
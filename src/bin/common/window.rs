@@ -0,0 +1,137 @@
+use std::num::NonZeroU32;
+
+use glutin::{
+    config::{ConfigTemplateBuilder, GlConfig},
+    context::{
+        ContextApi, ContextAttributesBuilder, NotCurrentGlContext, PossiblyCurrentContext, Version,
+    },
+    display::{GetGlDisplay, GlDisplay},
+    surface::{GlSurface, Surface, SurfaceAttributesBuilder, SwapInterval, WindowSurface},
+};
+use glutin_winit::DisplayBuilder;
+use raw_window_handle::HasWindowHandle;
+use winit::{
+    dpi::{LogicalSize, PhysicalSize},
+    event_loop::ActiveEventLoop,
+    window::{Window, WindowAttributes},
+};
+
+use ratbeam::Error;
+
+/// A winit window with a current OpenGL 3.3 context, for the bundled binaries.
+pub struct GlWindow {
+    window: Window,
+    gl_context: PossiblyCurrentContext,
+    gl_surface: Surface<WindowSurface>,
+}
+
+impl GlWindow {
+    /// Opens a window of `size` logical pixels and makes its GL context current.
+    pub fn new(
+        event_loop: &ActiveEventLoop,
+        title: &str,
+        size: (u32, u32),
+    ) -> Result<(Self, glow::Context), Error> {
+        let window_attrs = WindowAttributes::default()
+            .with_title(title)
+            .with_inner_size(LogicalSize::new(size.0, size.1));
+
+        let config_template = ConfigTemplateBuilder::new().with_alpha_size(8);
+
+        let (window, gl_config) = DisplayBuilder::new()
+            .with_window_attributes(Some(window_attrs))
+            .build(event_loop, config_template, |configs| {
+                configs
+                    .reduce(|accum, config| {
+                        if config.num_samples() > accum.num_samples() {
+                            config
+                        } else {
+                            accum
+                        }
+                    })
+                    .unwrap()
+            })
+            .map_err(|e| window_error("failed to build display", e))?;
+
+        let window = window.ok_or_else(|| Error::Other("failed to create window".to_string()))?;
+        let window_handle = window
+            .window_handle()
+            .map_err(|e| window_error("failed to get window handle", e))?
+            .as_raw();
+        let gl_display = gl_config.display();
+
+        let context_attrs = ContextAttributesBuilder::new()
+            .with_context_api(ContextApi::OpenGl(Some(Version::new(3, 3))))
+            .build(Some(window_handle));
+
+        let not_current_context = unsafe { gl_display.create_context(&gl_config, &context_attrs) }
+            .map_err(|e| window_error("failed to create GL context", e))?;
+
+        let inner = window.inner_size();
+        let surface_attrs = SurfaceAttributesBuilder::<WindowSurface>::new().build(
+            window_handle,
+            non_zero(inner.width),
+            non_zero(inner.height),
+        );
+
+        let gl_surface = unsafe { gl_display.create_window_surface(&gl_config, &surface_attrs) }
+            .map_err(|e| window_error("failed to create GL surface", e))?;
+
+        let gl_context = not_current_context
+            .make_current(&gl_surface)
+            .map_err(|e| window_error("failed to make GL context current", e))?;
+
+        let _ = gl_surface.set_swap_interval(&gl_context, SwapInterval::Wait(non_zero(1)));
+
+        let gl = unsafe {
+            glow::Context::from_loader_function_cstr(|name| gl_display.get_proc_address(name))
+        };
+
+        let win = Self {
+            window,
+            gl_context,
+            gl_surface,
+        };
+        Ok((win, gl))
+    }
+
+    /// Returns the winit window.
+    pub fn window(&self) -> &Window {
+        &self.window
+    }
+
+    /// Returns the size of the drawable area in physical pixels.
+    pub fn physical_size(&self) -> (i32, i32) {
+        let size = self.window.inner_size();
+        (size.width as i32, size.height as i32)
+    }
+
+    /// Returns the ratio of physical to logical pixels.
+    pub fn pixel_ratio(&self) -> f32 {
+        self.window.scale_factor() as f32
+    }
+
+    /// Resizes the GL surface after the window was resized.
+    pub fn resize_surface(&self, new_size: PhysicalSize<u32>) {
+        self.gl_surface.resize(
+            &self.gl_context,
+            non_zero(new_size.width),
+            non_zero(new_size.height),
+        );
+    }
+
+    /// Presents the rendered frame.
+    pub fn swap_buffers(&self) -> Result<(), Error> {
+        self.gl_surface
+            .swap_buffers(&self.gl_context)
+            .map_err(|e| window_error("failed to swap buffers", e))
+    }
+}
+
+fn non_zero(value: u32) -> NonZeroU32 {
+    NonZeroU32::new(value.max(1)).unwrap()
+}
+
+fn window_error(context: &str, e: impl std::fmt::Display) -> Error {
+    Error::Other(format!("{context}: {e}"))
+}
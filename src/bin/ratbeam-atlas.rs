@@ -0,0 +1,225 @@
+//! Builds and inspects font atlases in beamterm's binary format.
+//!
+//! Run with:
+//! ```sh
+//! cargo run --bin ratbeam-atlas -- check font.atlas
+//! cargo run --features font --bin ratbeam-atlas -- build Font.ttf --size 14 -o font.atlas
+//! ```
+//!
+//! `check` lists the glyph ranges and font styles of an atlas, then the
+//! symbols of ratatui's widgets it lacks, which would render as blanks. It
+//! exits with status 1 if any are missing.
+//!
+//! `build` (feature `font`) rasterizes a TTF or OTF font into an atlas
+//! loadable with `FontAtlasData::from_binary`. Options:
+//! - `--size <px>`: the font size in pixels per em, required
+//! - `-o <file>`: the atlas to write, required
+//! - `--glyphs <presets>`: a comma-separated list of `ascii`, `latin1`,
+//!   `box-drawing`, `braille`, `symbols`, `powerline`, `nerd-font` and
+//!   `default`, the latter being Latin-1, symbols, box drawing and braille;
+//!   default `default`
+//! - `--chars <text>`: additional characters to include
+//! - `--bold <file>`, `--italic <file>`, `--bold-italic <file>`: faces for
+//!   the other styles, synthesized from the regular face when omitted
+//! - `--pixel-ratio <ratio>`: rasterize for a HiDPI display, default 1
+
+use std::process::ExitCode;
+
+use beamterm_core::{FontAtlasData, FontStyle};
+use ratbeam::{AtlasCoverage, SymbolGroup};
+
+/// Missing symbols listed per group before eliding the rest.
+const MAX_LISTED: usize = 64;
+
+const USAGE: &str = "usage: ratbeam-atlas check <file.atlas>\n       \
+    ratbeam-atlas build <font> --size <px> -o <file.atlas> [--glyphs <presets>] \
+    [--chars <text>] [--bold <font>] [--italic <font>] [--bold-italic <font>] \
+    [--pixel-ratio <ratio>]";
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let result = match args.first().map(String::as_str) {
+        Some("check") if args.len() == 2 => check(&args[1]),
+        Some("build") => build(&args[1..]),
+        _ => {
+            eprintln!("{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+
+    result.unwrap_or_else(|e| {
+        eprintln!("ratbeam-atlas: {e}");
+        eprintln!("{USAGE}");
+        ExitCode::from(2)
+    })
+}
+
+fn check(path: &str) -> Result<ExitCode, String> {
+    let bytes = std::fs::read(path).map_err(|e| format!("{path}: {e}"))?;
+    let data = FontAtlasData::from_binary(&bytes).map_err(|e| format!("{path}: {e:?}"))?;
+    let coverage = AtlasCoverage::new(&data);
+
+    print_summary(path, &data, &coverage);
+    println!("ranges:");
+    for range in coverage.ranges() {
+        let (start, end) = (*range.start(), *range.end());
+        let count = end as u32 - start as u32 + 1;
+        if start == end {
+            println!("  U+{:04X}          {start}", start as u32);
+        } else {
+            println!(
+                "  U+{:04X}-U+{:04X}  {start}..{end} ({count})",
+                start as u32, end as u32
+            );
+        }
+    }
+    let graphemes = coverage.graphemes();
+    if !graphemes.is_empty() {
+        println!("graphemes: {} ({})", graphemes.len(), graphemes.concat());
+    }
+
+    Ok(if print_ratatui_coverage(&coverage) {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    })
+}
+
+#[cfg(feature = "font")]
+fn build(args: &[String]) -> Result<ExitCode, String> {
+    use std::rc::Rc;
+
+    use ratbeam::{AtlasBuilder, FontFamily, GlyphSet};
+
+    let mut args = args.iter();
+    let font = args.next().ok_or("build needs a font file")?;
+    let mut faces: Vec<(FontStyle, &String)> = Vec::new();
+    let mut size = None;
+    let mut output = None;
+    let mut glyphs = GlyphSet::default();
+    let mut chars = String::new();
+    let mut pixel_ratio = 1.0;
+
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| format!("{arg} needs a value"));
+        match arg.as_str() {
+            "--size" => {
+                let value = value()?;
+                size = Some(
+                    value
+                        .parse::<f32>()
+                        .map_err(|_| format!("invalid font size: {value}"))?,
+                );
+            }
+            "-o" | "--output" => output = Some(value()?),
+            "--glyphs" => {
+                glyphs = GlyphSet::ascii();
+                for preset in value()?.split(',') {
+                    glyphs = glyphs.with_set(&glyph_preset(preset.trim())?);
+                }
+            }
+            "--chars" => chars.push_str(value()?),
+            "--bold" => faces.push((FontStyle::Bold, value()?)),
+            "--italic" => faces.push((FontStyle::Italic, value()?)),
+            "--bold-italic" => faces.push((FontStyle::BoldItalic, value()?)),
+            "--pixel-ratio" => {
+                let value = value()?;
+                pixel_ratio = value
+                    .parse()
+                    .map_err(|_| format!("invalid pixel ratio: {value}"))?;
+            }
+            _ => return Err(format!("unknown option: {arg}")),
+        }
+    }
+    let size = size.ok_or("build needs --size")?;
+    let output = output.ok_or("build needs an output file given with -o")?;
+
+    let mut family = FontFamily::load(font).map_err(|e| e.to_string())?;
+    for (style, path) in faces {
+        family = family.with_face(style, path).map_err(|e| e.to_string())?;
+    }
+    let data = AtlasBuilder::new(Rc::new(family), size)
+        .with_glyphs(glyphs.with_chars(&chars))
+        .with_pixel_ratio(pixel_ratio)
+        .build()
+        .map_err(|e| e.to_string())?;
+
+    let bytes = data.to_binary().map_err(|e| format!("{output}: {e:?}"))?;
+    std::fs::write(output, bytes).map_err(|e| format!("{output}: {e}"))?;
+
+    let coverage = AtlasCoverage::new(&data);
+    print_summary(output, &data, &coverage);
+    print_ratatui_coverage(&coverage);
+    Ok(ExitCode::SUCCESS)
+}
+
+#[cfg(not(feature = "font"))]
+fn build(_args: &[String]) -> Result<ExitCode, String> {
+    Err("build needs ratbeam-atlas to be built with the font feature".to_string())
+}
+
+#[cfg(feature = "font")]
+fn glyph_preset(name: &str) -> Result<ratbeam::GlyphSet, String> {
+    use ratbeam::GlyphSet;
+
+    Ok(match name {
+        "ascii" => GlyphSet::ascii(),
+        "latin1" => GlyphSet::latin1(),
+        "box-drawing" => GlyphSet::box_drawing(),
+        "braille" => GlyphSet::braille(),
+        "symbols" => GlyphSet::symbols(),
+        "powerline" => GlyphSet::powerline(),
+        "nerd-font" => GlyphSet::nerd_font(),
+        "default" => GlyphSet::default(),
+        _ => return Err(format!("unknown glyph preset: {name}")),
+    })
+}
+
+/// Prints the font, size and glyph counts of an atlas.
+fn print_summary(path: &str, data: &FontAtlasData, coverage: &AtlasCoverage) {
+    let (width, height) = data.cell_size;
+    println!(
+        "{path}: {} {}px, {} glyphs, cell {}x{}",
+        data.font_name,
+        data.font_size,
+        data.glyphs.len(),
+        width - 2 * FontAtlasData::PADDING,
+        height - 2 * FontAtlasData::PADDING,
+    );
+
+    let styles: Vec<String> = coverage
+        .styles()
+        .map(|(style, count)| format!("{} {count}", style_name(style)))
+        .collect();
+    println!("styles: {}", styles.join(", "));
+}
+
+/// Prints the ratatui symbols the atlas lacks; returns true if none are missing.
+fn print_ratatui_coverage(coverage: &AtlasCoverage) -> bool {
+    println!("ratatui symbols:");
+    let mut complete = true;
+    for group in SymbolGroup::ratatui() {
+        let missing = coverage.missing(&group);
+        let present = group.symbols.len() - missing.len();
+        println!("  {:<11} {present:>3}/{}", group.name, group.symbols.len());
+        if !missing.is_empty() {
+            complete = false;
+            let listed = missing[..missing.len().min(MAX_LISTED)].concat();
+            let elided = missing.len().saturating_sub(MAX_LISTED);
+            match elided {
+                0 => println!("    missing: {listed}"),
+                _ => println!("    missing: {listed} and {elided} more"),
+            }
+        }
+    }
+    complete
+}
+
+fn style_name(style: FontStyle) -> &'static str {
+    match style {
+        FontStyle::Normal => "normal",
+        FontStyle::Bold => "bold",
+        FontStyle::Italic => "italic",
+        FontStyle::BoldItalic => "bold italic",
+    }
+}
@@ -0,0 +1,230 @@
+//! Plays back asciicast v2 recordings, such as those written by
+//! `AsciicastRecorder`, through `BeamtermBackend`.
+//!
+//! Run with:
+//! ```sh
+//! cargo run --features replay --bin ratbeam-replay -- session.cast
+//! ```
+//!
+//! Keys: `space` pause, `←`/`→` seek 5s, `↑`/`↓` speed, `.`/`,` step one
+//! event forward/back, `home` restart, `q`/`esc` quit.
+
+mod common;
+
+use std::rc::Rc;
+use std::time::Instant;
+
+use beamterm_core::{
+    Drawable, FontAtlasData, GlState, GlslVersion, RenderContext, StaticFontAtlas, TerminalGrid,
+};
+use ratatui::{Terminal, layout::Position};
+use ratbeam::{BeamtermBackend, Cast, CastPlayer};
+use winit::{
+    application::ApplicationHandler,
+    dpi::PhysicalSize,
+    event::{ElementState, WindowEvent},
+    event_loop::{ActiveEventLoop, EventLoop},
+    keyboard::{Key, NamedKey},
+    window::WindowId,
+};
+
+use crate::common::GlWindow;
+
+const SEEK_SECONDS: f64 = 5.0;
+const MIN_SPEED: f64 = 0.125;
+const MAX_SPEED: f64 = 16.0;
+
+fn main() {
+    let Some(path) = std::env::args().nth(1) else {
+        eprintln!("usage: ratbeam-replay <file.cast>");
+        std::process::exit(2);
+    };
+
+    let cast = std::fs::read_to_string(&path)
+        .map_err(ratbeam::Error::from)
+        .and_then(|contents| Cast::parse(&contents))
+        .unwrap_or_else(|e| {
+            eprintln!("{path}: {e}");
+            std::process::exit(1);
+        });
+
+    let event_loop = EventLoop::new().expect("failed to create event loop");
+    let mut app = ReplayApp {
+        player: CastPlayer::new(cast),
+        state: None,
+    };
+    event_loop.run_app(&mut app).expect("event loop failed");
+}
+
+struct ReplayApp {
+    player: CastPlayer,
+    state: Option<ReplayState>,
+}
+
+struct ReplayState {
+    /// Dropped before the window, which owns the GL context.
+    terminal: Terminal<BeamtermBackend>,
+    win: GlWindow,
+    gl: Rc<glow::Context>,
+    gl_state: GlState,
+    paused: bool,
+    speed: f64,
+    last_frame: Instant,
+}
+
+impl ApplicationHandler for ReplayApp {
+    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
+        if self.state.is_some() {
+            return;
+        }
+
+        let (win, gl) = GlWindow::new(event_loop, "ratbeam-replay", (1280, 800))
+            .expect("failed to create window");
+        let gl = Rc::new(gl);
+        let gl_state = GlState::new(&gl);
+
+        let atlas = StaticFontAtlas::load(&gl, FontAtlasData::default())
+            .expect("failed to load font atlas");
+        let grid = TerminalGrid::new(
+            &gl,
+            atlas.into(),
+            win.physical_size(),
+            win.pixel_ratio(),
+            &GlslVersion::Gl330,
+        )
+        .expect("failed to create terminal grid");
+
+        // fit the window to the recorded terminal size
+        let (cell_w, cell_h) = grid.cell_size();
+        let cast = self.player.cast();
+        let _ = win.window().request_inner_size(PhysicalSize::new(
+            (cast.width as i32 * cell_w) as u32,
+            (cast.height as i32 * cell_h) as u32,
+        ));
+
+        let backend = BeamtermBackend::new(grid, gl.clone());
+        let terminal = Terminal::new(backend).expect("failed to create terminal");
+
+        self.state = Some(ReplayState {
+            win,
+            gl,
+            gl_state,
+            terminal,
+            paused: false,
+            speed: 1.0,
+            last_frame: Instant::now(),
+        });
+    }
+
+    fn window_event(
+        &mut self,
+        event_loop: &ActiveEventLoop,
+        _window_id: WindowId,
+        event: WindowEvent,
+    ) {
+        let Some(state) = self.state.as_mut() else {
+            return;
+        };
+        let player = &mut self.player;
+
+        match event {
+            WindowEvent::CloseRequested => event_loop.exit(),
+            WindowEvent::KeyboardInput { event, .. } if event.state == ElementState::Pressed => {
+                match event.logical_key.as_ref() {
+                    Key::Named(NamedKey::Escape) | Key::Character("q") => event_loop.exit(),
+                    Key::Named(NamedKey::Space) => state.paused = !state.paused,
+                    Key::Named(NamedKey::ArrowLeft) => player.seek(player.time() - SEEK_SECONDS),
+                    Key::Named(NamedKey::ArrowRight) => player.seek(player.time() + SEEK_SECONDS),
+                    Key::Named(NamedKey::ArrowUp) => {
+                        state.speed = (state.speed * 2.0).min(MAX_SPEED)
+                    }
+                    Key::Named(NamedKey::ArrowDown) => {
+                        state.speed = (state.speed / 2.0).max(MIN_SPEED)
+                    }
+                    Key::Named(NamedKey::Home) => player.seek(0.0),
+                    Key::Character(".") => {
+                        state.paused = true;
+                        player.step_forward();
+                    }
+                    Key::Character(",") => {
+                        state.paused = true;
+                        player.step_backward();
+                    }
+                    _ => {}
+                }
+            }
+            WindowEvent::Resized(new_size) if new_size.width > 0 && new_size.height > 0 => {
+                state.win.resize_surface(new_size);
+                let _ = state.terminal.backend_mut().resize(
+                    (new_size.width as i32, new_size.height as i32),
+                    state.win.pixel_ratio(),
+                );
+            }
+            WindowEvent::RedrawRequested => {
+                let now = Instant::now();
+                if !state.paused && !player.is_finished() {
+                    let elapsed = now.duration_since(state.last_frame).as_secs_f64();
+                    player.advance(elapsed * state.speed);
+                }
+                state.last_frame = now;
+
+                state.win.window().set_title(&status(player, state));
+                render(state, player);
+            }
+            _ => {}
+        }
+    }
+
+    fn about_to_wait(&mut self, _event_loop: &ActiveEventLoop) {
+        if let Some(state) = self.state.as_ref() {
+            state.win.window().request_redraw();
+        }
+    }
+}
+
+fn status(player: &CastPlayer, state: &ReplayState) -> String {
+    let mode = if state.paused { "paused" } else { "playing" };
+    format!(
+        "ratbeam-replay: {:.1}s / {:.1}s, {}x, {mode}",
+        player.time(),
+        player.cast().duration(),
+        state.speed,
+    )
+}
+
+fn render(state: &mut ReplayState, player: &CastPlayer) {
+    state
+        .terminal
+        .draw(|frame| {
+            let screen = player.screen();
+            let buffer = frame.buffer_mut();
+            for position in buffer.area.intersection(screen.area).positions() {
+                buffer[position] = screen[position].clone();
+            }
+
+            if let Some(cursor) = player.cursor() {
+                frame.set_cursor_position(Position::new(cursor.x, cursor.y));
+            }
+        })
+        .expect("failed to draw");
+
+    let (w, h) = state.terminal.backend().grid().canvas_size();
+    state.gl_state.viewport(&state.gl, 0, 0, w, h);
+    state.gl_state.clear_color(&state.gl, 0.0, 0.0, 0.0, 1.0);
+
+    unsafe {
+        use glow::HasContext;
+        state.gl.clear(glow::COLOR_BUFFER_BIT);
+    }
+
+    let mut ctx = RenderContext {
+        gl: &state.gl,
+        state: &mut state.gl_state,
+    };
+    let grid = state.terminal.backend().grid();
+    grid.prepare(&mut ctx).expect("failed to prepare grid");
+    grid.draw(&mut ctx);
+    grid.cleanup(&mut ctx);
+
+    state.win.swap_buffers().expect("failed to swap buffers");
+}
@@ -0,0 +1,658 @@
+//! A minimal GPU terminal emulator built on `TerminalWidget` and
+//! `BeamtermBackend`.
+//!
+//! Run with:
+//! ```sh
+//! cargo run --features term --bin ratbeam-term -- [options] [-- command args...]
+//! ```
+//!
+//! Options:
+//! - `--atlas <file>`: a font atlas in beamterm's binary format
+//! - `--font <file>`: a TTF or OTF font rasterized at startup instead of an atlas;
+//!   `--bold-font`, `--italic-font` and `--bold-italic-font` add its other faces
+//! - `--font-size <px>`: the size of `--font` in pixels per em, default 14
+//! - `--ligatures`: draw the ligatures of `--font`, such as `->` and `!=`
+//! - `--fallback-font <file>`: a font for characters missing from the atlas or
+//!   `--font`, rasterized when first drawn; may be repeated
+//! - `--palette <file>`: lines of `name = #rrggbb`, where `name` is
+//!   `foreground`, `background` or `color0` to `color15`; `#` starts a comment line
+//! - `--scrollback <lines>`: lines of scrollback history, default 10000
+//!
+//! Keys: `shift+pageup`/`shift+pagedown` and the mouse wheel scroll the view,
+//! dragging selects text, `ctrl+shift+c`/`ctrl+shift+v` copy and paste,
+//! `ctrl+=`/`ctrl+-` zoom in and out and `ctrl+0` restores the initial size.
+//! Fonts given with `--font` zoom in steps of one pixel per em, atlases by
+//! whole factors.
+
+mod common;
+
+use std::process::Command;
+use std::rc::Rc;
+
+use beamterm_core::{
+    FontAtlas, FontAtlasData, FontStyle, GlState, GlslVersion, SelectionMode, TerminalGrid,
+};
+use ratatui::{
+    Terminal,
+    buffer::Cell,
+    layout::Position,
+    style::{Color, Modifier},
+};
+use ratbeam::{
+    AtlasBuilder, BeamtermBackend, Event, FallbackAtlas, FontFamily, Key, Modifiers,
+    SystemClipboard, TerminalWidget,
+};
+use winit::{
+    application::ApplicationHandler,
+    dpi::PhysicalSize,
+    event::{ElementState, MouseButton, MouseScrollDelta, WindowEvent},
+    event_loop::{ActiveEventLoop, EventLoop},
+    keyboard::{self, NamedKey},
+    window::WindowId,
+};
+
+use crate::common::GlWindow;
+
+const DEFAULT_SCROLLBACK: usize = 10_000;
+const DEFAULT_FONT_SIZE: f32 = 14.0;
+const FONT_SIZES: std::ops::RangeInclusive<f32> = 6.0..=72.0;
+const MAX_ATLAS_ZOOM: u8 = 4;
+const INITIAL_SIZE: (u16, u16) = (100, 30);
+/// Lines scrolled per mouse wheel notch.
+const WHEEL_LINES: f64 = 3.0;
+
+fn main() {
+    let config = Config::from_args().unwrap_or_else(|e| {
+        eprintln!("ratbeam-term: {e}");
+        eprintln!(
+            "usage: ratbeam-term [--atlas <file> | --font <file> [--font-size <px>] [--ligatures]] \
+             [--fallback-font <file>]... [--palette <file>] [--scrollback <lines>] [-- command args...]"
+        );
+        std::process::exit(2);
+    });
+
+    let event_loop = EventLoop::new().expect("failed to create event loop");
+    let mut app = TermApp {
+        config,
+        state: None,
+    };
+    event_loop.run_app(&mut app).expect("event loop failed");
+}
+
+struct Config {
+    atlas: FontAtlasData,
+    /// Rasterizes the font given with `--font`, replacing `atlas`.
+    font: Option<AtlasBuilder>,
+    /// Draws the ligatures of `font`.
+    ligatures: bool,
+    /// Fonts given with `--fallback-font`.
+    fallbacks: Vec<Rc<FontFamily>>,
+    palette: Palette,
+    scrollback: usize,
+    command: Vec<String>,
+}
+
+impl Config {
+    fn from_args() -> Result<Self, String> {
+        let mut config = Config {
+            atlas: FontAtlasData::default(),
+            font: None,
+            ligatures: false,
+            fallbacks: Vec::new(),
+            palette: Palette::default(),
+            scrollback: DEFAULT_SCROLLBACK,
+            command: Vec::new(),
+        };
+
+        let mut fonts: [Option<String>; 4] = Default::default();
+        let mut font_size = DEFAULT_FONT_SIZE;
+
+        let mut args = std::env::args().skip(1);
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().ok_or_else(|| format!("{arg} needs a value"));
+            match arg.as_str() {
+                "--atlas" => {
+                    let path = value()?;
+                    let bytes = std::fs::read(&path).map_err(|e| format!("{path}: {e}"))?;
+                    config.atlas =
+                        FontAtlasData::from_binary(&bytes).map_err(|e| format!("{path}: {e:?}"))?;
+                }
+                "--font" => fonts[0] = Some(value()?),
+                "--bold-font" => fonts[1] = Some(value()?),
+                "--italic-font" => fonts[2] = Some(value()?),
+                "--bold-italic-font" => fonts[3] = Some(value()?),
+                "--font-size" => {
+                    let size = value()?;
+                    font_size = size
+                        .parse()
+                        .map_err(|_| format!("invalid font size: {size}"))?;
+                }
+                "--ligatures" => config.ligatures = true,
+                "--fallback-font" => {
+                    let path = value()?;
+                    let family = FontFamily::load(&path).map_err(|e| e.to_string())?;
+                    config.fallbacks.push(Rc::new(family));
+                }
+                "--palette" => {
+                    let path = value()?;
+                    let contents =
+                        std::fs::read_to_string(&path).map_err(|e| format!("{path}: {e}"))?;
+                    config.palette =
+                        Palette::parse(&contents).map_err(|e| format!("{path}: {e}"))?;
+                }
+                "--scrollback" => {
+                    let lines = value()?;
+                    config.scrollback = lines
+                        .parse()
+                        .map_err(|_| format!("invalid scrollback: {lines}"))?;
+                }
+                "--" => {
+                    config.command = args.collect();
+                    break;
+                }
+                _ => return Err(format!("unknown option: {arg}")),
+            }
+        }
+
+        if let [Some(regular), faces @ ..] = &fonts {
+            let mut family = FontFamily::load(regular).map_err(|e| e.to_string())?;
+            for (style, path) in FontStyle::ALL[1..].iter().zip(faces) {
+                if let Some(path) = path {
+                    family = family.with_face(*style, path).map_err(|e| e.to_string())?;
+                }
+            }
+            let builder = config.fallbacks.iter().fold(
+                AtlasBuilder::new(Rc::new(family), font_size),
+                |builder, fallback| builder.with_fallback(fallback.clone()),
+            );
+            config.font = Some(builder);
+        } else if fonts.iter().any(Option::is_some) {
+            return Err("font styles need a regular face given with --font".to_string());
+        }
+        if config.ligatures && config.font.is_none() {
+            return Err("ligatures need a font given with --font".to_string());
+        }
+
+        Ok(config)
+    }
+
+    fn command(&self) -> Command {
+        match self.command.split_first() {
+            Some((program, args)) => {
+                let mut command = Command::new(program);
+                command.args(args);
+                command
+            }
+            None => Command::new(std::env::var("SHELL").unwrap_or_else(|_| "/bin/sh".into())),
+        }
+    }
+}
+
+/// Colors replacing the defaults and the 16 base colors of the terminal.
+#[derive(Default)]
+struct Palette {
+    foreground: Option<Color>,
+    background: Option<Color>,
+    colors: [Option<Color>; 16],
+}
+
+impl Palette {
+    fn parse(contents: &str) -> Result<Self, String> {
+        let mut palette = Palette::default();
+        for (idx, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let invalid = || format!("line {}: expected `name = #rrggbb`", idx + 1);
+            let (name, value) = line.split_once('=').ok_or_else(invalid)?;
+            let color = parse_color(value.trim()).ok_or_else(invalid)?;
+            let slot = match name.trim() {
+                "foreground" => &mut palette.foreground,
+                "background" => &mut palette.background,
+                name => name
+                    .strip_prefix("color")
+                    .and_then(|n| n.parse::<usize>().ok())
+                    .and_then(|n| palette.colors.get_mut(n))
+                    .ok_or_else(|| format!("line {}: unknown color {name}", idx + 1))?,
+            };
+            *slot = Some(color);
+        }
+
+        Ok(palette)
+    }
+
+    fn apply(&self, cell: &mut Cell) {
+        cell.fg = self.resolve(cell.fg, self.foreground);
+        cell.bg = self.resolve(cell.bg, self.background);
+    }
+
+    fn resolve(&self, color: Color, default: Option<Color>) -> Color {
+        let replacement = match color {
+            Color::Reset => default,
+            Color::Indexed(n) if n < 16 => self.colors[n as usize],
+            _ => None,
+        };
+        replacement.unwrap_or(color)
+    }
+
+    /// Returns the background as normalized RGB for clearing the canvas.
+    fn clear_color(&self) -> (f32, f32, f32) {
+        match self.background {
+            Some(Color::Rgb(r, g, b)) => (r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0),
+            _ => (0.0, 0.0, 0.0),
+        }
+    }
+}
+
+fn parse_color(value: &str) -> Option<Color> {
+    let hex = value.strip_prefix('#')?;
+    if hex.len() != 6 {
+        return None;
+    }
+
+    let rgb = u32::from_str_radix(hex, 16).ok()?;
+    Some(Color::Rgb((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8))
+}
+
+struct TermApp {
+    config: Config,
+    state: Option<TermState>,
+}
+
+struct TermState {
+    /// Declared first so it drops while the window's context is current.
+    terminal: Terminal<BeamtermBackend>,
+    win: GlWindow,
+    gl: Rc<glow::Context>,
+    gl_state: GlState,
+    widget: TerminalWidget,
+    modifiers: Modifiers,
+    pointer: Option<Position>,
+    /// Start of the selection while the left button is held.
+    selecting: Option<Position>,
+    /// Scrollback line at the top of the view, to drop the selection of
+    /// screen cells once the view scrolls.
+    view_top: usize,
+    title: String,
+    /// Current size of the `--font` font.
+    font_size: f32,
+    /// Whole factor scaling a prebuilt atlas.
+    atlas_zoom: u8,
+}
+
+impl TermState {
+    /// Clears the selection if the view scrolled since the last call, by
+    /// scrolling back or by output pushing lines into the scrollback; the
+    /// selection covers screen cells, which then show other text. Finished
+    /// selections are also cleared by the grid once their content changes.
+    fn clear_selection_if_scrolled(&mut self) {
+        let history = self.widget.screen().scrollback_len();
+        let view_top = history - self.widget.scroll_offset();
+        if view_top != self.view_top {
+            self.view_top = view_top;
+            self.selecting = None;
+            self.terminal.backend_mut().clear_selection();
+        }
+    }
+
+    /// Returns the pixel ratio of the window, scaled by the atlas zoom.
+    fn pixel_ratio(&self) -> f32 {
+        self.win.pixel_ratio() * self.atlas_zoom as f32
+    }
+}
+
+impl ApplicationHandler for TermApp {
+    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
+        if self.state.is_some() {
+            return;
+        }
+
+        let (win, gl) = GlWindow::new(event_loop, "ratbeam-term", (1280, 800))
+            .expect("failed to create window");
+        let gl = Rc::new(gl);
+        let gl_state = GlState::new(&gl);
+
+        let atlas: FontAtlas = match &self.config.font {
+            Some(builder) => builder
+                .clone()
+                .with_pixel_ratio(win.pixel_ratio())
+                .load(&gl)
+                .expect("failed to rasterize font")
+                .into(),
+            None => FallbackAtlas::load(
+                &gl,
+                self.config.atlas.clone(),
+                self.config.fallbacks.clone(),
+            )
+            .expect("failed to load font atlas")
+            .into(),
+        };
+        let grid = TerminalGrid::new(
+            &gl,
+            atlas,
+            win.physical_size(),
+            win.pixel_ratio(),
+            &GlslVersion::Gl330,
+        )
+        .expect("failed to create terminal grid");
+
+        let (cell_w, cell_h) = grid.cell_size();
+        let (cols, rows) = INITIAL_SIZE;
+        let _ = win.window().request_inner_size(PhysicalSize::new(
+            (cols as i32 * cell_w) as u32,
+            (rows as i32 * cell_h) as u32,
+        ));
+
+        let mut backend = BeamtermBackend::new(grid, gl.clone());
+        let _ = backend.resize(win.physical_size(), win.pixel_ratio());
+        if let Some(builder) = self.config.font.as_ref().filter(|_| self.config.ligatures) {
+            // ligatures are shaped with the font the backend switched to
+            let enabled = backend
+                .set_font(builder.clone())
+                .and_then(|()| backend.set_ligatures(true));
+            if let Err(e) = enabled {
+                eprintln!("ratbeam-term: drawing without ligatures: {e}");
+            }
+        }
+        match SystemClipboard::new() {
+            Ok(clipboard) => backend.set_clipboard(clipboard),
+            Err(e) => eprintln!("ratbeam-term: using an in-memory clipboard: {e}"),
+        }
+        let terminal = Terminal::new(backend).expect("failed to create terminal");
+
+        let mut widget =
+            TerminalWidget::spawn(self.config.command(), cols, rows).unwrap_or_else(|e| {
+                eprintln!("ratbeam-term: failed to spawn the command: {e}");
+                std::process::exit(1);
+            });
+        widget.set_scrollback(self.config.scrollback);
+
+        self.state = Some(TermState {
+            win,
+            gl,
+            gl_state,
+            terminal,
+            widget,
+            modifiers: Modifiers::NONE,
+            pointer: None,
+            selecting: None,
+            view_top: 0,
+            title: String::new(),
+            font_size: self
+                .config
+                .font
+                .as_ref()
+                .map_or(0.0, AtlasBuilder::font_size),
+            atlas_zoom: 1,
+        });
+    }
+
+    fn window_event(
+        &mut self,
+        event_loop: &ActiveEventLoop,
+        _window_id: WindowId,
+        event: WindowEvent,
+    ) {
+        let Some(state) = self.state.as_mut() else {
+            return;
+        };
+
+        match event {
+            WindowEvent::CloseRequested => event_loop.exit(),
+            WindowEvent::ModifiersChanged(modifiers) => {
+                let modifiers = modifiers.state();
+                state.modifiers = Modifiers {
+                    shift: modifiers.shift_key(),
+                    ctrl: modifiers.control_key(),
+                    alt: modifiers.alt_key(),
+                };
+            }
+            WindowEvent::KeyboardInput { event, .. } if event.state == ElementState::Pressed => {
+                handle_key(state, &event.logical_key, self.config.font.as_ref());
+            }
+            WindowEvent::MouseWheel { delta, .. } => {
+                let lines = match delta {
+                    MouseScrollDelta::LineDelta(_, y) => y as f64 * WHEEL_LINES,
+                    MouseScrollDelta::PixelDelta(position) => {
+                        let (_, cell_h) = state.terminal.backend().grid().cell_size();
+                        position.y / cell_h as f64
+                    }
+                };
+                if lines > 0.0 {
+                    state.widget.scroll_up(lines.round() as usize);
+                } else {
+                    state.widget.scroll_down(-lines.round() as usize);
+                }
+                state.clear_selection_if_scrolled();
+            }
+            WindowEvent::CursorMoved { position, .. } => {
+                let backend = state.terminal.backend_mut();
+                state.pointer = backend.cell_at_pixel(position.x, position.y);
+                if let (Some(_), Some(cell)) = (state.selecting, state.pointer) {
+                    backend.extend_selection(cell);
+                }
+            }
+            WindowEvent::CursorLeft { .. } => state.pointer = None,
+            WindowEvent::MouseInput {
+                state: button_state,
+                button: MouseButton::Left,
+                ..
+            } => {
+                let backend = state.terminal.backend_mut();
+                match (button_state, state.pointer) {
+                    (ElementState::Pressed, Some(cell)) => {
+                        backend.start_selection(cell, SelectionMode::Linear);
+                        state.selecting = Some(cell);
+                    }
+                    (ElementState::Released, _) => {
+                        // a click without dragging selects nothing
+                        if state.selecting.take() == state.pointer {
+                            backend.clear_selection();
+                        } else {
+                            backend.end_selection();
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            WindowEvent::Resized(new_size) if new_size.width > 0 && new_size.height > 0 => {
+                state.win.resize_surface(new_size);
+                let pixel_ratio = state.pixel_ratio();
+                let _ = state
+                    .terminal
+                    .backend_mut()
+                    .resize((new_size.width as i32, new_size.height as i32), pixel_ratio);
+            }
+            WindowEvent::RedrawRequested => {
+                state.widget.poll();
+                state.clear_selection_if_scrolled();
+                if !state.widget.is_running() {
+                    event_loop.exit();
+                    return;
+                }
+
+                if state.widget.title() != state.title {
+                    state.title = state.widget.title().to_string();
+                    let title = match state.title.as_str() {
+                        "" => "ratbeam-term",
+                        title => title,
+                    };
+                    state.win.window().set_title(title);
+                }
+
+                render(state, &self.config.palette);
+            }
+            _ => {}
+        }
+    }
+
+    fn about_to_wait(&mut self, _event_loop: &ActiveEventLoop) {
+        if let Some(state) = self.state.as_ref() {
+            state.win.window().request_redraw();
+        }
+    }
+}
+
+fn handle_key(state: &mut TermState, key: &keyboard::Key, font: Option<&AtlasBuilder>) {
+    let modifiers = state.modifiers;
+    let page = state.widget.screen().size().1.saturating_sub(1).max(1) as usize;
+
+    let key = match key.as_ref() {
+        keyboard::Key::Character(c) if modifiers.ctrl && matches!(c, "=" | "+" | "-" | "0") => {
+            let step = match c {
+                "-" => Some(-1),
+                "0" => None,
+                _ => Some(1),
+            };
+            zoom(state, font, step);
+            return;
+        }
+        keyboard::Key::Character(c) if modifiers.ctrl && modifiers.shift => {
+            let backend = state.terminal.backend_mut();
+            if c.eq_ignore_ascii_case("c") {
+                let _ = backend.copy_selection();
+            } else if c.eq_ignore_ascii_case("v") {
+                let _ = backend.paste();
+                while let Some(event) = backend.poll_event() {
+                    if let Event::Paste(text) = event {
+                        let _ = state.widget.paste(&text);
+                    }
+                }
+            }
+            return;
+        }
+        keyboard::Key::Named(NamedKey::PageUp) if modifiers.shift => {
+            state.widget.scroll_up(page);
+            state.clear_selection_if_scrolled();
+            return;
+        }
+        keyboard::Key::Named(NamedKey::PageDown) if modifiers.shift => {
+            state.widget.scroll_down(page);
+            state.clear_selection_if_scrolled();
+            return;
+        }
+        keyboard::Key::Character(text) => {
+            for ch in text.chars() {
+                let _ = state.widget.send_key(Key::Char(ch), modifiers);
+            }
+            return;
+        }
+        keyboard::Key::Named(named) => match translate_named_key(named) {
+            Some(key) => key,
+            None => return,
+        },
+        _ => return,
+    };
+
+    let _ = state.widget.send_key(key, modifiers);
+}
+
+/// Zooms by `step` font sizes or atlas factors, or back to the initial size for `None`.
+fn zoom(state: &mut TermState, font: Option<&AtlasBuilder>, step: Option<i8>) {
+    match font {
+        Some(font) => {
+            let size = match step {
+                Some(step) => {
+                    (state.font_size + step as f32).clamp(*FONT_SIZES.start(), *FONT_SIZES.end())
+                }
+                None => font.font_size(),
+            };
+            if size == state.font_size {
+                return;
+            }
+            let backend = state.terminal.backend_mut();
+            match backend.set_font(font.clone().with_font_size(size)) {
+                Ok(()) => state.font_size = size,
+                Err(e) => eprintln!("ratbeam-term: {e}"),
+            }
+        }
+        None => {
+            state.atlas_zoom = match step {
+                Some(step) => state
+                    .atlas_zoom
+                    .saturating_add_signed(step)
+                    .clamp(1, MAX_ATLAS_ZOOM),
+                None => 1,
+            };
+            let pixel_ratio = state.pixel_ratio();
+            let backend = state.terminal.backend_mut();
+            let _ = backend.resize(backend.grid().canvas_size(), pixel_ratio);
+        }
+    }
+}
+
+fn translate_named_key(key: NamedKey) -> Option<Key> {
+    let key = match key {
+        NamedKey::Enter => Key::Enter,
+        NamedKey::Tab => Key::Tab,
+        NamedKey::Backspace => Key::Backspace,
+        NamedKey::Escape => Key::Escape,
+        NamedKey::Space => Key::Char(' '),
+        NamedKey::ArrowUp => Key::Up,
+        NamedKey::ArrowDown => Key::Down,
+        NamedKey::ArrowLeft => Key::Left,
+        NamedKey::ArrowRight => Key::Right,
+        NamedKey::Home => Key::Home,
+        NamedKey::End => Key::End,
+        NamedKey::PageUp => Key::PageUp,
+        NamedKey::PageDown => Key::PageDown,
+        NamedKey::Insert => Key::Insert,
+        NamedKey::Delete => Key::Delete,
+        NamedKey::F1 => Key::F(1),
+        NamedKey::F2 => Key::F(2),
+        NamedKey::F3 => Key::F(3),
+        NamedKey::F4 => Key::F(4),
+        NamedKey::F5 => Key::F(5),
+        NamedKey::F6 => Key::F(6),
+        NamedKey::F7 => Key::F(7),
+        NamedKey::F8 => Key::F(8),
+        NamedKey::F9 => Key::F(9),
+        NamedKey::F10 => Key::F(10),
+        NamedKey::F11 => Key::F(11),
+        NamedKey::F12 => Key::F(12),
+        _ => return None,
+    };
+    Some(key)
+}
+
+fn render(state: &mut TermState, palette: &Palette) {
+    let widget = &mut state.widget;
+    state
+        .terminal
+        .draw(|frame| {
+            let area = frame.area();
+            frame.render_widget(&mut *widget, area);
+            for cell in frame.buffer_mut().content.iter_mut() {
+                palette.apply(cell);
+            }
+
+            if let Some(cursor) = widget.cursor_position(area) {
+                // the backend draws no cursor, so swap the colors of its cell
+                if let Some(cell) = frame.buffer_mut().cell_mut(cursor) {
+                    cell.modifier.toggle(Modifier::REVERSED);
+                }
+                frame.set_cursor_position(cursor);
+            }
+        })
+        .expect("failed to draw");
+
+    let (w, h) = state.terminal.backend().grid().canvas_size();
+    let (r, g, b) = palette.clear_color();
+    state.gl_state.viewport(&state.gl, 0, 0, w, h);
+    state.gl_state.clear_color(&state.gl, r, g, b, 1.0);
+
+    unsafe {
+        use glow::HasContext;
+        state.gl.clear(glow::COLOR_BUFFER_BIT);
+    }
+
+    state
+        .terminal
+        .backend()
+        .render(&mut state.gl_state)
+        .expect("failed to render");
+
+    state.win.swap_buffers().expect("failed to swap buffers");
+}
@@ -0,0 +1,127 @@
+use beamterm_core::TerminalGrid;
+use glow::HasContext;
+
+use crate::error::Error;
+use crate::image::ImageLayer;
+use crate::target::{self, TextureTarget};
+
+/// An RGBA8 image of the rendered grid, read back from the GPU.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CapturedFrame {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+impl CapturedFrame {
+    /// Creates a frame from tightly packed RGBA8 pixels, top row first.
+    ///
+    /// Returns `None` if `pixels` does not hold `width * height` pixels.
+    pub fn new(width: u32, height: u32, pixels: Vec<u8>) -> Option<Self> {
+        let expected = width as usize * height as usize * 4;
+        (pixels.len() == expected).then_some(Self {
+            width,
+            height,
+            pixels,
+        })
+    }
+
+    /// Width in pixels.
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Height in pixels.
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// RGBA8 pixels, top row first.
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+}
+
+/// Renders `grid` and the `images` over it into an offscreen framebuffer and reads back the pixels.
+///
+/// The grid is drawn as by [`target::render_to`], which restores the host's
+/// GL state afterwards.
+pub(crate) fn capture(
+    gl: &glow::Context,
+    grid: &TerminalGrid,
+    images: &ImageLayer,
+) -> Result<CapturedFrame, Error> {
+    let (width, height) = grid.canvas_size();
+    if width <= 0 || height <= 0 {
+        return Err(Error::Other("cannot capture an empty canvas".to_string()));
+    }
+
+    let texture_target = TextureTarget::new(gl, (width, height))?;
+    let result = target::render_to(gl, grid, images, &texture_target.render_target())
+        .map(|()| read_pixels(gl, &texture_target));
+    texture_target.delete(gl);
+
+    let mut pixels = result?;
+    flip_rows(&mut pixels, width as usize * 4);
+    Ok(CapturedFrame {
+        width: width as u32,
+        height: height as u32,
+        pixels,
+    })
+}
+
+/// Reads back the RGBA8 pixels of `texture_target`, bottom row first.
+///
+/// The host's read framebuffer and pack alignment are restored afterwards.
+fn read_pixels(gl: &glow::Context, texture_target: &TextureTarget) -> Vec<u8> {
+    let (width, height) = texture_target.size();
+    let mut pixels = vec![0u8; width as usize * height as usize * 4];
+    unsafe {
+        let previous = gl.get_parameter_framebuffer(glow::READ_FRAMEBUFFER_BINDING);
+        let alignment = gl.get_parameter_i32(glow::PACK_ALIGNMENT);
+
+        gl.bind_framebuffer(glow::READ_FRAMEBUFFER, Some(texture_target.framebuffer()));
+        gl.pixel_store_i32(glow::PACK_ALIGNMENT, 1);
+        gl.read_pixels(
+            0,
+            0,
+            width,
+            height,
+            glow::RGBA,
+            glow::UNSIGNED_BYTE,
+            glow::PixelPackData::Slice(Some(&mut pixels)),
+        );
+
+        gl.pixel_store_i32(glow::PACK_ALIGNMENT, alignment);
+        gl.bind_framebuffer(glow::READ_FRAMEBUFFER, previous);
+    }
+
+    pixels
+}
+
+/// Reverses the row order of an image; GL reads pixels bottom row first.
+fn flip_rows(pixels: &mut [u8], stride: usize) {
+    let rows = pixels.len() / stride;
+    for y in 0..rows / 2 {
+        let (top, bottom) = pixels.split_at_mut((rows - 1 - y) * stride);
+        top[y * stride..(y + 1) * stride].swap_with_slice(&mut bottom[..stride]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn captured_frame_checks_pixel_count() {
+        assert!(CapturedFrame::new(2, 2, vec![0; 16]).is_some());
+        assert!(CapturedFrame::new(2, 2, vec![0; 15]).is_none());
+    }
+
+    #[test]
+    fn flip_rows_reverses_row_order() {
+        let mut pixels = vec![1, 1, 2, 2, 3, 3];
+        flip_rows(&mut pixels, 2);
+        assert_eq!(pixels, [3, 3, 2, 2, 1, 1]);
+    }
+}
@@ -0,0 +1,121 @@
+use crate::error::Error;
+
+/// A source and sink for clipboard text.
+///
+/// The backend copies the active grid selection into the clipboard and reads
+/// it back when the application requests a paste.
+pub trait Clipboard {
+    /// Returns the current clipboard text, or `None` if the clipboard holds no text.
+    fn get_text(&mut self) -> Result<Option<String>, Error>;
+
+    /// Replaces the clipboard contents with `text`.
+    fn set_text(&mut self, text: &str) -> Result<(), Error>;
+}
+
+/// An in-memory [`Clipboard`], private to the process.
+///
+/// This is the backend's default clipboard and is useful in tests.
+#[derive(Debug, Default, Clone)]
+pub struct MemoryClipboard {
+    contents: Option<String>,
+}
+
+impl MemoryClipboard {
+    /// Creates an empty [`MemoryClipboard`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Clipboard for MemoryClipboard {
+    fn get_text(&mut self) -> Result<Option<String>, Error> {
+        Ok(self.contents.clone())
+    }
+
+    fn set_text(&mut self, text: &str) -> Result<(), Error> {
+        self.contents = Some(text.to_string());
+        Ok(())
+    }
+}
+
+/// A [`Clipboard`] backed by the operating system clipboard.
+#[cfg(feature = "system-clipboard")]
+pub struct SystemClipboard {
+    inner: arboard::Clipboard,
+}
+
+#[cfg(feature = "system-clipboard")]
+impl SystemClipboard {
+    /// Connects to the system clipboard.
+    pub fn new() -> Result<Self, Error> {
+        let inner = arboard::Clipboard::new().map_err(|e| Error::Clipboard(e.to_string()))?;
+        Ok(Self { inner })
+    }
+}
+
+#[cfg(feature = "system-clipboard")]
+impl Clipboard for SystemClipboard {
+    fn get_text(&mut self) -> Result<Option<String>, Error> {
+        match self.inner.get_text() {
+            Ok(text) => Ok(Some(text)),
+            Err(arboard::Error::ContentNotAvailable) => Ok(None),
+            Err(e) => Err(Error::Clipboard(e.to_string())),
+        }
+    }
+
+    fn set_text(&mut self, text: &str) -> Result<(), Error> {
+        self.inner
+            .set_text(text)
+            .map_err(|e| Error::Clipboard(e.to_string()))
+    }
+}
+
+/// Reads the clipboard and wraps non-empty text in an [`Event::Paste`](crate::Event::Paste).
+pub(crate) fn paste_event(clipboard: &mut dyn Clipboard) -> Result<Option<crate::Event>, Error> {
+    let text = clipboard.get_text()?.filter(|text| !text.is_empty());
+    Ok(text.map(crate::Event::Paste))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Event;
+
+    #[test]
+    fn memory_clipboard_starts_empty() {
+        let mut clipboard = MemoryClipboard::new();
+        assert_eq!(clipboard.get_text().unwrap(), None);
+    }
+
+    #[test]
+    fn memory_clipboard_round_trip() {
+        let mut clipboard = MemoryClipboard::new();
+        clipboard.set_text("hello\nworld").unwrap();
+        assert_eq!(
+            clipboard.get_text().unwrap().as_deref(),
+            Some("hello\nworld")
+        );
+
+        clipboard.set_text("replaced").unwrap();
+        assert_eq!(clipboard.get_text().unwrap().as_deref(), Some("replaced"));
+    }
+
+    #[test]
+    fn paste_event_wraps_clipboard_text() {
+        let mut clipboard = MemoryClipboard::new();
+        clipboard.set_text("ls -la").unwrap();
+        assert_eq!(
+            paste_event(&mut clipboard).unwrap(),
+            Some(Event::Paste("ls -la".to_string()))
+        );
+    }
+
+    #[test]
+    fn paste_event_skips_empty_clipboard() {
+        let mut clipboard = MemoryClipboard::new();
+        assert_eq!(paste_event(&mut clipboard).unwrap(), None);
+
+        clipboard.set_text("").unwrap();
+        assert_eq!(paste_event(&mut clipboard).unwrap(), None);
+    }
+}
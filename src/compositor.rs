@@ -0,0 +1,313 @@
+use std::rc::Rc;
+
+use beamterm_core::{Atlas, GlslVersion, TerminalGrid};
+use ratatui::{Terminal, layout::Position};
+
+use crate::atlas::SharedAtlas;
+use crate::backend::BeamtermBackend;
+use crate::error::Error;
+use crate::layout::SplitLayout;
+use crate::target::RenderTarget;
+
+/// A rectangle of the window in physical pixels, with the origin at the
+/// top-left corner.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Viewport {
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+}
+
+impl Viewport {
+    pub const fn new(x: i32, y: i32, width: i32, height: i32) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    /// Returns true if the pixel at `x`, `y` lies inside the viewport.
+    pub fn contains(&self, x: f64, y: f64) -> bool {
+        x >= self.x as f64
+            && y >= self.y as f64
+            && x < (self.x + self.width) as f64
+            && y < (self.y + self.height) as f64
+    }
+
+    /// Returns true if the viewport covers no pixels.
+    pub fn is_empty(&self) -> bool {
+        self.width <= 0 || self.height <= 0
+    }
+
+    /// Returns the lower-left corner in GL window coordinates, for a window
+    /// `window_height` pixels high.
+    fn gl_origin(&self, window_height: i32) -> (i32, i32) {
+        (self.x, window_height - self.y - self.height)
+    }
+}
+
+/// Identifies a pane of a [`Compositor`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct PaneId(pub(crate) u32);
+
+struct Pane {
+    id: PaneId,
+    viewport: Viewport,
+    terminal: Terminal<BeamtermBackend>,
+}
+
+/// Lays out several independent ratatui terminals in one window.
+///
+/// Each pane is a [`Terminal`] over its own [`BeamtermBackend`], drawn into
+/// its [`Viewport`] of the window; all panes share one GL context and one
+/// [`SharedAtlas`]. One pane has the input focus, and pointer positions can
+/// be mapped to the pane and cell beneath them.
+///
+/// Panes are drawn in the order they were added, so later panes overlap
+/// earlier ones.
+///
+/// Dropping the compositor deletes the grids of its panes, then the atlas
+/// if no other handle to it remains, so the GL context must still be
+/// current when it drops.
+pub struct Compositor {
+    gl: Rc<glow::Context>,
+    atlas: SharedAtlas,
+    glsl_version: GlslVersion,
+    window_size: (i32, i32),
+    pixel_ratio: f32,
+    panes: Vec<Pane>,
+    focus: Option<PaneId>,
+    next_id: u32,
+    clear_color: [f32; 4],
+}
+
+impl Compositor {
+    /// Creates a compositor without panes for a window of `window_size`
+    /// physical pixels.
+    pub fn new(
+        gl: Rc<glow::Context>,
+        atlas: SharedAtlas,
+        glsl_version: GlslVersion,
+        window_size: (i32, i32),
+        pixel_ratio: f32,
+    ) -> Self {
+        Self {
+            gl,
+            atlas,
+            glsl_version,
+            window_size,
+            pixel_ratio,
+            panes: Vec::new(),
+            focus: None,
+            next_id: 0,
+            clear_color: [0.0, 0.0, 0.0, 1.0],
+        }
+    }
+
+    /// Sets the color filling each viewport behind its grid. Defaults to black.
+    pub fn set_clear_color(&mut self, rgba: [f32; 4]) {
+        self.clear_color = rgba;
+    }
+
+    /// Returns the atlas shared by the panes.
+    pub fn atlas(&self) -> &SharedAtlas {
+        &self.atlas
+    }
+
+    /// Adds a pane covering `viewport`. The first pane receives the focus.
+    pub fn add_pane(&mut self, viewport: Viewport) -> Result<PaneId, Error> {
+        let grid = TerminalGrid::new(
+            &self.gl,
+            self.atlas.clone().into(),
+            (viewport.width.max(1), viewport.height.max(1)),
+            self.pixel_ratio,
+            &self.glsl_version,
+        )?;
+        let backend = BeamtermBackend::new(grid, self.gl.clone());
+        let terminal = Terminal::new(backend)?;
+
+        let id = PaneId(self.next_id);
+        self.next_id += 1;
+        self.panes.push(Pane {
+            id,
+            viewport,
+            terminal,
+        });
+        self.focus.get_or_insert(id);
+        Ok(id)
+    }
+
+    /// Removes a pane, returning its terminal; its grid is deleted when the
+    /// terminal is dropped. The focus moves to the first remaining pane if
+    /// the removed one had it.
+    pub fn remove_pane(&mut self, id: PaneId) -> Option<Terminal<BeamtermBackend>> {
+        let idx = self.panes.iter().position(|pane| pane.id == id)?;
+        let pane = self.panes.remove(idx);
+        if self.focus == Some(id) {
+            self.focus = self.panes.first().map(|pane| pane.id);
+        }
+        Some(pane.terminal)
+    }
+
+    /// Returns the ids of all panes, in drawing order.
+    pub fn panes(&self) -> impl Iterator<Item = PaneId> + '_ {
+        self.panes.iter().map(|pane| pane.id)
+    }
+
+    /// Returns the viewport of a pane.
+    pub fn viewport(&self, id: PaneId) -> Option<Viewport> {
+        self.pane(id).map(|pane| pane.viewport)
+    }
+
+    /// Moves and resizes a pane; the terminal is resized to fit on its next draw.
+    pub fn set_viewport(&mut self, id: PaneId, viewport: Viewport) -> Result<(), Error> {
+        let pixel_ratio = self.pixel_ratio;
+        let pane = self
+            .pane_mut(id)
+            .ok_or_else(|| Error::Other(format!("unknown pane {id:?}")))?;
+        if pane.viewport == viewport {
+            return Ok(());
+        }
+
+        pane.viewport = viewport;
+        if !viewport.is_empty() {
+            let size = (viewport.width, viewport.height);
+            pane.terminal.backend_mut().resize(size, pixel_ratio)?;
+        }
+        Ok(())
+    }
+
+    /// Moves and resizes the panes of `layout` to the viewports it assigns.
+    /// Panes outside the layout keep their viewports.
+    pub fn apply_layout(&mut self, layout: &SplitLayout) -> Result<(), Error> {
+        for (id, viewport) in layout.viewports() {
+            self.set_viewport(id, viewport)?;
+        }
+        Ok(())
+    }
+
+    /// Updates the window size, needed to place the viewports, and the pixel
+    /// ratio of all panes. Viewports are left unchanged.
+    pub fn resize_window(
+        &mut self,
+        window_size: (i32, i32),
+        pixel_ratio: f32,
+    ) -> Result<(), Error> {
+        self.window_size = window_size;
+        if self.pixel_ratio != pixel_ratio {
+            self.pixel_ratio = pixel_ratio;
+            for pane in &mut self.panes {
+                let size = (pane.viewport.width.max(1), pane.viewport.height.max(1));
+                pane.terminal.backend_mut().resize(size, pixel_ratio)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the terminal of a pane.
+    pub fn terminal(&self, id: PaneId) -> Option<&Terminal<BeamtermBackend>> {
+        self.pane(id).map(|pane| &pane.terminal)
+    }
+
+    /// Returns the terminal of a pane, for drawing into it.
+    pub fn terminal_mut(&mut self, id: PaneId) -> Option<&mut Terminal<BeamtermBackend>> {
+        self.pane_mut(id).map(|pane| &mut pane.terminal)
+    }
+
+    /// Returns the pane with the input focus.
+    pub fn focused(&self) -> Option<PaneId> {
+        self.focus
+    }
+
+    /// Gives the input focus to a pane. Returns false for unknown panes.
+    pub fn focus(&mut self, id: PaneId) -> bool {
+        let known = self.pane(id).is_some();
+        if known {
+            self.focus = Some(id);
+        }
+        known
+    }
+
+    /// Returns the terminal of the focused pane, to route input to.
+    pub fn focused_terminal_mut(&mut self) -> Option<&mut Terminal<BeamtermBackend>> {
+        self.terminal_mut(self.focus?)
+    }
+
+    /// Returns the topmost pane at a physical pixel position of the window.
+    pub fn pane_at(&self, x: f64, y: f64) -> Option<PaneId> {
+        self.panes
+            .iter()
+            .rev()
+            .find(|pane| pane.viewport.contains(x, y))
+            .map(|pane| pane.id)
+    }
+
+    /// Returns the topmost pane at a physical pixel position of the window,
+    /// along with the cell of that pane beneath it.
+    pub fn cell_at(&self, x: f64, y: f64) -> Option<(PaneId, Position)> {
+        let id = self.pane_at(x, y)?;
+        let pane = self.pane(id)?;
+        let viewport = pane.viewport;
+        let position = pane
+            .terminal
+            .backend()
+            .cell_at_pixel(x - viewport.x as f64, y - viewport.y as f64)?;
+        Some((id, position))
+    }
+
+    /// Draws all panes into the default framebuffer.
+    ///
+    /// Panes are drawn as last flushed by their terminals; the GL state is
+    /// restored as in [`BeamtermBackend::render_to`].
+    pub fn render(&self) -> Result<(), Error> {
+        let (_, window_height) = self.window_size;
+        for pane in self.panes.iter().filter(|pane| !pane.viewport.is_empty()) {
+            let (x, y) = pane.viewport.gl_origin(window_height);
+            let target = RenderTarget::new(None)
+                .at(x, y)
+                .with_clear_color(self.clear_color);
+            pane.terminal.backend().render_to(&target)?;
+        }
+        Ok(())
+    }
+
+    fn pane(&self, id: PaneId) -> Option<&Pane> {
+        self.panes.iter().find(|pane| pane.id == id)
+    }
+
+    fn pane_mut(&mut self, id: PaneId) -> Option<&mut Pane> {
+        self.panes.iter_mut().find(|pane| pane.id == id)
+    }
+}
+
+impl Drop for Compositor {
+    fn drop(&mut self) {
+        // the grids hold handles to the atlas, which is deleted only by the last
+        self.panes.clear();
+        self.atlas.delete(&self.gl);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn viewport_contains_half_open_range() {
+        let viewport = Viewport::new(10, 20, 30, 40);
+        assert!(viewport.contains(10.0, 20.0));
+        assert!(viewport.contains(39.9, 59.9));
+        assert!(!viewport.contains(40.0, 30.0));
+        assert!(!viewport.contains(9.9, 30.0));
+    }
+
+    #[test]
+    fn viewport_gl_origin_flips_y() {
+        let viewport = Viewport::new(10, 20, 30, 40);
+        assert_eq!(viewport.gl_origin(100), (10, 40));
+        assert!(Viewport::new(0, 0, 0, 5).is_empty());
+    }
+}
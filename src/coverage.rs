@@ -0,0 +1,321 @@
+use std::collections::HashSet;
+use std::ops::RangeInclusive;
+
+use beamterm_data::{FontAtlasData, FontStyle};
+use compact_str::CompactString;
+use ratatui::symbols::{
+    bar, block, border, braille, half_block, line, marker, pixel, scrollbar, shade,
+};
+
+/// The symbols a font atlas has glyphs for.
+///
+/// Used to check an atlas against the [`SymbolGroup`]s of ratatui's widgets
+/// before symbols silently render as the grid's fallback glyph:
+///
+/// ```
+/// # use beamterm_core::FontAtlasData;
+/// # use ratbeam::{AtlasCoverage, SymbolGroup};
+/// let coverage = AtlasCoverage::new(&FontAtlasData::default());
+/// for group in SymbolGroup::ratatui() {
+///     let missing = coverage.missing(&group);
+///     println!("{}: {} of {} missing", group.name, missing.len(), group.symbols.len());
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct AtlasCoverage {
+    symbols: HashSet<CompactString>,
+    /// Number of glyphs per style, in the order of [`FontStyle::ALL`].
+    style_counts: [usize; 4],
+}
+
+impl AtlasCoverage {
+    /// Collects the symbols of an atlas.
+    pub fn new(data: &FontAtlasData) -> Self {
+        let mut style_counts = [0; 4];
+        for glyph in &data.glyphs {
+            if let Some(idx) = FontStyle::ALL
+                .iter()
+                .position(|style| *style == glyph.style)
+            {
+                style_counts[idx] += 1;
+            }
+        }
+
+        let symbols = data
+            .glyphs
+            .iter()
+            .filter(|glyph| glyph.style == FontStyle::Normal)
+            .map(|glyph| glyph.symbol.clone())
+            .collect();
+        Self {
+            symbols,
+            style_counts,
+        }
+    }
+
+    /// Returns true if the atlas has a glyph for `symbol`.
+    ///
+    /// ASCII symbols are always drawn from their code point's glyph.
+    pub fn contains(&self, symbol: &str) -> bool {
+        matches!(symbol.as_bytes(), [byte] if byte.is_ascii()) || self.symbols.contains(symbol)
+    }
+
+    /// Returns the number of glyphs of each style present in the atlas.
+    pub fn styles(&self) -> impl Iterator<Item = (FontStyle, usize)> + '_ {
+        FontStyle::ALL
+            .into_iter()
+            .zip(self.style_counts)
+            .filter(|(_, count)| *count > 0)
+    }
+
+    /// Returns the single-character symbols of the atlas as ranges of
+    /// consecutive characters, in ascending order.
+    pub fn ranges(&self) -> Vec<RangeInclusive<char>> {
+        let mut chars: Vec<char> = self
+            .symbols
+            .iter()
+            .filter_map(|symbol| single_char(symbol))
+            .collect();
+        chars.sort_unstable();
+
+        let mut ranges: Vec<RangeInclusive<char>> = Vec::new();
+        for c in chars {
+            match ranges.last_mut() {
+                Some(range) if char::from_u32(*range.end() as u32 + 1) == Some(c) => {
+                    *range = *range.start()..=c;
+                }
+                _ => ranges.push(c..=c),
+            }
+        }
+        ranges
+    }
+
+    /// Returns the symbols made of several characters, such as emoji
+    /// sequences, in sorted order.
+    pub fn graphemes(&self) -> Vec<&str> {
+        let mut graphemes: Vec<&str> = self
+            .symbols
+            .iter()
+            .filter(|symbol| single_char(symbol).is_none())
+            .map(CompactString::as_str)
+            .collect();
+        graphemes.sort_unstable();
+        graphemes
+    }
+
+    /// Returns the symbols of `group` the atlas has no glyph for.
+    pub fn missing<'a>(&self, group: &'a SymbolGroup) -> Vec<&'a str> {
+        group
+            .symbols
+            .iter()
+            .map(String::as_str)
+            .filter(|symbol| !self.contains(symbol))
+            .collect()
+    }
+}
+
+fn single_char(symbol: &str) -> Option<char> {
+    let mut chars = symbol.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Some(c),
+        _ => None,
+    }
+}
+
+/// Symbols drawn together by a family of widgets.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SymbolGroup {
+    pub name: &'static str,
+    /// Distinct symbols, in the order first listed.
+    pub symbols: Vec<String>,
+}
+
+impl SymbolGroup {
+    /// Creates a group of the distinct non-blank `symbols`.
+    pub fn new<S: Into<String>>(name: &'static str, symbols: impl IntoIterator<Item = S>) -> Self {
+        let mut seen = HashSet::new();
+        let symbols = symbols
+            .into_iter()
+            .map(Into::into)
+            .filter(|symbol| !symbol.trim().is_empty() && seen.insert(symbol.clone()))
+            .collect();
+        Self { name, symbols }
+    }
+
+    /// The symbols of ratatui's [`symbols`](ratatui::symbols) module: block
+    /// borders and lines, bar and block levels, braille and the other canvas
+    /// markers, and scrollbars.
+    pub fn ratatui() -> Vec<Self> {
+        let borders = [
+            border::PLAIN,
+            border::ROUNDED,
+            border::DOUBLE,
+            border::THICK,
+            border::LIGHT_DOUBLE_DASHED,
+            border::HEAVY_DOUBLE_DASHED,
+            border::LIGHT_TRIPLE_DASHED,
+            border::HEAVY_TRIPLE_DASHED,
+            border::LIGHT_QUADRUPLE_DASHED,
+            border::HEAVY_QUADRUPLE_DASHED,
+            border::QUADRANT_OUTSIDE,
+            border::QUADRANT_INSIDE,
+            border::ONE_EIGHTH_WIDE,
+            border::ONE_EIGHTH_TALL,
+            border::PROPORTIONAL_WIDE,
+            border::PROPORTIONAL_TALL,
+            border::FULL,
+        ];
+        let lines = [
+            line::NORMAL,
+            line::ROUNDED,
+            line::DOUBLE,
+            line::THICK,
+            line::LIGHT_DOUBLE_DASHED,
+            line::HEAVY_DOUBLE_DASHED,
+            line::LIGHT_TRIPLE_DASHED,
+            line::HEAVY_TRIPLE_DASHED,
+            line::LIGHT_QUADRUPLE_DASHED,
+            line::HEAVY_QUADRUPLE_DASHED,
+        ];
+        let bars = [bar::NINE_LEVELS, bar::THREE_LEVELS];
+        let blocks = [block::NINE_LEVELS, block::THREE_LEVELS];
+        let scrollbars = [
+            scrollbar::VERTICAL,
+            scrollbar::HORIZONTAL,
+            scrollbar::DOUBLE_VERTICAL,
+            scrollbar::DOUBLE_HORIZONTAL,
+        ];
+
+        vec![
+            Self::new(
+                "borders",
+                borders.iter().flat_map(|set| {
+                    [
+                        set.top_left,
+                        set.top_right,
+                        set.bottom_left,
+                        set.bottom_right,
+                        set.vertical_left,
+                        set.vertical_right,
+                        set.horizontal_top,
+                        set.horizontal_bottom,
+                    ]
+                }),
+            ),
+            Self::new(
+                "lines",
+                lines.iter().flat_map(|set| {
+                    [
+                        set.vertical,
+                        set.horizontal,
+                        set.top_right,
+                        set.top_left,
+                        set.bottom_right,
+                        set.bottom_left,
+                        set.vertical_left,
+                        set.vertical_right,
+                        set.horizontal_down,
+                        set.horizontal_up,
+                        set.cross,
+                    ]
+                }),
+            ),
+            Self::new(
+                "bars",
+                bars.iter().flat_map(|set| {
+                    [
+                        set.full,
+                        set.seven_eighths,
+                        set.three_quarters,
+                        set.five_eighths,
+                        set.half,
+                        set.three_eighths,
+                        set.one_quarter,
+                        set.one_eighth,
+                    ]
+                }),
+            ),
+            Self::new(
+                "blocks",
+                blocks
+                    .iter()
+                    .flat_map(|set| {
+                        [
+                            set.full,
+                            set.seven_eighths,
+                            set.three_quarters,
+                            set.five_eighths,
+                            set.half,
+                            set.three_eighths,
+                            set.one_quarter,
+                            set.one_eighth,
+                        ]
+                    })
+                    .map(String::from)
+                    .chain(
+                        [half_block::UPPER, half_block::LOWER, half_block::FULL].map(String::from),
+                    )
+                    .chain([shade::LIGHT, shade::MEDIUM, shade::DARK].map(String::from)),
+            ),
+            Self::new("braille", braille::BRAILLE.map(String::from)),
+            Self::new(
+                "markers",
+                [marker::DOT.to_string()]
+                    .into_iter()
+                    .chain(pixel::QUADRANTS.map(String::from))
+                    .chain(pixel::SEXTANTS.map(String::from))
+                    .chain(pixel::OCTANTS.map(String::from)),
+            ),
+            Self::new(
+                "scrollbars",
+                scrollbars
+                    .iter()
+                    .flat_map(|set| [set.track, set.thumb, set.begin, set.end]),
+            ),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ranges_merge_consecutive_chars() {
+        let mut data = FontAtlasData::default();
+        data.glyphs.retain(|glyph| glyph.style == FontStyle::Normal);
+        let coverage = AtlasCoverage::new(&data);
+
+        let ranges = coverage.ranges();
+        assert!(ranges.contains(&(' '..='~')));
+        assert!(ranges.windows(2).all(|w| *w[0].end() < *w[1].start()));
+        assert_eq!(coverage.styles().count(), 1);
+    }
+
+    #[test]
+    fn missing_reports_symbols_without_glyphs() {
+        let mut data = FontAtlasData::default();
+        data.glyphs.retain(|glyph| glyph.symbol != "─");
+        let coverage = AtlasCoverage::new(&data);
+        let group = SymbolGroup::new("test", ["a", "─", "│", " "]);
+
+        assert_eq!(group.symbols, vec!["a", "─", "│"]);
+        assert!(coverage.contains("a") && coverage.contains("\u{7f}"));
+        assert_eq!(coverage.missing(&group), vec!["─"]);
+    }
+
+    #[test]
+    fn ratatui_groups_are_distinct() {
+        let groups = SymbolGroup::ratatui();
+        let braille = groups.iter().find(|group| group.name == "braille").unwrap();
+        // the blank pattern U+2800 is kept; only whitespace is dropped
+        assert_eq!(braille.symbols.len(), 256);
+
+        let lines = groups.iter().find(|group| group.name == "lines").unwrap();
+        assert!(lines.symbols.contains(&"╭".to_string()));
+        assert_eq!(
+            lines.symbols.iter().collect::<HashSet<_>>().len(),
+            lines.symbols.len()
+        );
+    }
+}
@@ -0,0 +1,316 @@
+use std::ops::Range;
+
+use ratatui::layout::Rect;
+use regex::Regex;
+use unicode_width::UnicodeWidthStr;
+
+/// Matches `http(s)://`, `ftp://` and `file://` URLs.
+const URL_PATTERN: &str = r#"\b(?:https?|ftp|file)://[^\s<>"'`]+"#;
+
+/// Matches `path:line` and `path:line:col` locations, as printed by compilers.
+const FILE_LOCATION_PATTERN: &str =
+    r"(?:[A-Za-z]:)?(?:[\w.~-]*[/\\])*[\w.-]*\.\w+:(\d+)(?::(\d+))?";
+
+/// The kind of text recognized by a [`LinkDetector`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MatchKind {
+    /// A URL, such as `https://ratatui.rs`.
+    Url,
+    /// A source location, such as `src/main.rs:12:5`.
+    FileLocation {
+        path: String,
+        line: u32,
+        column: Option<u32>,
+    },
+    /// A match for a custom pattern, identified by the name it was registered with.
+    Custom(String),
+}
+
+/// Text recognized by a [`LinkDetector`] in the rendered grid.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DetectedMatch {
+    /// What kind of text was matched.
+    pub kind: MatchKind,
+    /// The matched text.
+    pub text: String,
+    /// The cells covered by the match.
+    pub area: Rect,
+}
+
+#[derive(Debug, Clone)]
+enum Pattern {
+    Url(Regex),
+    FileLocation(Regex),
+    Custom(String, Regex),
+}
+
+/// Scans rendered cell text for URLs, file locations and custom patterns.
+///
+/// Detection is opt-in: configure a detector and install it with
+/// [`BeamtermBackend::set_link_detector`](crate::BeamtermBackend::set_link_detector).
+/// Patterns are tried in the order they were added, and matches never span rows.
+#[derive(Debug, Clone, Default)]
+pub struct LinkDetector {
+    patterns: Vec<Pattern>,
+}
+
+impl LinkDetector {
+    /// Creates a detector without any patterns.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Detects `http(s)://`, `ftp://` and `file://` URLs.
+    pub fn urls(mut self) -> Self {
+        let regex = Regex::new(URL_PATTERN).expect("valid url pattern");
+        self.patterns.push(Pattern::Url(regex));
+        self
+    }
+
+    /// Detects `path:line` and `path:line:col` source locations.
+    pub fn file_locations(mut self) -> Self {
+        let regex = Regex::new(FILE_LOCATION_PATTERN).expect("valid file location pattern");
+        self.patterns.push(Pattern::FileLocation(regex));
+        self
+    }
+
+    /// Detects matches of a custom regex, reported as [`MatchKind::Custom`] with `name`.
+    pub fn pattern(mut self, name: impl Into<String>, regex: Regex) -> Self {
+        self.patterns.push(Pattern::Custom(name.into(), regex));
+        self
+    }
+
+    /// Finds the match covering column `col` of a row, given the row's cell symbols.
+    pub(crate) fn find_at<S: AsRef<str>>(
+        &self,
+        row: u16,
+        cells: &[S],
+        col: u16,
+    ) -> Option<DetectedMatch> {
+        let (text, offsets) = join_cells(cells);
+        let offset = *offsets.get(col as usize)?;
+
+        self.patterns.iter().find_map(|pattern| {
+            let (regex, trim) = match pattern {
+                Pattern::Url(regex) => (regex, true),
+                Pattern::FileLocation(regex) | Pattern::Custom(_, regex) => (regex, false),
+            };
+
+            regex
+                .captures_iter(&text)
+                .map(|captures| {
+                    let full = captures.get(0).expect("capture group 0 is always present");
+                    let range = if trim {
+                        trim_url(&text, full.range())
+                    } else {
+                        full.range()
+                    };
+                    (captures, range)
+                })
+                .find(|(_, range)| range.contains(&offset))
+                .map(|(captures, range)| {
+                    let matched = &text[range.clone()];
+                    let kind = match pattern {
+                        Pattern::Url(_) => MatchKind::Url,
+                        Pattern::FileLocation(_) => file_location(&captures),
+                        Pattern::Custom(name, _) => MatchKind::Custom(name.clone()),
+                    };
+
+                    let (start, end) = column_span(&offsets, &range);
+                    DetectedMatch {
+                        kind,
+                        text: matched.to_string(),
+                        area: Rect::new(start, row, end - start + 1, 1),
+                    }
+                })
+        })
+    }
+}
+
+/// Concatenates cell symbols, returning the text and the byte offset of each cell.
+///
+/// The blank cells covered by a wide character add no text; they share the
+/// offset of the wide character.
+fn join_cells<S: AsRef<str>>(cells: &[S]) -> (String, Vec<usize>) {
+    let mut text = String::new();
+    let mut offsets = Vec::with_capacity(cells.len());
+    let mut covered = 0;
+    let mut wide_start = 0;
+    for cell in cells {
+        if covered > 0 {
+            covered -= 1;
+            offsets.push(wide_start);
+            continue;
+        }
+
+        let symbol = cell.as_ref();
+        wide_start = text.len();
+        covered = symbol.width().saturating_sub(1);
+        offsets.push(wide_start);
+        text.push_str(symbol);
+    }
+
+    (text, offsets)
+}
+
+/// Returns the first and last column of the cells holding the bytes in `range`.
+fn column_span(offsets: &[usize], range: &Range<usize>) -> (u16, u16) {
+    let containing = offsets.partition_point(|&start| start <= range.start) - 1;
+    let first = offsets.partition_point(|&start| start < offsets[containing]);
+    let last = offsets.partition_point(|&start| start < range.end) - 1;
+    (first as u16, last as u16)
+}
+
+/// Trims trailing punctuation and unbalanced closing parentheses from a URL match.
+fn trim_url(text: &str, mut range: Range<usize>) -> Range<usize> {
+    let opened = text[range.clone()].matches('(').count();
+    let mut closed = text[range.clone()].matches(')').count();
+
+    while let Some(ch) = text[range.clone()].chars().last() {
+        let unbalanced_paren = ch == ')' && closed > opened;
+        if !unbalanced_paren && !matches!(ch, '.' | ',' | ';' | ':' | '!' | '?') {
+            break;
+        }
+
+        if ch == ')' {
+            closed -= 1;
+        }
+        range.end -= ch.len_utf8();
+    }
+
+    range
+}
+
+/// Splits a `path:line:col` match into its components.
+fn file_location(captures: &regex::Captures) -> MatchKind {
+    let full = captures.get(0).expect("capture group 0 is always present");
+    let line = captures.get(1).expect("line number is captured");
+
+    // the path ends at the ':' preceding the line number
+    let path = full.as_str()[..line.start() - full.start() - 1].to_string();
+    let column = captures.get(2).and_then(|m| m.as_str().parse().ok());
+
+    MatchKind::FileLocation {
+        path,
+        line: line.as_str().parse().unwrap_or(0),
+        column,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use unicode_width::UnicodeWidthChar;
+
+    use super::*;
+
+    /// Splits `s` into cell symbols, followed by a blank cell for each wide
+    /// character as in a ratatui buffer.
+    fn cells(s: &str) -> Vec<String> {
+        let mut cells = Vec::new();
+        for ch in s.chars() {
+            cells.push(ch.to_string());
+            if ch.width() == Some(2) {
+                cells.push(" ".to_string());
+            }
+        }
+
+        cells
+    }
+
+    #[test]
+    fn detects_url_under_cursor() {
+        let detector = LinkDetector::new().urls();
+        let row = cells("see https://ratatui.rs/docs. now");
+
+        let found = detector.find_at(3, &row, 10).unwrap();
+        assert_eq!(found.kind, MatchKind::Url);
+        assert_eq!(found.text, "https://ratatui.rs/docs");
+        assert_eq!(found.area, Rect::new(4, 3, 23, 1));
+
+        assert_eq!(detector.find_at(3, &row, 1), None);
+        assert_eq!(detector.find_at(3, &row, 27), None);
+    }
+
+    #[test]
+    fn url_keeps_balanced_parens() {
+        let detector = LinkDetector::new().urls();
+        let row = cells("(https://en.wikipedia.org/wiki/Rust_(language))");
+
+        let found = detector.find_at(0, &row, 5).unwrap();
+        assert_eq!(found.text, "https://en.wikipedia.org/wiki/Rust_(language)");
+    }
+
+    #[test]
+    fn detects_file_location() {
+        let detector = LinkDetector::new().file_locations();
+        let row = cells("  --> src/backend.rs:142:9");
+
+        let found = detector.find_at(0, &row, 12).unwrap();
+        assert_eq!(found.text, "src/backend.rs:142:9");
+        assert_eq!(found.area, Rect::new(6, 0, 20, 1));
+        assert_eq!(
+            found.kind,
+            MatchKind::FileLocation {
+                path: "src/backend.rs".to_string(),
+                line: 142,
+                column: Some(9),
+            }
+        );
+    }
+
+    #[test]
+    fn file_location_without_column() {
+        let detector = LinkDetector::new().file_locations();
+        let row = cells("main.rs:7: warning");
+
+        let found = detector.find_at(0, &row, 0).unwrap();
+        assert_eq!(
+            found.kind,
+            MatchKind::FileLocation {
+                path: "main.rs".to_string(),
+                line: 7,
+                column: None
+            }
+        );
+    }
+
+    #[test]
+    fn custom_pattern() {
+        let regex = Regex::new(r"#\d+").unwrap();
+        let detector = LinkDetector::new().urls().pattern("issue", regex);
+        let row = cells("fixes #1234");
+
+        let found = detector.find_at(0, &row, 8).unwrap();
+        assert_eq!(found.kind, MatchKind::Custom("issue".to_string()));
+        assert_eq!(found.text, "#1234");
+        assert_eq!(found.area, Rect::new(6, 0, 5, 1));
+    }
+
+    #[test]
+    fn columns_account_for_multibyte_cells() {
+        let detector = LinkDetector::new().urls();
+        let row = cells("→ ╭ http://a.b");
+
+        let found = detector.find_at(0, &row, 6).unwrap();
+        assert_eq!(found.area, Rect::new(4, 0, 10, 1));
+    }
+
+    #[test]
+    fn wide_characters_cover_two_columns() {
+        let detector = LinkDetector::new().urls();
+        let row = cells("見 https://ja.wikipedia.org/wiki/東京 x");
+
+        let found = detector.find_at(0, &row, 3).unwrap();
+        assert_eq!(found.text, "https://ja.wikipedia.org/wiki/東京");
+        assert_eq!(found.area, Rect::new(3, 0, 34, 1));
+        // the blank cell of the last wide character
+        assert_eq!(detector.find_at(0, &row, 36), Some(found));
+        assert_eq!(detector.find_at(0, &row, 1), None);
+    }
+
+    #[test]
+    fn no_patterns_no_matches() {
+        let row = cells("https://ratatui.rs");
+        assert_eq!(LinkDetector::new().find_at(0, &row, 3), None);
+    }
+}
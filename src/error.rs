@@ -1,7 +1,20 @@
 #[derive(thiserror::Error, Debug)]
+#[non_exhaustive]
 pub enum Error {
     #[error("beamterm: {0}")]
     Beamterm(#[from] beamterm_core::Error),
+    #[error("clipboard: {0}")]
+    Clipboard(String),
+    #[error("asciicast: {0}")]
+    Cast(String),
+    #[cfg(feature = "font")]
+    #[error("font: {0}")]
+    Font(String),
+    #[error("io: {0}")]
+    Io(#[from] std::io::Error),
+    #[cfg(feature = "frame-recorder")]
+    #[error("png: {0}")]
+    Png(#[from] png::EncodingError),
     #[error("{0}")]
     Other(String),
 }
@@ -0,0 +1,26 @@
+use ratatui::layout::Position;
+
+use crate::detect::DetectedMatch;
+
+/// Events raised by the backend for the application to handle.
+///
+/// Events are queued by the backend and retrieved with
+/// [`BeamtermBackend::poll_event`](crate::BeamtermBackend::poll_event).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Event {
+    /// Text pasted from the clipboard, delivered as a single bracketed paste.
+    Paste(String),
+    /// A hyperlink was ctrl+clicked.
+    LinkActivated {
+        /// The URI of the activated hyperlink.
+        uri: String,
+        /// The cell that was clicked.
+        position: Position,
+    },
+    /// Text recognized by the [`LinkDetector`](crate::LinkDetector) was ctrl+clicked.
+    MatchActivated(DetectedMatch),
+    /// A symbol was drawn that the font atlas has no glyph for; it is shown as
+    /// the grid's fallback glyph. Raised once per symbol, see
+    /// [`BeamtermBackend::missing_glyphs`](crate::BeamtermBackend::missing_glyphs).
+    MissingGlyph(String),
+}
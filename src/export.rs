@@ -0,0 +1,286 @@
+use std::fmt::Write;
+
+use ratatui::{
+    buffer::{Buffer, Cell},
+    style::{Color, Modifier},
+};
+use unicode_width::UnicodeWidthStr;
+
+use crate::color::to_rgb;
+
+/// Default foreground, matching the backend's fallback for [`Color::Reset`].
+const DEFAULT_FG: u32 = 0xffffff;
+/// Default background, matching the backend's fallback for [`Color::Reset`].
+const DEFAULT_BG: u32 = 0x000000;
+
+/// SGR parameters for each modifier, in ascending SGR order.
+const SGR_MODIFIERS: [(Modifier, u8); 9] = [
+    (Modifier::BOLD, 1),
+    (Modifier::DIM, 2),
+    (Modifier::ITALIC, 3),
+    (Modifier::UNDERLINED, 4),
+    (Modifier::SLOW_BLINK, 5),
+    (Modifier::RAPID_BLINK, 6),
+    (Modifier::REVERSED, 7),
+    (Modifier::HIDDEN, 8),
+    (Modifier::CROSSED_OUT, 9),
+];
+
+/// Serializes the buffer to plain text, one line per row.
+///
+/// Trailing whitespace is trimmed from each row.
+pub(crate) fn to_plain_text(buffer: &Buffer) -> String {
+    let mut text = String::new();
+    for row in rows(buffer) {
+        let line: String = row.iter().map(|cell| cell.symbol()).collect();
+        text.push_str(line.trim_end());
+        text.push('\n');
+    }
+
+    text
+}
+
+/// Serializes the buffer to a string of text and ANSI escape sequences.
+///
+/// Colors are emitted as truecolor SGR sequences; [`Color::Reset`] maps to the
+/// terminal's default colors. Each row ends with an SGR reset.
+pub(crate) fn to_ansi(buffer: &Buffer) -> String {
+    let mut ansi = String::new();
+    for row in rows(buffer) {
+        let mut current: Option<(Color, Color, Modifier)> = None;
+        for cell in row {
+            let style = (cell.fg, cell.bg, cell.modifier);
+            if current != Some(style) {
+                push_sgr(&mut ansi, cell);
+                current = Some(style);
+            }
+            ansi.push_str(cell.symbol());
+        }
+        ansi.push_str("\x1b[0m\n");
+    }
+
+    ansi
+}
+
+/// Serializes the buffer to an HTML `<pre>` element with inline styles.
+///
+/// The element is self-contained and can be embedded in any HTML document.
+pub(crate) fn to_html(buffer: &Buffer) -> String {
+    let mut html = format!(
+        "<pre style=\"color:{};background-color:{};font-family:monospace\">",
+        css_color(DEFAULT_FG),
+        css_color(DEFAULT_BG),
+    );
+
+    for row in rows(buffer) {
+        let mut cells = row.into_iter().peekable();
+        while let Some(first) = cells.next() {
+            // group consecutive cells sharing a style into a single span
+            let mut text = escape_html(first.symbol());
+            while let Some(cell) = cells.next_if(|cell| same_style(cell, first)) {
+                text.push_str(&escape_html(cell.symbol()));
+            }
+
+            let _ = write!(html, "<span style=\"{}\">{text}</span>", css_style(first));
+        }
+        html.push('\n');
+    }
+
+    html.push_str("</pre>");
+    html
+}
+
+/// Returns the cells of each row, skipping the cells covered by wide graphemes.
+fn rows(buffer: &Buffer) -> impl Iterator<Item = Vec<&Cell>> {
+    let area = buffer.area;
+    (area.top()..area.bottom()).map(move |y| {
+        let mut row = Vec::with_capacity(area.width as usize);
+        let mut skip = 0;
+        for x in area.left()..area.right() {
+            if skip > 0 {
+                skip -= 1;
+                continue;
+            }
+
+            let cell = &buffer[(x, y)];
+            skip = cell.symbol().width().saturating_sub(1);
+            row.push(cell);
+        }
+
+        row
+    })
+}
+
+/// Appends an SGR sequence selecting the style of `cell`, after a reset.
+pub(crate) fn push_sgr(ansi: &mut String, cell: &Cell) {
+    ansi.push_str("\x1b[0");
+    for (modifier, code) in SGR_MODIFIERS {
+        if cell.modifier.contains(modifier) {
+            let _ = write!(ansi, ";{code}");
+        }
+    }
+
+    if cell.fg != Color::Reset {
+        let (r, g, b) = rgb_components(to_rgb(cell.fg, DEFAULT_FG));
+        let _ = write!(ansi, ";38;2;{r};{g};{b}");
+    }
+    if cell.bg != Color::Reset {
+        let (r, g, b) = rgb_components(to_rgb(cell.bg, DEFAULT_BG));
+        let _ = write!(ansi, ";48;2;{r};{g};{b}");
+    }
+
+    ansi.push('m');
+}
+
+/// Returns true if both cells render with the same colors and modifiers.
+fn same_style(a: &Cell, b: &Cell) -> bool {
+    (a.fg, a.bg, a.modifier) == (b.fg, b.bg, b.modifier)
+}
+
+/// Returns the inline CSS for the style of `cell`.
+fn css_style(cell: &Cell) -> String {
+    let mut fg = to_rgb(cell.fg, DEFAULT_FG);
+    let mut bg = to_rgb(cell.bg, DEFAULT_BG);
+    if cell.modifier.contains(Modifier::REVERSED) {
+        std::mem::swap(&mut fg, &mut bg);
+    }
+    if cell.modifier.contains(Modifier::HIDDEN) {
+        fg = bg;
+    }
+
+    let mut css = format!("color:{};background-color:{}", css_color(fg), css_color(bg));
+    if cell.modifier.contains(Modifier::BOLD) {
+        css.push_str(";font-weight:bold");
+    }
+    if cell.modifier.contains(Modifier::DIM) {
+        css.push_str(";opacity:0.5");
+    }
+    if cell.modifier.contains(Modifier::ITALIC) {
+        css.push_str(";font-style:italic");
+    }
+
+    let decorations: Vec<&str> = [
+        (Modifier::UNDERLINED, "underline"),
+        (Modifier::CROSSED_OUT, "line-through"),
+    ]
+    .into_iter()
+    .filter(|(modifier, _)| cell.modifier.contains(*modifier))
+    .map(|(_, decoration)| decoration)
+    .collect();
+
+    if !decorations.is_empty() {
+        let _ = write!(css, ";text-decoration:{}", decorations.join(" "));
+    }
+
+    css
+}
+
+fn css_color(rgb: u32) -> String {
+    format!("#{rgb:06x}")
+}
+
+fn rgb_components(rgb: u32) -> (u8, u8, u8) {
+    ((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8)
+}
+
+fn escape_html(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for ch in s.chars() {
+        match ch {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            _ => escaped.push(ch),
+        }
+    }
+
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use ratatui::{
+        layout::Rect,
+        style::{Style, Stylize},
+        text::Line,
+    };
+
+    use super::*;
+
+    fn buffer(lines: &[Line]) -> Buffer {
+        let width = lines.iter().map(Line::width).max().unwrap_or(0) as u16;
+        let mut buffer = Buffer::empty(Rect::new(0, 0, width, lines.len() as u16));
+        for (y, line) in lines.iter().enumerate() {
+            buffer.set_line(0, y as u16, line, width);
+        }
+
+        buffer
+    }
+
+    #[test]
+    fn plain_text_trims_trailing_whitespace() {
+        let buffer = buffer(&[Line::from("hello  "), Line::from("  world")]);
+        assert_eq!(to_plain_text(&buffer), "hello\n  world\n");
+    }
+
+    #[test]
+    fn plain_text_skips_wide_grapheme_padding() {
+        let buffer = buffer(&[Line::from("中文ab")]);
+        assert_eq!(to_plain_text(&buffer), "中文ab\n");
+    }
+
+    #[test]
+    fn ansi_default_style() {
+        let buffer = buffer(&[Line::from("ab")]);
+        assert_eq!(to_ansi(&buffer), "\x1b[0mab\x1b[0m\n");
+    }
+
+    #[test]
+    fn ansi_truecolor_and_modifiers() {
+        let line = Line::from(vec![
+            "a".bold().fg(Color::Rgb(255, 0, 128)),
+            "b".italic().underlined().bg(Color::Indexed(196)),
+            "c".into(),
+        ]);
+
+        assert_eq!(
+            to_ansi(&buffer(&[line])),
+            "\x1b[0;1;38;2;255;0;128ma\x1b[0;3;4;48;2;255;0;0mb\x1b[0mc\x1b[0m\n"
+        );
+    }
+
+    #[test]
+    fn ansi_only_emits_style_changes() {
+        let line = Line::from("abc").style(Style::new().red());
+        assert_eq!(
+            to_ansi(&buffer(&[line])),
+            "\x1b[0;38;2;128;0;0mabc\x1b[0m\n"
+        );
+    }
+
+    #[test]
+    fn html_groups_runs_and_escapes() {
+        let line = Line::from(vec!["<a>".bold(), "&".crossed_out().underlined()]);
+        let html = to_html(&buffer(&[line]));
+
+        assert_eq!(
+            html,
+            "<pre style=\"color:#ffffff;background-color:#000000;font-family:monospace\">\
+             <span style=\"color:#ffffff;background-color:#000000;font-weight:bold\">&lt;a&gt;</span>\
+             <span style=\"color:#ffffff;background-color:#000000;\
+             text-decoration:underline line-through\">&amp;</span>\n</pre>"
+        );
+    }
+
+    #[test]
+    fn html_reversed_swaps_colors() {
+        let line = Line::from(
+            "x".fg(Color::Rgb(1, 2, 3))
+                .bg(Color::Rgb(4, 5, 6))
+                .reversed(),
+        );
+        let html = to_html(&buffer(&[line]));
+        assert!(html.contains("color:#040506;background-color:#010203"));
+    }
+}
@@ -0,0 +1,989 @@
+use std::fmt;
+use std::ops::{Range, RangeInclusive};
+use std::path::Path;
+use std::rc::Rc;
+
+use ab_glyph::{Font, FontVec, GlyphId, PxScale, ScaleFont, point};
+use beamterm_core::{Atlas, Error as BeamtermError, GlyphSlot, GlyphTracker};
+use beamterm_data::{FontAtlasData, FontStyle, Glyph, LineDecoration};
+use compact_str::CompactString;
+use unicode_width::UnicodeWidthChar;
+
+use crate::error::Error;
+
+mod color;
+mod fallback;
+mod ligature;
+mod procedural;
+
+pub use fallback::FallbackAtlas;
+use fallback::FallbackFont;
+use ligature::ShapedGlyph;
+pub(crate) use ligature::column_key;
+
+/// Base glyph ids per font style; the style bits follow above them.
+const BASE_GLYPH_IDS: u16 = 1024;
+/// Glyphs stored in each layer of the atlas texture.
+const GLYPHS_PER_LAYER: i32 = FontAtlasData::CELLS_PER_SLICE;
+/// Horizontal offset per pixel above the baseline of synthesized italics.
+const SYNTHETIC_SLANT: f32 = 0.2;
+
+/// The faces of a font family, loaded from TTF or OTF files.
+///
+/// Only the regular face is required. Bold and italic styles missing from
+/// the family are synthesized from the closest available face.
+pub struct FontFamily {
+    name: String,
+    /// Faces indexed by [`style_index`]; the regular face is always present.
+    faces: [Option<FontVec>; 4],
+}
+
+impl FontFamily {
+    /// Loads the regular face from a font file, naming the family after it.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let name = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        Self::from_bytes(name, read_font(path)?)
+    }
+
+    /// Creates a family from the contents of a font file for the regular face.
+    pub fn from_bytes(name: impl Into<String>, data: Vec<u8>) -> Result<Self, Error> {
+        Ok(Self {
+            name: name.into(),
+            faces: [Some(parse_face(data)?), None, None, None],
+        })
+    }
+
+    /// Loads the face used for `style` from a font file.
+    pub fn with_face(self, style: FontStyle, path: impl AsRef<Path>) -> Result<Self, Error> {
+        self.with_face_bytes(style, read_font(path.as_ref())?)
+    }
+
+    /// Sets the face used for `style` from the contents of a font file.
+    pub fn with_face_bytes(mut self, style: FontStyle, data: Vec<u8>) -> Result<Self, Error> {
+        self.faces[style_index(style)] = Some(parse_face(data)?);
+        Ok(self)
+    }
+
+    /// Returns the name of the family.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns true if the family has its own face for `style`.
+    pub fn has_face(&self, style: FontStyle) -> bool {
+        self.faces[style_index(style)].is_some()
+    }
+
+    /// Returns true if the regular face has a glyph for `c`.
+    pub fn has_glyph(&self, c: char) -> bool {
+        self.regular().glyph_id(c).0 != 0
+    }
+
+    /// Returns the ranges of `text` the face for `style` draws as
+    /// ligatures of two or more characters.
+    pub(crate) fn ligatures(&self, style: FontStyle, text: &[char]) -> Vec<Range<usize>> {
+        let (face, _) = self.face(style);
+        ligature::ligatures(face.as_slice(), text)
+    }
+
+    fn regular(&self) -> &FontVec {
+        self.faces[0]
+            .as_ref()
+            .expect("regular face is always loaded")
+    }
+
+    /// Returns the face to draw `style` with, along with the styles to
+    /// synthesize on top of it.
+    fn face(&self, style: FontStyle) -> (&FontVec, Synthetic) {
+        let candidates: &[(FontStyle, Synthetic)] = match style {
+            FontStyle::Normal => &[],
+            FontStyle::Bold => &[(FontStyle::Bold, Synthetic::NONE)],
+            FontStyle::Italic => &[(FontStyle::Italic, Synthetic::NONE)],
+            FontStyle::BoldItalic => &[
+                (FontStyle::BoldItalic, Synthetic::NONE),
+                (FontStyle::Italic, Synthetic::BOLD),
+                (FontStyle::Bold, Synthetic::ITALIC),
+            ],
+        };
+        candidates
+            .iter()
+            .find_map(|(style, synthetic)| {
+                let face = self.faces[style_index(*style)].as_ref()?;
+                Some((face, *synthetic))
+            })
+            .unwrap_or_else(|| (self.regular(), Synthetic::for_style(style)))
+    }
+}
+
+impl fmt::Debug for FontFamily {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let styles: Vec<_> = FontStyle::ALL
+            .into_iter()
+            .filter(|style| self.has_face(*style))
+            .collect();
+        f.debug_struct("FontFamily")
+            .field("name", &self.name)
+            .field("faces", &styles)
+            .finish()
+    }
+}
+
+/// Styles synthesized from a face lacking them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Synthetic {
+    bold: bool,
+    italic: bool,
+}
+
+impl Synthetic {
+    const NONE: Self = Self::new(false, false);
+    const BOLD: Self = Self::new(true, false);
+    const ITALIC: Self = Self::new(false, true);
+
+    const fn new(bold: bool, italic: bool) -> Self {
+        Self { bold, italic }
+    }
+
+    fn for_style(style: FontStyle) -> Self {
+        Self::new(
+            matches!(style, FontStyle::Bold | FontStyle::BoldItalic),
+            matches!(style, FontStyle::Italic | FontStyle::BoldItalic),
+        )
+    }
+}
+
+fn style_index(style: FontStyle) -> usize {
+    (style.style_mask() >> 10) as usize
+}
+
+fn read_font(path: &Path) -> Result<Vec<u8>, Error> {
+    std::fs::read(path).map_err(|e| Error::Font(format!("{}: {e}", path.display())))
+}
+
+fn parse_face(data: Vec<u8>) -> Result<FontVec, Error> {
+    FontVec::try_from_vec(data).map_err(|e| Error::Font(e.to_string()))
+}
+
+/// The characters to rasterize into an atlas.
+///
+/// Printable ASCII is always included. The default set adds Latin-1 and the
+/// box drawing, block, braille and symbol ranges ratatui's widgets draw with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GlyphSet {
+    ranges: Vec<RangeInclusive<char>>,
+}
+
+impl GlyphSet {
+    /// Printable ASCII only.
+    pub fn ascii() -> Self {
+        Self {
+            ranges: vec![' '..='~'],
+        }
+    }
+
+    /// The Latin-1 supplement, U+00A0 to U+00FF.
+    pub fn latin1() -> Self {
+        Self::ascii().with_range('\u{a0}'..='\u{ff}')
+    }
+
+    /// Box drawing and block elements, U+2500 to U+259F.
+    pub fn box_drawing() -> Self {
+        Self::ascii().with_range('\u{2500}'..='\u{259f}')
+    }
+
+    /// Braille patterns, U+2800 to U+28FF.
+    pub fn braille() -> Self {
+        Self::ascii().with_range('\u{2800}'..='\u{28ff}')
+    }
+
+    /// General punctuation, arrows and geometric shapes.
+    pub fn symbols() -> Self {
+        Self::ascii()
+            .with_range('\u{2010}'..='\u{2027}')
+            .with_range('\u{2190}'..='\u{21ff}')
+            .with_range('\u{25a0}'..='\u{25ff}')
+    }
+
+    /// Powerline symbols, U+E0A0 to U+E0A3 and U+E0B0 to U+E0D7, including
+    /// the extra separators of Nerd Fonts.
+    pub fn powerline() -> Self {
+        Self::ascii()
+            .with_range('\u{e0a0}'..='\u{e0a3}')
+            .with_range('\u{e0b0}'..='\u{e0d7}')
+    }
+
+    /// The Powerline symbols and the Nerd Font icons most used by prompts
+    /// and file listers: Pomicons, IEC power symbols, Seti-UI and font logos.
+    ///
+    /// All Nerd Font icons exceed the capacity of an atlas; runtime atlases
+    /// rasterize the others on demand.
+    pub fn nerd_font() -> Self {
+        Self::powerline()
+            .with_range('\u{e000}'..='\u{e00a}')
+            .with_range('\u{23fb}'..='\u{23fe}')
+            .with_range('\u{2b58}'..='\u{2b58}')
+            .with_range('\u{e5fa}'..='\u{e6b7}')
+            .with_range('\u{f300}'..='\u{f381}')
+    }
+
+    /// Adds a range of characters.
+    pub fn with_range(mut self, range: RangeInclusive<char>) -> Self {
+        self.ranges.push(range);
+        self
+    }
+
+    /// Adds every character of `chars`.
+    pub fn with_chars(mut self, chars: &str) -> Self {
+        self.ranges.extend(chars.chars().map(|c| c..=c));
+        self
+    }
+
+    /// Adds the characters of another set.
+    pub fn with_set(mut self, other: &GlyphSet) -> Self {
+        self.ranges.extend(other.ranges.iter().cloned());
+        self
+    }
+
+    /// Returns true if the set includes `c`.
+    pub fn contains(&self, c: char) -> bool {
+        self.ranges.iter().any(|range| range.contains(&c))
+    }
+
+    /// Returns the characters of the set in ascending order.
+    pub fn chars(&self) -> Vec<char> {
+        let mut chars: Vec<char> = self.ranges.iter().cloned().flatten().collect();
+        chars.sort_unstable();
+        chars.dedup();
+        chars
+    }
+}
+
+impl Default for GlyphSet {
+    fn default() -> Self {
+        Self::latin1()
+            .with_set(&Self::symbols())
+            .with_set(&Self::box_drawing())
+            .with_set(&Self::braille())
+    }
+}
+
+/// Rasterizes a [`FontFamily`] into a font atlas at a given size.
+///
+/// The font size is in pixels per em at a pixel ratio of 1, the convention
+/// of the prebuilt atlases. [`build`](Self::build) produces the same
+/// [`FontAtlasData`] as a prebuilt `.atlas` file; [`load`](Self::load)
+/// uploads it as a [`RuntimeAtlas`] ready for a
+/// [`TerminalGrid`](beamterm_core::TerminalGrid):
+///
+/// ```no_run
+/// # use std::rc::Rc;
+/// # use beamterm_core::FontStyle;
+/// # use ratbeam::{AtlasBuilder, FontFamily};
+/// # fn atlas(gl: &glow::Context) -> Result<(), ratbeam::Error> {
+/// let family = FontFamily::load("JetBrainsMono-Regular.ttf")?
+///     .with_face(FontStyle::Bold, "JetBrainsMono-Bold.ttf")?;
+/// let atlas = AtlasBuilder::new(Rc::new(family), 14.0)
+///     .with_pixel_ratio(2.0)
+///     .load(gl)?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct AtlasBuilder {
+    family: Rc<FontFamily>,
+    font_size: f32,
+    pixel_ratio: f32,
+    glyphs: GlyphSet,
+    fallbacks: Vec<Rc<FontFamily>>,
+}
+
+impl AtlasBuilder {
+    /// Creates a builder for `family` at `font_size` with the default [`GlyphSet`].
+    pub fn new(family: Rc<FontFamily>, font_size: f32) -> Self {
+        Self {
+            family,
+            font_size,
+            pixel_ratio: 1.0,
+            glyphs: GlyphSet::default(),
+            fallbacks: Vec::new(),
+        }
+    }
+
+    /// Sets the characters to rasterize.
+    pub fn with_glyphs(mut self, glyphs: GlyphSet) -> Self {
+        self.glyphs = glyphs;
+        self
+    }
+
+    /// Adds a font to draw the characters the family lacks with, tried in
+    /// the order added.
+    pub fn with_fallback(mut self, family: Rc<FontFamily>) -> Self {
+        self.fallbacks.push(family);
+        self
+    }
+
+    /// Rasterizes at `pixel_ratio` physical pixels per logical pixel. Defaults to 1.
+    pub fn with_pixel_ratio(mut self, pixel_ratio: f32) -> Self {
+        self.pixel_ratio = pixel_ratio;
+        self
+    }
+
+    /// Sets the font size in pixels per em.
+    pub fn with_font_size(mut self, font_size: f32) -> Self {
+        self.font_size = font_size;
+        self
+    }
+
+    /// Returns the font family.
+    pub fn family(&self) -> &Rc<FontFamily> {
+        &self.family
+    }
+
+    /// Returns the font size in pixels per em.
+    pub fn font_size(&self) -> f32 {
+        self.font_size
+    }
+
+    /// Rasterizes the glyphs into atlas data.
+    ///
+    /// Characters the family has no glyph for are left out, except box
+    /// drawing, block elements, braille and powerline separators, which are
+    /// drawn procedurally.
+    pub fn build(&self) -> Result<FontAtlasData, Error> {
+        let px_per_em = self.font_size * self.pixel_ratio;
+        if !(px_per_em >= 1.0 && px_per_em.is_finite()) {
+            return Err(Error::Font(format!(
+                "invalid font size: {}",
+                self.font_size
+            )));
+        }
+
+        let metrics = Metrics::new(self.family.regular(), px_per_em)?;
+        let (narrow, wide): (Vec<char>, Vec<char>) = self
+            .glyphs
+            .chars()
+            .into_iter()
+            .filter(|c| !c.is_ascii() && (self.family.has_glyph(*c) || procedural::supports(*c)))
+            .filter(|c| matches!(c.width(), Some(1 | 2)))
+            .partition(|c| c.width() == Some(1));
+        let ids = GlyphIds::assign(&narrow, &wide)?;
+
+        let mut texture = AtlasTexture::new(metrics.padded_cell_size(), ids.max_base_id());
+        let mut glyphs = Vec::new();
+        for style in FontStyle::ALL {
+            let (face, synthetic) = self.family.face(style);
+            let raster = Rasterizer {
+                face,
+                synthetic,
+                metrics: &metrics,
+            };
+
+            for (id, c, columns) in ids.iter() {
+                let coverage = raster.rasterize(c, columns);
+                for column in 0..columns {
+                    let slot_id = (id + column as u16) | style.style_mask();
+                    texture.blit(slot_id, &coverage, columns, column);
+                }
+                let slot_id = id | style.style_mask();
+                glyphs.push(Glyph::new_with_id(
+                    id,
+                    c.encode_utf8(&mut [0; 4]),
+                    style,
+                    texture.pixel_coords(slot_id),
+                ));
+            }
+        }
+
+        Ok(FontAtlasData {
+            font_name: self.family.name().into(),
+            font_size: self.font_size,
+            max_halfwidth_base_glyph_id: ids.max_halfwidth_base_glyph_id,
+            texture_dimensions: texture.dimensions,
+            cell_size: metrics.padded_cell_size(),
+            underline: metrics.underline,
+            strikethrough: metrics.strikethrough,
+            glyphs,
+            texture_data: texture.data,
+        })
+    }
+
+    /// Rasterizes the glyphs and uploads them to a texture.
+    ///
+    /// Characters outside the glyph set are rasterized on demand, from the
+    /// family if it has them and from the fallback fonts otherwise.
+    pub fn load(self, gl: &glow::Context) -> Result<RuntimeAtlas, Error> {
+        let (data, fonts) = self.rasterize()?;
+        let inner = FallbackAtlas::with_fonts(gl, data, fonts)?;
+        Ok(RuntimeAtlas {
+            builder: self,
+            inner,
+        })
+    }
+
+    /// Builds the atlas data along with the fonts rasterizing glyphs on demand.
+    fn rasterize(&self) -> Result<(FontAtlasData, Vec<FallbackFont>), Error> {
+        let data = self.build()?;
+        let fonts = self.fonts(&data)?;
+        Ok((data, fonts))
+    }
+
+    /// Returns the chain of fonts rasterizing glyphs on demand into the
+    /// atlas `data`: the family at the size of the atlas, then the fallbacks.
+    fn fonts(&self, data: &FontAtlasData) -> Result<Vec<FallbackFont>, Error> {
+        let metrics = Metrics::new(self.family.regular(), self.font_size * self.pixel_ratio)?;
+        let family = FallbackFont {
+            family: self.family.clone(),
+            metrics,
+        };
+        let fallbacks = self
+            .fallbacks
+            .iter()
+            .map(|family| FallbackFont::fit(family.clone(), data));
+        std::iter::once(Ok(family)).chain(fallbacks).collect()
+    }
+}
+
+/// Cell metrics of a face at a pixel size.
+struct Metrics {
+    px_per_em: f32,
+    cell_size: (i32, i32),
+    /// Baseline offset from the top of the cell, in pixels.
+    baseline: f32,
+    underline: LineDecoration,
+    strikethrough: LineDecoration,
+}
+
+impl Metrics {
+    fn new(face: &FontVec, px_per_em: f32) -> Result<Self, Error> {
+        let scaled = face.as_scaled(face_scale(face, px_per_em)?);
+        let width = scaled.h_advance(face.glyph_id('M')).round().max(1.0);
+        let height = (scaled.ascent() - scaled.descent() + scaled.line_gap())
+            .round()
+            .max(1.0);
+        let baseline = (scaled.ascent() + scaled.line_gap() / 2.0).round();
+
+        let thickness = (px_per_em / 14.0).round().max(1.0);
+        let underline_center = (baseline - scaled.descent() / 2.0).min(height - thickness);
+        // halfway up the x-height, which is about half the ascent if unavailable
+        let x_height = face
+            .outline_glyph(
+                face.glyph_id('x')
+                    .with_scale_and_position(scaled.scale(), point(0.0, baseline)),
+            )
+            .map(|outline| baseline - outline.px_bounds().min.y)
+            .unwrap_or(scaled.ascent() / 2.0);
+        let strikethrough_center = baseline - x_height / 2.0;
+
+        Ok(Self {
+            px_per_em,
+            cell_size: (width as i32, height as i32),
+            baseline,
+            underline: LineDecoration::new(underline_center / height, thickness / height),
+            strikethrough: LineDecoration::new(strikethrough_center / height, thickness / height),
+        })
+    }
+
+    /// Scales `face` to fill the height of cells of `cell_size`, for glyphs
+    /// borrowed from another font than the one the cells were sized for.
+    fn fit(
+        face: &FontVec,
+        cell_size: (i32, i32),
+        underline: LineDecoration,
+        strikethrough: LineDecoration,
+    ) -> Result<Self, Error> {
+        let units_per_em = face
+            .units_per_em()
+            .ok_or_else(|| Error::Font("font has no units per em".to_string()))?;
+        let px_per_em = cell_size.1 as f32 * units_per_em / face.height_unscaled();
+        let scaled = face.as_scaled(face_scale(face, px_per_em)?);
+        Ok(Self {
+            px_per_em,
+            cell_size,
+            baseline: scaled.ascent().round(),
+            underline,
+            strikethrough,
+        })
+    }
+
+    fn padded_cell_size(&self) -> (i32, i32) {
+        let (width, height) = self.cell_size;
+        (
+            width + 2 * FontAtlasData::PADDING,
+            height + 2 * FontAtlasData::PADDING,
+        )
+    }
+}
+
+/// Returns the scale drawing `face` at `px_per_em` pixels per em.
+fn face_scale(face: &FontVec, px_per_em: f32) -> Result<PxScale, Error> {
+    let units_per_em = face
+        .units_per_em()
+        .ok_or_else(|| Error::Font("font has no units per em".to_string()))?;
+    Ok(PxScale::from(
+        px_per_em * face.height_unscaled() / units_per_em,
+    ))
+}
+
+/// Draws the glyphs of one face into coverage bitmaps of whole cells.
+struct Rasterizer<'a> {
+    face: &'a FontVec,
+    synthetic: Synthetic,
+    metrics: &'a Metrics,
+}
+
+impl Rasterizer<'_> {
+    /// Returns the coverage of `c` centered in `columns` cells, row by row.
+    ///
+    /// Box drawing, block elements, braille and powerline separators are
+    /// drawn procedurally to fill the cell, the same in every style. Icons
+    /// are centered in the cells rather than set on the baseline.
+    fn rasterize(&self, c: char, columns: i32) -> Vec<u8> {
+        if let Some(coverage) = procedural::draw(c, self.metrics.cell_size) {
+            return coverage;
+        }
+        let (cell_width, height) = self.metrics.cell_size;
+        let width = cell_width * columns;
+        let mut coverage = vec![0u8; (width * height) as usize];
+        let baseline = self.metrics.baseline;
+
+        let Ok(mut scale) = face_scale(self.face, self.metrics.px_per_em) else {
+            return coverage;
+        };
+        let id = self.face.glyph_id(c);
+        let glyph = if is_icon(c) {
+            self.fit_icon(id, scale, (width, height))
+        } else {
+            let mut advance = self.face.as_scaled(scale).h_advance(id);
+            if advance > width as f32 + 1.0 {
+                // shrink glyphs of proportional fonts that would be clipped
+                let shrink = width as f32 / advance;
+                scale = PxScale {
+                    x: scale.x * shrink,
+                    y: scale.y * shrink,
+                };
+                advance = width as f32;
+            }
+            let x = ((width as f32 - advance) / 2.0).max(0.0);
+            id.with_scale_and_position(scale, point(x, baseline))
+        };
+        self.draw(glyph, &mut coverage, width);
+        self.embolden(&mut coverage, width);
+        coverage
+    }
+
+    /// Returns the coverage of shaped glyphs drawn over `columns` cells,
+    /// each at the cell of the first character it was shaped from.
+    fn rasterize_run(&self, glyphs: &[ShapedGlyph], columns: i32) -> Vec<u8> {
+        let (cell_width, height) = self.metrics.cell_size;
+        let width = cell_width * columns;
+        let mut coverage = vec![0u8; (width * height) as usize];
+        let Ok(scale) = face_scale(self.face, self.metrics.px_per_em) else {
+            return coverage;
+        };
+
+        for glyph in glyphs {
+            let x = (glyph.cluster as i32 * cell_width) as f32;
+            let position = point(x, self.metrics.baseline);
+            self.draw(
+                GlyphId(glyph.id).with_scale_and_position(scale, position),
+                &mut coverage,
+                width,
+            );
+        }
+        self.embolden(&mut coverage, width);
+        coverage
+    }
+
+    /// Draws the outline of `glyph` into a coverage bitmap `width` pixels
+    /// wide, slanted if italics are synthesized.
+    fn draw(&self, glyph: ab_glyph::Glyph, coverage: &mut [u8], width: i32) {
+        let Some(outline) = self.face.outline_glyph(glyph) else {
+            return;
+        };
+
+        let height = coverage.len() as i32 / width;
+        let baseline = self.metrics.baseline;
+        let bounds = outline.px_bounds();
+        outline.draw(|gx, gy, value| {
+            let py = bounds.min.y as i32 + gy as i32;
+            let mut px = bounds.min.x as i32 + gx as i32;
+            if self.synthetic.italic {
+                px += ((baseline - py as f32) * SYNTHETIC_SLANT).round() as i32;
+            }
+            if (0..width).contains(&px) && (0..height).contains(&py) {
+                let pixel = &mut coverage[(py * width + px) as usize];
+                *pixel = (*pixel).max((value.clamp(0.0, 1.0) * 255.0).round() as u8);
+            }
+        });
+    }
+
+    /// Smears the coverage one pixel to the right if bold is synthesized.
+    fn embolden(&self, coverage: &mut [u8], width: i32) {
+        if self.synthetic.bold {
+            for row in coverage.chunks_mut(width as usize) {
+                for x in (1..row.len()).rev() {
+                    row[x] = row[x].max(row[x - 1]);
+                }
+            }
+        }
+    }
+
+    /// Centers the ink of an icon in its cells, scaled down to fit it whole
+    /// instead of being clipped at the cell edges.
+    fn fit_icon(
+        &self,
+        id: GlyphId,
+        scale: PxScale,
+        (width, height): (i32, i32),
+    ) -> ab_glyph::Glyph {
+        let Some(bounds) = self.face.outline(id).map(|outline| outline.bounds) else {
+            return id.with_scale(scale);
+        };
+        let factor = self.face.as_scaled(scale).scale_factor();
+        let ink_width = (bounds.max.x - bounds.min.x) * factor.horizontal;
+        // the top of the outline is in `min.y`, up from the baseline
+        let ink_height = (bounds.min.y - bounds.max.y).abs() * factor.vertical;
+        let fit = (width as f32 / ink_width)
+            .min(height as f32 / ink_height)
+            .min(1.0);
+
+        let x = width as f32 / 2.0 - (bounds.min.x + bounds.max.x) / 2.0 * factor.horizontal * fit;
+        let y = height as f32 / 2.0 + (bounds.min.y + bounds.max.y) / 2.0 * factor.vertical * fit;
+        let scale = PxScale {
+            x: scale.x * fit,
+            y: scale.y * fit,
+        };
+        id.with_scale_and_position(scale, point(x, y))
+    }
+}
+
+/// Returns true for private-use characters, such as Nerd Font icons, which
+/// are fit into their cells rather than set on the baseline.
+fn is_icon(c: char) -> bool {
+    matches!(c, '\u{e000}'..='\u{f8ff}' | '\u{f0000}'..='\u{10fffd}')
+}
+
+/// Base glyph ids of the non-ASCII characters of an atlas.
+///
+/// ASCII characters use their code point as id. Other single-width
+/// characters take the free ids below and after ASCII, and double-width
+/// characters take pairs of ids at the end of the id space, starting at
+/// `max_halfwidth_base_glyph_id`.
+#[derive(Debug, PartialEq)]
+struct GlyphIds {
+    narrow: Vec<(u16, char)>,
+    wide: Vec<(u16, char)>,
+    max_halfwidth_base_glyph_id: u16,
+}
+
+impl GlyphIds {
+    fn assign(narrow: &[char], wide: &[char]) -> Result<Self, Error> {
+        let wide_ids = 2 * wide.len();
+        let capacity = (BASE_GLYPH_IDS as usize - 0x60).saturating_sub(wide_ids);
+        if narrow.len() > capacity || wide_ids > BASE_GLYPH_IDS as usize - 0x80 {
+            return Err(Error::Font(format!(
+                "{} glyphs exceed the atlas capacity",
+                narrow.len() + wide.len()
+            )));
+        }
+
+        let max_halfwidth_base_glyph_id = BASE_GLYPH_IDS - wide_ids as u16;
+        let free_ids = (0..0x20).chain(0x80..max_halfwidth_base_glyph_id);
+        Ok(Self {
+            narrow: free_ids.zip(narrow.iter().copied()).collect(),
+            wide: (max_halfwidth_base_glyph_id..)
+                .step_by(2)
+                .zip(wide.iter().copied())
+                .collect(),
+            max_halfwidth_base_glyph_id,
+        })
+    }
+
+    /// Returns every glyph as its base id, character and width in cells.
+    fn iter(&self) -> impl Iterator<Item = (u16, char, i32)> + '_ {
+        let ascii = (' '..='~').map(|c| (c as u16, c, 1));
+        let narrow = self.narrow.iter().map(|&(id, c)| (id, c, 1));
+        let wide = self.wide.iter().map(|&(id, c)| (id, c, 2));
+        ascii.chain(narrow).chain(wide)
+    }
+
+    fn max_base_id(&self) -> u16 {
+        self.iter()
+            .map(|(id, _, columns)| id + columns as u16 - 1)
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+/// The RGBA texture array of an atlas: layers of 32 glyphs stacked vertically.
+struct AtlasTexture {
+    dimensions: (i32, i32, i32),
+    data: Vec<u8>,
+}
+
+impl AtlasTexture {
+    fn new(padded_cell_size: (i32, i32), max_base_id: u16) -> Self {
+        let (width, cell_height) = padded_cell_size;
+        let height = cell_height * GLYPHS_PER_LAYER;
+        let max_id = (max_base_id | FontStyle::BoldItalic.style_mask()) as i32;
+        let layers = max_id / GLYPHS_PER_LAYER + 1;
+        Self {
+            dimensions: (width, height, layers),
+            data: vec![0; (width * height * layers * 4) as usize],
+        }
+    }
+
+    /// Returns the position of the unpadded glyph within its layer.
+    fn pixel_coords(&self, id: u16) -> (i32, i32) {
+        let cell_height = self.dimensions.1 / GLYPHS_PER_LAYER;
+        let slot = id as i32 % GLYPHS_PER_LAYER;
+        (
+            FontAtlasData::PADDING,
+            slot * cell_height + FontAtlasData::PADDING,
+        )
+    }
+
+    /// Copies `column` of a coverage bitmap `columns` cells wide into the
+    /// slot of `id`, as white with the coverage as alpha.
+    fn blit(&mut self, id: u16, coverage: &[u8], columns: i32, column: i32) {
+        let (width, height, _) = self.dimensions;
+        let cell_width = width - 2 * FontAtlasData::PADDING;
+        let source_width = cell_width * columns;
+        let layer = id as i32 / GLYPHS_PER_LAYER;
+        let (x0, y0) = self.pixel_coords(id);
+
+        for (y, row) in coverage.chunks(source_width as usize).enumerate() {
+            let cells = &row[(column * cell_width) as usize..][..cell_width as usize];
+            for (x, &alpha) in cells.iter().enumerate() {
+                let offset = ((layer * height + y0 + y as i32) * width + x0 + x as i32) * 4;
+                let offset = offset as usize;
+                self.data[offset..offset + 4].copy_from_slice(&[255, 255, 255, alpha]);
+            }
+        }
+    }
+}
+
+/// A font atlas rasterized at runtime with an [`AtlasBuilder`].
+///
+/// Prebuilt atlases are scaled by whole factors on HiDPI displays; a runtime
+/// atlas is rasterized again at the exact pixel ratio instead, so its cell
+/// size is in physical pixels. Characters outside the glyph set are
+/// rasterized on demand as by a [`FallbackAtlas`].
+#[derive(Debug)]
+pub struct RuntimeAtlas {
+    builder: AtlasBuilder,
+    inner: FallbackAtlas,
+}
+
+impl RuntimeAtlas {
+    /// Returns the builder the atlas was rasterized with.
+    pub fn builder(&self) -> &AtlasBuilder {
+        &self.builder
+    }
+
+    /// Returns the number of glyphs rasterized on demand.
+    pub fn dynamic_glyph_count(&self) -> usize {
+        self.inner.dynamic_glyph_count()
+    }
+}
+
+impl Atlas for RuntimeAtlas {
+    fn get_glyph_id(&self, key: &str, style_bits: u16) -> Option<u16> {
+        self.inner.get_glyph_id(key, style_bits)
+    }
+
+    fn get_base_glyph_id(&self, key: &str) -> Option<u16> {
+        self.inner.get_base_glyph_id(key)
+    }
+
+    fn cell_size(&self) -> (i32, i32) {
+        self.inner.cell_size()
+    }
+
+    fn bind(&self, gl: &glow::Context) {
+        self.inner.bind(gl);
+    }
+
+    fn underline(&self) -> LineDecoration {
+        self.inner.underline()
+    }
+
+    fn strikethrough(&self) -> LineDecoration {
+        self.inner.strikethrough()
+    }
+
+    fn get_symbol(&self, glyph_id: u16) -> Option<CompactString> {
+        self.inner.get_symbol(glyph_id)
+    }
+
+    fn get_ascii_char(&self, glyph_id: u16) -> Option<char> {
+        self.inner.get_ascii_char(glyph_id)
+    }
+
+    fn glyph_tracker(&self) -> &GlyphTracker {
+        self.inner.glyph_tracker()
+    }
+
+    fn glyph_count(&self) -> u32 {
+        self.inner.glyph_count()
+    }
+
+    fn flush(&self, gl: &glow::Context) -> Result<(), BeamtermError> {
+        self.inner.flush(gl)
+    }
+
+    fn recreate_texture(&mut self, gl: &glow::Context) -> Result<(), BeamtermError> {
+        self.inner.recreate_texture(gl)
+    }
+
+    fn for_each_symbol(&self, f: &mut dyn FnMut(u16, &str)) {
+        self.inner.for_each_symbol(f);
+    }
+
+    /// Resolves the column symbols of ligatures, see
+    /// [`BeamtermBackend::set_ligatures`](crate::BeamtermBackend::set_ligatures),
+    /// to slices of the ligature drawn with the family.
+    fn resolve_glyph_slot(&self, key: &str, style_bits: u16) -> Option<GlyphSlot> {
+        match ligature::parse_column_key(key) {
+            Some((text, column)) => self.inner.resolve_ligature(key, text, column, style_bits),
+            None => self.inner.resolve_glyph_slot(key, style_bits),
+        }
+    }
+
+    fn base_lookup_mask(&self) -> u32 {
+        self.inner.base_lookup_mask()
+    }
+
+    fn delete(&self, gl: &glow::Context) {
+        self.inner.delete(gl);
+    }
+
+    /// Rasterizes the glyphs again if the pixel ratio changed, including
+    /// those rasterized on demand. Glyph ids stay the same.
+    fn update_pixel_ratio(
+        &mut self,
+        gl: &glow::Context,
+        pixel_ratio: f32,
+    ) -> Result<f32, BeamtermError> {
+        if pixel_ratio != self.builder.pixel_ratio {
+            let builder = self.builder.clone().with_pixel_ratio(pixel_ratio);
+            let (data, fonts) = builder
+                .rasterize()
+                .map_err(|e| BeamtermError::Data(e.to_string()))?;
+            self.inner.reload(gl, data, fonts)?;
+            self.builder = builder;
+        }
+        Ok(pixel_ratio)
+    }
+
+    fn cell_scale_for_dpr(&self, _pixel_ratio: f32) -> f32 {
+        1.0
+    }
+
+    fn texture_cell_size(&self) -> (i32, i32) {
+        self.inner.texture_cell_size()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A monospace font of rectangles with `->` and `fi` ligatures, a
+    /// contextual `==` and a color bitmap for U+1F600; see its generator.
+    const TEST_FONT: &[u8] = include_bytes!("../tests/fixtures/fonts/ratbeam-test.ttf");
+
+    fn test_family() -> FontFamily {
+        FontFamily::from_bytes("ratbeam-test", TEST_FONT.to_vec()).unwrap()
+    }
+
+    #[test]
+    fn build_sizes_cells_and_assigns_ids_from_the_font() {
+        let builder = AtlasBuilder::new(Rc::new(test_family()), 20.0)
+            .with_glyphs(GlyphSet::ascii().with_chars("é😀ñ"));
+        let data = builder.build().unwrap();
+
+        // 600 by 1000 units at 20 pixels per em
+        let padding = 2 * FontAtlasData::PADDING;
+        assert_eq!(data.cell_size, (12 + padding, 20 + padding));
+        let id = |symbol: &str| {
+            data.glyphs
+                .iter()
+                .find(|glyph| glyph.symbol == symbol && glyph.style == FontStyle::Normal)
+                .map(|glyph| glyph.id)
+        };
+        assert_eq!(id("-"), Some('-' as u16));
+        assert_eq!(id("é"), Some(0));
+        assert_eq!(id("😀"), Some(BASE_GLYPH_IDS - 2));
+        assert_eq!(id("ñ"), None);
+        assert_eq!(data.max_halfwidth_base_glyph_id, BASE_GLYPH_IDS - 2);
+    }
+
+    #[test]
+    fn ligatures_span_the_characters_they_replace() {
+        let family = test_family();
+        assert_eq!(family.ligatures(FontStyle::Normal, &['-', '>']), vec![0..2]);
+
+        let text: Vec<char> = "-> == fi -".chars().collect();
+        let expected = vec![0..2, 3..5, 6..8];
+        assert_eq!(family.ligatures(FontStyle::Italic, &text), expected);
+    }
+
+    #[test]
+    fn glyph_set_chars_are_sorted_and_unique() {
+        let set = GlyphSet::ascii()
+            .with_chars("─a─")
+            .with_range('\u{2500}'..='\u{2502}');
+        let chars = set.chars();
+        assert_eq!(chars.len(), 95 + 3);
+        assert_eq!(&chars[95..], &['─', '━', '│']);
+        assert!(set.contains('━') && !set.contains('\u{7f}'));
+    }
+
+    #[test]
+    fn nerd_font_preset_fits_an_atlas() {
+        let set = GlyphSet::nerd_font().with_set(&GlyphSet::box_drawing());
+        assert!(set.contains('\u{e0b0}') && set.contains('\u{f300}'));
+
+        let narrow: Vec<char> = set.chars().into_iter().filter(|c| !c.is_ascii()).collect();
+        assert!(GlyphIds::assign(&narrow, &[]).is_ok());
+    }
+
+    #[test]
+    fn glyph_ids_skip_ascii_and_pair_wide_glyphs() {
+        let narrow: Vec<char> = ('\u{a0}'..='\u{ff}').collect();
+        let ids = GlyphIds::assign(&narrow, &['中', '文']).unwrap();
+
+        assert_eq!(ids.narrow[0], (0x00, '\u{a0}'));
+        assert_eq!(ids.narrow[31], (0x1f, '\u{bf}'));
+        assert_eq!(ids.narrow[32], (0x80, '\u{c0}'));
+        assert_eq!(ids.max_halfwidth_base_glyph_id, 1020);
+        assert_eq!(ids.wide, vec![(1020, '中'), (1022, '文')]);
+        assert_eq!(ids.max_base_id(), 1023);
+    }
+
+    #[test]
+    fn glyph_ids_reject_overflow() {
+        let narrow: Vec<char> = ('\u{100}'..='\u{500}').collect();
+        assert!(GlyphIds::assign(&narrow, &[]).is_err());
+    }
+
+    #[test]
+    fn texture_blit_places_glyph_in_its_slot() {
+        let mut texture = AtlasTexture::new((4, 5), 'A' as u16);
+        assert_eq!(texture.dimensions, (4, 5 * 32, 0xC41 / 32 + 1));
+
+        // 2x3 cell, fully covered
+        texture.blit('A' as u16, &[255; 6], 1, 0);
+        let (x, y) = texture.pixel_coords('A' as u16);
+        assert_eq!((x, y), (1, 6));
+        let layer = 'A' as i32 / 32;
+        let offset = (((layer * 160 + y) * 4 + x) * 4) as usize;
+        assert_eq!(&texture.data[offset..offset + 4], &[255, 255, 255, 255]);
+        assert_eq!(texture.data[offset - 1], 0);
+    }
+}
@@ -0,0 +1,234 @@
+use std::io::Cursor;
+
+use ab_glyph::{Font, FontVec, GlyphId, GlyphImageFormat};
+use png::{ColorType, Transformations};
+
+use super::ligature;
+
+/// A color bitmap of a glyph, as rows of RGBA pixels with straight alpha.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct ColorImage {
+    pub(super) width: usize,
+    pub(super) height: usize,
+    pub(super) pixels: Vec<u8>,
+}
+
+/// Returns the glyph `face` draws `key` with: that of its character, or
+/// the one its emoji sequence composes into. A trailing emoji variation
+/// selector is dropped if the face has no glyph for the whole sequence.
+pub(super) fn glyph(face: &FontVec, key: &str) -> Option<GlyphId> {
+    let chars: Vec<char> = key.chars().collect();
+    let glyph = |chars: &[char]| match chars {
+        [c] => Some(face.glyph_id(*c)).filter(|id| id.0 != 0),
+        _ => ligature::cluster_glyph(face.as_slice(), chars).map(GlyphId),
+    };
+    match chars.split_last() {
+        Some((&'\u{fe0f}', rest)) if !rest.is_empty() => glyph(&chars).or_else(|| glyph(rest)),
+        _ => glyph(&chars),
+    }
+}
+
+/// Returns true if `face` draws glyph `id` as a color bitmap, as emoji
+/// fonts with CBDT or sbix tables do.
+pub(super) fn has_image(face: &FontVec, id: GlyphId) -> bool {
+    face.glyph_raster_image2(id, u16::MAX)
+        .is_some_and(|image| is_color(image.format))
+}
+
+/// Decodes the color bitmap of glyph `id` from the strike of `face`
+/// closest to `pixel_size` pixels per em.
+pub(super) fn image(face: &FontVec, id: GlyphId, pixel_size: u16) -> Option<ColorImage> {
+    let image = face.glyph_raster_image2(id, pixel_size)?;
+    match image.format {
+        GlyphImageFormat::Png => decode_png(image.data),
+        GlyphImageFormat::BitmapPremulBgra32 => {
+            from_premultiplied_bgra(image.data, image.width.into(), image.height.into())
+        }
+        _ => None,
+    }
+}
+
+fn is_color(format: GlyphImageFormat) -> bool {
+    matches!(
+        format,
+        GlyphImageFormat::Png | GlyphImageFormat::BitmapPremulBgra32
+    )
+}
+
+/// Scales `image` to fit `width` by `height` pixels, centered, and returns
+/// the RGBA pixels. Each pixel averages the image pixels it covers.
+pub(super) fn fit(image: &ColorImage, (width, height): (usize, usize)) -> Vec<u8> {
+    let mut pixels = vec![0; width * height * 4];
+    if image.width == 0 || image.height == 0 {
+        return pixels;
+    }
+
+    let scale = (width as f32 / image.width as f32).min(height as f32 / image.height as f32);
+    let x0 = (width as f32 - image.width as f32 * scale) / 2.0;
+    let y0 = (height as f32 - image.height as f32 * scale) / 2.0;
+    // image pixels covered by output pixel `i` offset by `origin`
+    let span = |i: usize, origin: f32, size: usize| {
+        let start = (((i as f32 - origin) / scale).floor().max(0.0) as usize).min(size - 1);
+        let end = (((i as f32 + 1.0 - origin) / scale).ceil() as usize).clamp(start + 1, size);
+        start..end
+    };
+
+    for y in 0..height {
+        let center_y = (y as f32 + 0.5 - y0) / scale;
+        if !(0.0..image.height as f32).contains(&center_y) {
+            continue;
+        }
+        let rows = span(y, y0, image.height);
+        for x in 0..width {
+            let center_x = (x as f32 + 0.5 - x0) / scale;
+            if !(0.0..image.width as f32).contains(&center_x) {
+                continue;
+            }
+
+            // average with premultiplied alpha, so transparent pixels don't
+            // darken the edges
+            let mut sum = [0u32; 4];
+            let mut count = 0;
+            for sy in rows.clone() {
+                for sx in span(x, x0, image.width) {
+                    let pixel = &image.pixels[(sy * image.width + sx) * 4..][..4];
+                    let alpha = pixel[3] as u32;
+                    for channel in 0..3 {
+                        sum[channel] += pixel[channel] as u32 * alpha;
+                    }
+                    sum[3] += alpha;
+                    count += 1;
+                }
+            }
+            if sum[3] == 0 {
+                continue;
+            }
+            let out = &mut pixels[(y * width + x) * 4..][..4];
+            for channel in 0..3 {
+                out[channel] = (sum[channel] / sum[3]) as u8;
+            }
+            out[3] = (sum[3] / count) as u8;
+        }
+    }
+    pixels
+}
+
+fn decode_png(data: &[u8]) -> Option<ColorImage> {
+    let mut decoder = png::Decoder::new(Cursor::new(data));
+    decoder.set_transformations(Transformations::normalize_to_color8());
+    let mut reader = decoder.read_info().ok()?;
+    let mut buffer = vec![0; reader.output_buffer_size()?];
+    let info = reader.next_frame(&mut buffer).ok()?;
+    let buffer = &buffer[..info.buffer_size()];
+
+    let pixels = match info.color_type {
+        ColorType::Rgba => buffer.to_vec(),
+        ColorType::Rgb => buffer
+            .chunks(3)
+            .flat_map(|rgb| [rgb[0], rgb[1], rgb[2], 255])
+            .collect(),
+        ColorType::GrayscaleAlpha => buffer
+            .chunks(2)
+            .flat_map(|ga| [ga[0], ga[0], ga[0], ga[1]])
+            .collect(),
+        ColorType::Grayscale => buffer.iter().flat_map(|&g| [g, g, g, 255]).collect(),
+        // expanded to RGB by the transformations
+        ColorType::Indexed => return None,
+    };
+    Some(ColorImage {
+        width: info.width as usize,
+        height: info.height as usize,
+        pixels,
+    })
+}
+
+fn from_premultiplied_bgra(data: &[u8], width: usize, height: usize) -> Option<ColorImage> {
+    let data = data.get(..width * height * 4)?;
+    let pixels = data
+        .chunks(4)
+        .flat_map(|bgra| {
+            let alpha = bgra[3] as u32;
+            let straight = |c: u8| match alpha {
+                0 => 0,
+                _ => (c as u32 * 255 / alpha).min(255) as u8,
+            };
+            [
+                straight(bgra[2]),
+                straight(bgra[1]),
+                straight(bgra[0]),
+                bgra[3],
+            ]
+        })
+        .collect();
+    Some(ColorImage {
+        width,
+        height,
+        pixels,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Draws U+1F600 as a 4x4 red PNG in an sbix strike.
+    const TEST_FONT: &[u8] = include_bytes!("../../tests/fixtures/fonts/ratbeam-test.ttf");
+
+    #[test]
+    fn sbix_glyphs_decode_to_their_bitmaps() {
+        let face = FontVec::try_from_vec(TEST_FONT.to_vec()).unwrap();
+        let id = glyph(&face, "😀").unwrap();
+        assert_eq!(glyph(&face, "😀\u{fe0f}"), Some(id));
+        assert!(has_image(&face, id));
+        assert!(!has_image(&face, face.glyph_id('-')));
+
+        let image = image(&face, id, 20).unwrap();
+        assert_eq!((image.width, image.height), (4, 4));
+        assert!(
+            image
+                .pixels
+                .chunks(4)
+                .all(|pixel| pixel == [255, 0, 0, 255])
+        );
+    }
+
+    #[test]
+    fn png_images_decode_to_rgba() {
+        let mut data = Vec::new();
+        let mut encoder = png::Encoder::new(&mut data, 2, 1);
+        encoder.set_color(ColorType::Rgb);
+        let mut writer = encoder.write_header().unwrap();
+        writer.write_image_data(&[255, 0, 0, 0, 0, 255]).unwrap();
+        writer.finish().unwrap();
+
+        let image = decode_png(&data).unwrap();
+        assert_eq!((image.width, image.height), (2, 1));
+        assert_eq!(image.pixels, vec![255, 0, 0, 255, 0, 0, 255, 255]);
+    }
+
+    #[test]
+    fn premultiplied_bgra_is_made_straight() {
+        let image = from_premultiplied_bgra(&[0, 64, 128, 128, 0, 0, 0, 0], 2, 1).unwrap();
+        assert_eq!(image.pixels, vec![255, 127, 0, 128, 0, 0, 0, 0]);
+        assert_eq!(from_premultiplied_bgra(&[0; 4], 2, 1), None);
+    }
+
+    #[test]
+    fn images_are_averaged_into_the_center() {
+        // 4x4 opaque red with a transparent left half, into 4x2 pixels
+        let pixels = (0..16)
+            .flat_map(|i| if i % 4 < 2 { [0; 4] } else { [255, 0, 0, 255] })
+            .collect();
+        let image = ColorImage {
+            width: 4,
+            height: 4,
+            pixels,
+        };
+        let fitted = fit(&image, (4, 2));
+
+        let alpha: Vec<u8> = fitted.chunks(4).map(|pixel| pixel[3]).collect();
+        // the image is 2x2 in the middle columns; its left half is clear
+        assert_eq!(alpha, vec![0, 0, 255, 0, 0, 0, 255, 0]);
+        assert_eq!(&fitted[8..12], &[255, 0, 0, 255]);
+    }
+}
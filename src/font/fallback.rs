@@ -0,0 +1,903 @@
+use std::cell::RefCell;
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+use std::mem;
+use std::ops::Range;
+use std::rc::Rc;
+
+use ab_glyph::GlyphId;
+
+use beamterm_core::gl::{RasterizedGlyph, Texture};
+use beamterm_core::{Atlas, Error as BeamtermError, GlyphSlot, GlyphTracker};
+use beamterm_data::{FontAtlasData, FontStyle, Glyph, LineDecoration};
+use compact_str::{CompactString, ToCompactString};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+use super::{
+    BASE_GLYPH_IDS, FontFamily, GLYPHS_PER_LAYER, Metrics, Rasterizer, color, ligature, procedural,
+};
+use crate::error::Error;
+
+/// Glyph ids after the emoji of the atlas data for color glyphs rasterized
+/// on demand; each takes two.
+const DYNAMIC_EMOJI_IDS: u16 = 512;
+
+/// A font atlas that rasterizes glyphs missing from its atlas data on demand.
+///
+/// Symbols without a glyph in the [`FontAtlasData`] are drawn with the first
+/// font of a fallback chain that has them, into base glyph ids the data
+/// leaves unused, and uploaded before the next frame is rendered. Symbols no
+/// font has, or that arrive once the free ids run out, are recorded in the
+/// [`GlyphTracker`] as with a [`StaticFontAtlas`](beamterm_core::StaticFontAtlas):
+///
+/// ```no_run
+/// # use std::rc::Rc;
+/// # use beamterm_core::FontAtlasData;
+/// # use ratbeam::{FallbackAtlas, FontFamily};
+/// # fn atlas(gl: &glow::Context) -> Result<(), ratbeam::Error> {
+/// let symbols = FontFamily::load("NotoSansSymbols2-Regular.ttf")?;
+/// let cjk = FontFamily::load("NotoSansCJK-Regular.ttc")?;
+/// let atlas = FallbackAtlas::load(
+///     gl,
+///     FontAtlasData::default(),
+///     vec![Rc::new(symbols), Rc::new(cjk)],
+/// )?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// Fallback glyphs are scaled to the height of a cell, and shrunk to fit
+/// its width when necessary. Color emoji from fonts with CBDT or sbix
+/// bitmaps, such as Noto Color Emoji, are drawn in color over two cells;
+/// emoji sequences the font composes, such as flags, included. Box drawing, block elements, braille and
+/// powerline separators are drawn procedurally to fill the cell instead,
+/// replacing those of the atlas data, so that lines and blocks join without
+/// gaps.
+pub struct FallbackAtlas {
+    texture: Texture,
+    /// Retained atlas data for context loss recovery.
+    data: FontAtlasData,
+    fonts: Vec<FallbackFont>,
+    glyphs: RefCell<GlyphTable>,
+    glyph_tracker: GlyphTracker,
+}
+
+/// A font of the fallback chain, scaled to the cells of the atlas.
+pub(super) struct FallbackFont {
+    pub(super) family: Rc<FontFamily>,
+    pub(super) metrics: Metrics,
+}
+
+impl FallbackFont {
+    /// Scales `family` to fill cells of the atlas `data`.
+    pub(super) fn fit(family: Rc<FontFamily>, data: &FontAtlasData) -> Result<Self, Error> {
+        let (width, height) = data.cell_size;
+        let cell_size = (
+            width - 2 * FontAtlasData::PADDING,
+            height - 2 * FontAtlasData::PADDING,
+        );
+        let metrics = Metrics::fit(
+            family.regular(),
+            cell_size,
+            data.underline,
+            data.strikethrough,
+        )?;
+        Ok(Self { family, metrics })
+    }
+}
+
+impl FallbackAtlas {
+    /// Uploads `data` and rasterizes the symbols it lacks from `fallbacks`,
+    /// trying the fonts in order.
+    pub fn load(
+        gl: &glow::Context,
+        data: FontAtlasData,
+        fallbacks: Vec<Rc<FontFamily>>,
+    ) -> Result<Self, Error> {
+        let fonts = fallbacks
+            .into_iter()
+            .map(|family| FallbackFont::fit(family, &data))
+            .collect::<Result<_, _>>()?;
+        let mut atlas = Self::with_fonts(gl, data, fonts)?;
+        atlas.glyphs.get_mut().redraw_procedural();
+        Ok(atlas)
+    }
+
+    pub(super) fn with_fonts(
+        gl: &glow::Context,
+        data: FontAtlasData,
+        fonts: Vec<FallbackFont>,
+    ) -> Result<Self, Error> {
+        let data = with_dynamic_layers(data);
+        let texture = Texture::from_font_atlas_data(gl, glow::RGBA, &data)?;
+        Ok(Self {
+            texture,
+            glyphs: RefCell::new(GlyphTable::new(&data)),
+            data,
+            fonts,
+            glyph_tracker: GlyphTracker::new(),
+        })
+    }
+
+    /// Replaces the atlas data and fonts with ones rasterized at another
+    /// size, keeping the ids of all glyphs. The glyphs of `data` must be
+    /// those of the current data.
+    pub(super) fn reload(
+        &mut self,
+        gl: &glow::Context,
+        data: FontAtlasData,
+        fonts: Vec<FallbackFont>,
+    ) -> Result<(), BeamtermError> {
+        let data = with_dynamic_layers(data);
+        let texture = Texture::from_font_atlas_data(gl, glow::RGBA, &data)?;
+        self.texture.delete(gl);
+        self.texture = texture;
+        self.data = data;
+        self.fonts = fonts;
+        self.glyphs.get_mut().invalidate();
+        Ok(())
+    }
+
+    /// Returns the number of glyphs rasterized on demand, or drawn
+    /// procedurally in place of those of the atlas data.
+    pub fn dynamic_glyph_count(&self) -> usize {
+        self.glyphs.borrow().dynamic.len()
+    }
+
+    /// Returns the number of base glyph ids still free for glyphs
+    /// rasterized on demand; double-width glyphs take two. Color emoji have
+    /// ids of their own.
+    pub fn free_glyph_ids(&self) -> usize {
+        self.glyphs.borrow().free.len()
+    }
+
+    /// Assigns an id to `key` if it is drawn procedurally or a font of the
+    /// chain has its glyph.
+    fn assign(&self, key: &str) -> Option<GlyphSlot> {
+        if let Some((font, glyph)) = self.color_glyph(key) {
+            let columns = if key.width() == 2 { 2 } else { 1 };
+            let source = GlyphSource::Color { font, glyph };
+            return self.glyphs.borrow_mut().insert_color(key, columns, source);
+        }
+
+        let mut chars = key.chars();
+        let (Some(c), None) = (chars.next(), chars.next()) else {
+            return None; // multi-character graphemes are left to the atlas data
+        };
+        let columns = match c.width() {
+            Some(1) => 1,
+            Some(2) => 2,
+            _ => return None,
+        };
+        let source = if procedural::supports(c) {
+            GlyphSource::Procedural
+        } else {
+            GlyphSource::Font(
+                self.fonts
+                    .iter()
+                    .position(|font| font.family.has_glyph(c))?,
+            )
+        };
+        self.glyphs.borrow_mut().insert(c, columns, source)
+    }
+
+    /// Returns the index of the first font of the chain with a glyph for
+    /// `key`, and the glyph, if that font draws it as a color bitmap.
+    fn color_glyph(&self, key: &str) -> Option<(usize, GlyphId)> {
+        let (index, glyph) =
+            self.fonts.iter().enumerate().find_map(|(index, font)| {
+                Some((index, color::glyph(font.family.regular(), key)?))
+            })?;
+        color::has_image(self.fonts[index].family.regular(), glyph).then_some((index, glyph))
+    }
+
+    /// Resolves the symbol `key` of `column` of the ligature `text`, drawn
+    /// with the first font of the chain. Once the free ids run out, the
+    /// character of the column is resolved on its own instead.
+    pub(super) fn resolve_ligature(
+        &self,
+        key: &str,
+        text: &str,
+        column: usize,
+        style_bits: u16,
+    ) -> Option<GlyphSlot> {
+        let c = text.chars().nth(column)?;
+        let known = self.glyphs.borrow().slots.get(key).copied();
+        match known.or_else(|| self.glyphs.borrow_mut().insert_ligature(key, c)) {
+            Some(slot) => Some(slot.with_styling(style_bits)),
+            None => self.resolve_glyph_slot(c.encode_utf8(&mut [0; 4]), style_bits),
+        }
+    }
+
+    /// Rasterizes a glyph in every style and uploads it to its slots.
+    fn upload(&self, gl: &glow::Context, glyph: DynamicGlyph) -> Result<(), BeamtermError> {
+        let font = match glyph.source {
+            GlyphSource::Font(index) => &self.fonts[index],
+            GlyphSource::Procedural => return self.upload_procedural(gl, glyph),
+            GlyphSource::Ligature => return self.upload_ligature(gl, glyph),
+            GlyphSource::Color { font, glyph: id } => {
+                return self.upload_color(gl, glyph, &self.fonts[font], id);
+            }
+        };
+        for style in FontStyle::ALL {
+            let (face, synthetic) = font.family.face(style);
+            let raster = Rasterizer {
+                face,
+                synthetic,
+                metrics: &font.metrics,
+            };
+            let coverage = raster.rasterize(glyph.c, glyph.columns);
+            for column in 0..glyph.columns {
+                let pixels = cell_pixels(&coverage, font.metrics.cell_size, glyph.columns, column);
+                let id = (glyph.id + column as u16) | style.style_mask();
+                self.texture
+                    .upload_glyph(gl, id, self.data.cell_size, &pixels)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Draws a glyph procedurally, the same in every style.
+    fn upload_procedural(
+        &self,
+        gl: &glow::Context,
+        glyph: DynamicGlyph,
+    ) -> Result<(), BeamtermError> {
+        let coverage = procedural::draw(glyph.c, self.cell_size()).unwrap_or_default();
+        let pixels = cell_pixels(&coverage, self.cell_size(), 1, 0);
+        for style in FontStyle::ALL {
+            self.texture.upload_glyph(
+                gl,
+                glyph.id | style.style_mask(),
+                self.data.cell_size,
+                &pixels,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Draws a color glyph scaled to fit its cells: in color into its emoji
+    /// ids if double-width, and otherwise as a silhouette in the text color,
+    /// the same in every style.
+    fn upload_color(
+        &self,
+        gl: &glow::Context,
+        glyph: DynamicGlyph,
+        font: &FallbackFont,
+        id: GlyphId,
+    ) -> Result<(), BeamtermError> {
+        let (cell_width, height) = self.cell_size();
+        let width = cell_width * glyph.columns;
+        let pixel_size = u16::try_from(2 * height).unwrap_or(u16::MAX);
+        let rgba = color::image(font.family.regular(), id, pixel_size)
+            .map(|image| color::fit(&image, (width as usize, height as usize)))
+            .unwrap_or_else(|| vec![0; (width * height * 4) as usize]);
+
+        if glyph.id & Glyph::EMOJI_FLAG != 0 {
+            for column in 0..glyph.columns {
+                let pixels = rgba_cell_pixels(&rgba, self.cell_size(), glyph.columns, column);
+                let id = glyph.id + column as u16;
+                self.texture
+                    .upload_glyph(gl, id, self.data.cell_size, &pixels)?;
+            }
+        } else {
+            let coverage: Vec<u8> = rgba.chunks(4).map(|pixel| pixel[3]).collect();
+            let pixels = cell_pixels(&coverage, self.cell_size(), 1, 0);
+            for style in FontStyle::ALL {
+                self.texture.upload_glyph(
+                    gl,
+                    glyph.id | style.style_mask(),
+                    self.data.cell_size,
+                    &pixels,
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Shapes the ligature of a column symbol in every style and uploads
+    /// the slice of its column.
+    fn upload_ligature(
+        &self,
+        gl: &glow::Context,
+        glyph: DynamicGlyph,
+    ) -> Result<(), BeamtermError> {
+        let symbol = self.glyphs.borrow().symbols.get(&glyph.id).cloned();
+        let Some((text, column)) = symbol.as_deref().and_then(ligature::parse_column_key) else {
+            return Ok(());
+        };
+        let Some(font) = self.fonts.first() else {
+            return Ok(());
+        };
+
+        let chars: Vec<char> = text.chars().collect();
+        let columns = chars.len() as i32;
+        for style in FontStyle::ALL {
+            let (face, synthetic) = font.family.face(style);
+            let raster = Rasterizer {
+                face,
+                synthetic,
+                metrics: &font.metrics,
+            };
+            let glyphs = ligature::shape(face.as_slice(), &chars);
+            let coverage = raster.rasterize_run(&glyphs, columns);
+            let pixels = cell_pixels(&coverage, font.metrics.cell_size, columns, column as i32);
+            self.texture.upload_glyph(
+                gl,
+                glyph.id | style.style_mask(),
+                self.data.cell_size,
+                &pixels,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for FallbackAtlas {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let fonts: Vec<_> = self.fonts.iter().map(|font| font.family.name()).collect();
+        f.debug_struct("FallbackAtlas")
+            .field("font_name", &self.data.font_name)
+            .field("fallbacks", &fonts)
+            .field("dynamic_glyph_count", &self.dynamic_glyph_count())
+            .finish_non_exhaustive()
+    }
+}
+
+impl Atlas for FallbackAtlas {
+    fn get_glyph_id(&self, key: &str, style_bits: u16) -> Option<u16> {
+        let base_id = self.get_base_glyph_id(key)?;
+        Some(base_id | style_bits)
+    }
+
+    fn get_base_glyph_id(&self, key: &str) -> Option<u16> {
+        self.resolve_glyph_slot(key, 0).map(|slot| slot.slot_id())
+    }
+
+    fn cell_size(&self) -> (i32, i32) {
+        let (width, height) = self.data.cell_size;
+        (
+            width - 2 * FontAtlasData::PADDING,
+            height - 2 * FontAtlasData::PADDING,
+        )
+    }
+
+    fn bind(&self, gl: &glow::Context) {
+        self.texture.bind(gl);
+    }
+
+    fn underline(&self) -> LineDecoration {
+        self.data.underline
+    }
+
+    fn strikethrough(&self) -> LineDecoration {
+        self.data.strikethrough
+    }
+
+    fn get_symbol(&self, glyph_id: u16) -> Option<CompactString> {
+        let base_id = if glyph_id & Glyph::EMOJI_FLAG != 0 {
+            glyph_id & Glyph::GLYPH_ID_EMOJI_MASK
+        } else {
+            glyph_id & Glyph::GLYPH_ID_MASK
+        };
+
+        if let Some(c) = ascii_char(base_id) {
+            return Some(c.to_compact_string());
+        }
+        let symbol = self.glyphs.borrow().symbols.get(&base_id).cloned()?;
+        match ligature::parse_column_key(&symbol) {
+            // ligature columns copy as their own character
+            Some((text, column)) => text.chars().nth(column).map(|c| c.to_compact_string()),
+            None => Some(symbol),
+        }
+    }
+
+    fn get_ascii_char(&self, glyph_id: u16) -> Option<char> {
+        ascii_char(glyph_id)
+    }
+
+    fn glyph_tracker(&self) -> &GlyphTracker {
+        &self.glyph_tracker
+    }
+
+    fn glyph_count(&self) -> u32 {
+        0x60 + self.glyphs.borrow().symbols.len() as u32
+    }
+
+    /// Uploads the glyphs assigned since the last flush.
+    fn flush(&self, gl: &glow::Context) -> Result<(), BeamtermError> {
+        let pending = mem::take(&mut self.glyphs.borrow_mut().pending);
+        for index in pending {
+            let glyph = self.glyphs.borrow().dynamic[index];
+            self.upload(gl, glyph)?;
+        }
+        Ok(())
+    }
+
+    /// Recreates the texture from the retained atlas data; glyphs
+    /// rasterized on demand are uploaded again on the next flush.
+    fn recreate_texture(&mut self, gl: &glow::Context) -> Result<(), BeamtermError> {
+        self.texture.delete(gl);
+        self.texture = Texture::from_font_atlas_data(gl, glow::RGBA, &self.data)?;
+        self.glyphs.get_mut().invalidate();
+        Ok(())
+    }
+
+    fn for_each_symbol(&self, f: &mut dyn FnMut(u16, &str)) {
+        for c in ' '..='\u{7f}' {
+            f(c as u16, c.encode_utf8(&mut [0; 4]));
+        }
+        let glyphs = self.glyphs.borrow();
+        let symbols = glyphs
+            .symbols
+            .iter()
+            .filter(|(_, symbol)| ligature::parse_column_key(symbol).is_none());
+        for (id, symbol) in symbols {
+            f(*id, symbol);
+        }
+    }
+
+    fn resolve_glyph_slot(&self, key: &str, style_bits: u16) -> Option<GlyphSlot> {
+        if let Some(id) = ascii_id(key) {
+            return Some(GlyphSlot::Normal(id | style_bits));
+        }
+
+        let known = self.glyphs.borrow().slots.get(key).copied();
+        match known.or_else(|| self.assign(key)) {
+            Some(slot) => Some(with_styling(slot, style_bits)),
+            None => {
+                self.glyph_tracker.record_missing(key);
+                None
+            }
+        }
+    }
+
+    fn base_lookup_mask(&self) -> u32 {
+        Glyph::GLYPH_ID_EMOJI_MASK as u32
+    }
+
+    fn delete(&self, gl: &glow::Context) {
+        self.texture.delete(gl);
+    }
+
+    fn update_pixel_ratio(
+        &mut self,
+        _gl: &glow::Context,
+        pixel_ratio: f32,
+    ) -> Result<f32, BeamtermError> {
+        Ok(pixel_ratio)
+    }
+
+    fn cell_scale_for_dpr(&self, pixel_ratio: f32) -> f32 {
+        // the scaling of prebuilt atlases, see StaticFontAtlas
+        if pixel_ratio <= 0.5 {
+            0.5
+        } else {
+            pixel_ratio.round().max(1.0)
+        }
+    }
+
+    fn texture_cell_size(&self) -> (i32, i32) {
+        self.cell_size()
+    }
+}
+
+/// A glyph rasterized on demand.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct DynamicGlyph {
+    id: u16,
+    /// The character, or that of the column of a ligature.
+    c: char,
+    columns: i32,
+    source: GlyphSource,
+}
+
+/// What a glyph rasterized on demand is drawn with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum GlyphSource {
+    /// The font at this index of the fallback chain.
+    Font(usize),
+    Procedural,
+    /// One column of a ligature shaped by the first font of the chain; the
+    /// ligature is encoded in the symbol of the glyph.
+    Ligature,
+    /// A color bitmap glyph of the font at `font` of the fallback chain.
+    Color {
+        font: usize,
+        glyph: GlyphId,
+    },
+}
+
+/// The base glyph ids of the non-ASCII symbols of an atlas, both those of
+/// the atlas data and those assigned on demand.
+struct GlyphTable {
+    slots: HashMap<CompactString, GlyphSlot>,
+    symbols: HashMap<u16, CompactString>,
+    /// Base ids used by neither ASCII nor the atlas data.
+    free: BTreeSet<u16>,
+    /// First ids of the free pairs of emoji ids.
+    free_emoji: BTreeSet<u16>,
+    dynamic: Vec<DynamicGlyph>,
+    /// Indices of the dynamic glyphs still to be uploaded.
+    pending: Vec<usize>,
+}
+
+impl GlyphTable {
+    fn new(data: &FontAtlasData) -> Self {
+        let mut slots = HashMap::new();
+        let mut symbols = HashMap::new();
+        let glyphs = data
+            .glyphs
+            .iter()
+            .filter(|glyph| glyph.style == FontStyle::Normal && !glyph.is_ascii());
+        for glyph in glyphs {
+            let slot = if glyph.id & Glyph::EMOJI_FLAG != 0 {
+                GlyphSlot::Emoji(glyph.id)
+            } else if glyph.id >= data.max_halfwidth_base_glyph_id {
+                GlyphSlot::Wide(glyph.id)
+            } else {
+                GlyphSlot::Normal(glyph.id)
+            };
+            slots.insert(glyph.symbol.clone(), slot);
+            symbols.insert(glyph.id, glyph.symbol.clone());
+        }
+
+        let free = (0..data.max_halfwidth_base_glyph_id.min(BASE_GLYPH_IDS))
+            .filter(|id| ascii_char(*id).is_none() && !symbols.contains_key(id))
+            .collect();
+        Self {
+            slots,
+            symbols,
+            free,
+            free_emoji: dynamic_emoji_ids(data).step_by(2).collect(),
+            dynamic: Vec::new(),
+            pending: Vec::new(),
+        }
+    }
+
+    /// Assigns free ids to `c`: the lowest one to single-width glyphs, and
+    /// the highest free pair to double-width glyphs.
+    fn insert(&mut self, c: char, columns: i32, source: GlyphSource) -> Option<GlyphSlot> {
+        let slot = if columns == 1 {
+            GlyphSlot::Normal(self.free.pop_first()?)
+        } else {
+            let id = self
+                .free
+                .iter()
+                .rev()
+                .find(|&&id| id > 0 && self.free.contains(&(id - 1)))?
+                - 1;
+            self.free.remove(&id);
+            self.free.remove(&(id + 1));
+            GlyphSlot::Wide(id)
+        };
+
+        self.push(c.to_compact_string(), slot, c, columns, source);
+        Some(slot)
+    }
+
+    /// Assigns ids to the color glyph of `key`: the lowest free pair of
+    /// emoji ids if double-width, and the lowest free id otherwise.
+    fn insert_color(&mut self, key: &str, columns: i32, source: GlyphSource) -> Option<GlyphSlot> {
+        let slot = if columns == 2 {
+            GlyphSlot::Emoji(self.free_emoji.pop_first()?)
+        } else {
+            GlyphSlot::Normal(self.free.pop_first()?)
+        };
+        let c = key.chars().next()?;
+        self.push(key.into(), slot, c, columns, source);
+        Some(slot)
+    }
+
+    /// Assigns the lowest free id to a column symbol of a ligature.
+    fn insert_ligature(&mut self, key: &str, c: char) -> Option<GlyphSlot> {
+        let slot = GlyphSlot::Normal(self.free.pop_first()?);
+        self.push(key.into(), slot, c, 1, GlyphSource::Ligature);
+        Some(slot)
+    }
+
+    fn push(
+        &mut self,
+        symbol: CompactString,
+        slot: GlyphSlot,
+        c: char,
+        columns: i32,
+        source: GlyphSource,
+    ) {
+        let id = slot.slot_id();
+        self.slots.insert(symbol.clone(), slot);
+        self.symbols.insert(id, symbol);
+        self.pending.push(self.dynamic.len());
+        self.dynamic.push(DynamicGlyph {
+            id,
+            c,
+            columns,
+            source,
+        });
+    }
+
+    /// Queues the glyphs of the atlas data that are drawn procedurally, to
+    /// replace them in their slots.
+    fn redraw_procedural(&mut self) {
+        let mut redrawn: Vec<(u16, char)> = self
+            .symbols
+            .iter()
+            .filter_map(|(id, symbol)| {
+                let mut chars = symbol.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) if procedural::supports(c) => Some((*id, c)),
+                    _ => None,
+                }
+            })
+            .collect();
+        redrawn.sort_unstable();
+
+        for (id, c) in redrawn {
+            self.pending.push(self.dynamic.len());
+            self.dynamic.push(DynamicGlyph {
+                id,
+                c,
+                columns: 1,
+                source: GlyphSource::Procedural,
+            });
+        }
+    }
+
+    /// Marks every dynamic glyph for upload, after the texture was replaced.
+    fn invalidate(&mut self) {
+        self.pending = (0..self.dynamic.len()).collect();
+    }
+}
+
+/// Returns the glyph id of a printable ASCII symbol, which is its code point.
+fn ascii_id(key: &str) -> Option<u16> {
+    match key.as_bytes() {
+        [byte] if byte.is_ascii() => Some(*byte as u16),
+        _ => None,
+    }
+}
+
+fn ascii_char(id: u16) -> Option<char> {
+    (0x20..0x80).contains(&id).then_some(id as u8 as char)
+}
+
+/// Applies `style_bits` to a resolved slot. Emoji are drawn the same in
+/// every font style, so they only take the underline and strikethrough.
+fn with_styling(slot: GlyphSlot, style_bits: u16) -> GlyphSlot {
+    match slot {
+        GlyphSlot::Emoji(_) => slot.with_styling(style_bits & !FontStyle::MASK),
+        _ => slot.with_styling(style_bits),
+    }
+}
+
+/// Returns the emoji ids for color glyphs rasterized on demand, those
+/// following the emoji of `data`.
+fn dynamic_emoji_ids(data: &FontAtlasData) -> Range<u16> {
+    let start = data
+        .glyphs
+        .iter()
+        .filter(|glyph| glyph.id & Glyph::EMOJI_FLAG != 0)
+        .map(|glyph| (glyph.id & Glyph::GLYPH_ID_EMOJI_MASK) + 2)
+        .max()
+        .unwrap_or(Glyph::EMOJI_FLAG);
+    let end = (start + DYNAMIC_EMOJI_IDS).min(Glyph::GLYPH_ID_EMOJI_MASK + 1);
+    start..end
+}
+
+/// Grows the texture of `data` to hold every base id in every style, and
+/// the emoji ids for color glyphs.
+fn with_dynamic_layers(mut data: FontAtlasData) -> FontAtlasData {
+    let (width, height, layers) = data.texture_dimensions;
+    let max_id = ((BASE_GLYPH_IDS - 1) | FontStyle::BoldItalic.style_mask())
+        .max(dynamic_emoji_ids(&data).end - 1);
+    let required = max_id as i32 / GLYPHS_PER_LAYER + 1;
+    if layers < required {
+        data.texture_data
+            .resize((width * height * required * 4) as usize, 0);
+        data.texture_dimensions = (width, height, required);
+    }
+    data
+}
+
+/// Returns `column` of a coverage bitmap `columns` cells wide as a padded
+/// cell, white with the coverage as alpha.
+fn cell_pixels(
+    coverage: &[u8],
+    cell_size: (i32, i32),
+    columns: i32,
+    column: i32,
+) -> RasterizedGlyph {
+    let rgba: Vec<u8> = coverage
+        .iter()
+        .flat_map(|&alpha| [255, 255, 255, alpha])
+        .collect();
+    rgba_cell_pixels(&rgba, cell_size, columns, column)
+}
+
+/// Returns `column` of RGBA pixels `columns` cells wide as a padded cell.
+fn rgba_cell_pixels(
+    rgba: &[u8],
+    cell_size: (i32, i32),
+    columns: i32,
+    column: i32,
+) -> RasterizedGlyph {
+    let (cell_width, cell_height) = cell_size;
+    let width = cell_width + 2 * FontAtlasData::PADDING;
+    let height = cell_height + 2 * FontAtlasData::PADDING;
+    let mut pixels = vec![0; (width * height * 4) as usize];
+
+    let rows = rgba.chunks((cell_width * columns * 4) as usize);
+    for (y, row) in rows.enumerate() {
+        let cells = &row[(column * cell_width * 4) as usize..][..(cell_width * 4) as usize];
+        let offset = ((y as i32 + FontAtlasData::PADDING) * width + FontAtlasData::PADDING) * 4;
+        pixels[offset as usize..][..cells.len()].copy_from_slice(cells);
+    }
+    RasterizedGlyph::new(pixels, width as u32, height as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn glyph_table_frees_ids_unused_by_the_data() {
+        let data = FontAtlasData::default();
+        let table = GlyphTable::new(&data);
+
+        assert_eq!(
+            table.slots.get("─"),
+            Some(&GlyphSlot::Normal(table_id(&data, "─")))
+        );
+        assert!(table.free.iter().all(|id| ascii_char(*id).is_none()));
+        assert!(table.free.iter().all(|id| !table.symbols.contains_key(id)));
+        assert!(
+            table
+                .free
+                .iter()
+                .all(|id| *id < data.max_halfwidth_base_glyph_id)
+        );
+    }
+
+    #[test]
+    fn glyph_table_assigns_narrow_low_and_wide_high() {
+        let mut data = FontAtlasData::default();
+        data.glyphs.clear();
+        data.max_halfwidth_base_glyph_id = 0x100;
+        let mut table = GlyphTable::new(&data);
+        assert_eq!(table.free.len(), 0x100 - 0x60);
+
+        assert_eq!(
+            table.insert('ж', 1, GlyphSource::Font(0)),
+            Some(GlyphSlot::Normal(0))
+        );
+        assert_eq!(
+            table.insert('中', 2, GlyphSource::Font(1)),
+            Some(GlyphSlot::Wide(0xfe))
+        );
+        assert_eq!(table.symbols.get(&0xfe).map(|s| s.as_str()), Some("中"));
+        assert_eq!(table.pending, vec![0, 1]);
+        assert_eq!(table.free.len(), 0x100 - 0x60 - 3);
+
+        table.pending.clear();
+        table.invalidate();
+        assert_eq!(table.pending, vec![0, 1]);
+    }
+
+    #[test]
+    fn glyph_table_runs_out_of_ids() {
+        let mut data = FontAtlasData::default();
+        data.glyphs.clear();
+        data.max_halfwidth_base_glyph_id = 0x21;
+        let mut table = GlyphTable::new(&data);
+
+        // ids 0..0x20 are free; a pair never spans the ASCII range
+        assert_eq!(
+            table.insert('中', 2, GlyphSource::Font(0)),
+            Some(GlyphSlot::Wide(0x1e))
+        );
+        while table.insert('ж', 1, GlyphSource::Font(0)).is_some() {}
+        assert!(table.free.is_empty());
+        assert_eq!(table.insert('中', 2, GlyphSource::Font(0)), None);
+    }
+
+    #[test]
+    fn glyph_table_redraws_procedural_glyphs_of_the_data() {
+        let data = FontAtlasData::default();
+        let mut table = GlyphTable::new(&data);
+        table.redraw_procedural();
+
+        let line = table.dynamic.iter().find(|glyph| glyph.c == '─').unwrap();
+        assert_eq!(line.id, table_id(&data, "─"));
+        assert_eq!(line.source, GlyphSource::Procedural);
+        assert!(
+            table
+                .dynamic
+                .iter()
+                .all(|glyph| procedural::supports(glyph.c))
+        );
+        assert_eq!(table.pending.len(), table.dynamic.len());
+    }
+
+    #[test]
+    fn glyph_table_keys_ligature_columns_by_symbol() {
+        let mut data = FontAtlasData::default();
+        data.glyphs.clear();
+        data.max_halfwidth_base_glyph_id = 0x100;
+        let mut table = GlyphTable::new(&data);
+
+        let key = ligature::column_key("->", 1);
+        assert_eq!(table.insert_ligature(&key, '>'), Some(GlyphSlot::Normal(0)));
+        assert_eq!(table.slots.get(key.as_str()), Some(&GlyphSlot::Normal(0)));
+        assert_eq!(table.dynamic[0].c, '>');
+        assert_eq!(table.dynamic[0].source, GlyphSource::Ligature);
+        assert_eq!(table.pending, vec![0]);
+    }
+
+    #[test]
+    fn glyph_table_assigns_emoji_after_those_of_the_data() {
+        let data = FontAtlasData::default();
+        let start = dynamic_emoji_ids(&data).start;
+        assert!(data.glyphs.iter().all(|glyph| glyph.id & 0x1fff < start));
+
+        let mut data = FontAtlasData::default();
+        data.glyphs.clear();
+        data.max_halfwidth_base_glyph_id = 0x100;
+        let mut table = GlyphTable::new(&data);
+        let source = GlyphSource::Color {
+            font: 0,
+            glyph: GlyphId(1),
+        };
+        assert_eq!(
+            table.insert_color("🫠", 2, source),
+            Some(GlyphSlot::Emoji(0x1000))
+        );
+        assert_eq!(
+            table.insert_color("🇳🇴", 2, source),
+            Some(GlyphSlot::Emoji(0x1002))
+        );
+        assert_eq!(
+            table.insert_color("☺", 1, source),
+            Some(GlyphSlot::Normal(0))
+        );
+        assert_eq!(table.symbols.get(&0x1002).map(|s| s.as_str()), Some("🇳🇴"));
+        assert_eq!(table.pending, vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn emoji_ids_fit_the_texture_and_ignore_font_styles() {
+        let data = with_dynamic_layers(FontAtlasData::default());
+        let ids = dynamic_emoji_ids(&data);
+        assert_eq!(ids.len(), DYNAMIC_EMOJI_IDS as usize);
+        assert!((ids.end as i32 - 1) / GLYPHS_PER_LAYER < data.texture_dimensions.2);
+
+        let bold_underline = FontStyle::Bold.style_mask() | 1 << 13;
+        assert_eq!(
+            with_styling(GlyphSlot::Emoji(ids.start), bold_underline),
+            GlyphSlot::Emoji(ids.start | 1 << 13)
+        );
+        assert_eq!(
+            with_styling(GlyphSlot::Normal(0x80), bold_underline),
+            GlyphSlot::Normal(0x80 | bold_underline)
+        );
+    }
+
+    #[test]
+    fn cell_pixels_pads_one_column() {
+        // two cells of 2x1: left half transparent, right half covered
+        let glyph = cell_pixels(&[0, 0, 255, 128], (2, 1), 2, 1);
+        assert_eq!((glyph.width, glyph.height), (4, 3));
+        assert_eq!(
+            &glyph.pixels[20..28],
+            &[255, 255, 255, 255, 255, 255, 255, 128]
+        );
+        assert_eq!(glyph.pixels[..16], [0; 16]);
+    }
+
+    fn table_id(data: &FontAtlasData, symbol: &str) -> u16 {
+        data.glyphs
+            .iter()
+            .find(|glyph| glyph.symbol == symbol && glyph.style == FontStyle::Normal)
+            .map(|glyph| glyph.id)
+            .unwrap()
+    }
+}
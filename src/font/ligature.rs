@@ -0,0 +1,411 @@
+use std::ops::Range;
+
+use compact_str::{CompactString, format_compact};
+use ttf_parser::gsub::{SingleSubstitution, SubstitutionSubtable};
+use ttf_parser::opentype_layout::{
+    ChainedContextLookup, ContextLookup, Coverage, LayoutTable, SequenceLookupRecord,
+};
+use ttf_parser::{Face, GlyphId, LazyArray16, Tag};
+
+/// GSUB features applied by [`shape`]: contextual alternates, which most
+/// programming fonts build their ligatures from, and standard ligatures.
+const FEATURES: &[&[u8; 4]] = &[b"calt", b"liga", b"clig"];
+/// GSUB features composing emoji sequences, such as flags and ZWJ
+/// sequences, into single glyphs.
+const CLUSTER_FEATURES: &[&[u8; 4]] = &[b"ccmp", b"liga", b"rlig", b"clig"];
+/// Depth of contextual lookups applying further lookups.
+const MAX_NESTING: usize = 4;
+/// Delimits the column of the symbols of ligature columns.
+const KEY_MARKER: char = '\u{1}';
+
+/// A glyph of a shaped run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) struct ShapedGlyph {
+    pub(super) id: u16,
+    /// Index of the first character the glyph was shaped from.
+    pub(super) cluster: usize,
+}
+
+/// Shapes `text` with the ligature features of the font in `data`.
+///
+/// This is the shaping of monospace programming fonts, not a general
+/// shaper: single, ligature and contextual substitutions are applied for
+/// Latin text, ignoring lookup flags, and glyphs keep the advance of their
+/// cells.
+pub(super) fn shape(data: &[u8], text: &[char]) -> Vec<ShapedGlyph> {
+    match Face::parse(data, 0) {
+        Ok(face) => shape_face(&face, text, FEATURES),
+        Err(_) => Vec::new(),
+    }
+}
+
+/// Returns the ranges of `text` the font in `data` draws as ligatures of
+/// two or more characters.
+pub(super) fn ligatures(data: &[u8], text: &[char]) -> Vec<Range<usize>> {
+    let Ok(face) = Face::parse(data, 0) else {
+        return Vec::new();
+    };
+    let glyphs = shape_face(&face, text, FEATURES);
+
+    let mut substituted = vec![false; text.len()];
+    for (i, glyph) in glyphs.iter().enumerate() {
+        let end = glyphs.get(i + 1).map_or(text.len(), |next| next.cluster);
+        let nominal = face.glyph_index(text[glyph.cluster]).map(|id| id.0);
+        if end - glyph.cluster > 1 || nominal != Some(glyph.id) {
+            substituted[glyph.cluster..end].fill(true);
+        }
+    }
+
+    let mut ranges: Vec<Range<usize>> = Vec::new();
+    for (i, _) in substituted.iter().enumerate().filter(|(_, s)| **s) {
+        match ranges.last_mut() {
+            Some(range) if range.end == i => range.end = i + 1,
+            _ => ranges.push(i..i + 1),
+        }
+    }
+    ranges.retain(|range| range.len() > 1);
+    ranges
+}
+
+/// Returns the glyph the font in `data` composes all of `text` into, such
+/// as the glyph of an emoji sequence.
+pub(super) fn cluster_glyph(data: &[u8], text: &[char]) -> Option<u16> {
+    let face = Face::parse(data, 0).ok()?;
+    match shape_face(&face, text, CLUSTER_FEATURES)[..] {
+        [glyph] if glyph.id != 0 => Some(glyph.id),
+        _ => None,
+    }
+}
+
+/// Returns the symbol of column `column` of the ligature `text`, which the
+/// atlas of the font draws as its slice of the shaped text.
+pub(crate) fn column_key(text: &str, column: usize) -> CompactString {
+    format_compact!("{KEY_MARKER}{column}{KEY_MARKER}{text}")
+}
+
+/// Returns the ligature text and column of a symbol made by [`column_key`].
+pub(crate) fn parse_column_key(key: &str) -> Option<(&str, usize)> {
+    let (column, text) = key.strip_prefix(KEY_MARKER)?.split_once(KEY_MARKER)?;
+    Some((text, column.parse().ok()?))
+}
+
+fn shape_face(face: &Face, text: &[char], features: &[&[u8; 4]]) -> Vec<ShapedGlyph> {
+    let mut glyphs: Vec<ShapedGlyph> = text
+        .iter()
+        .enumerate()
+        .map(|(cluster, &c)| ShapedGlyph {
+            id: face.glyph_index(c).map_or(0, |id| id.0),
+            cluster,
+        })
+        .collect();
+
+    if let Some(table) = face.tables().gsub {
+        let shaper = Shaper { table };
+        for lookup in shaper.lookups(features) {
+            let mut position = 0;
+            while position < glyphs.len() {
+                position += shaper.apply(lookup, &mut glyphs, position, 0).unwrap_or(1);
+            }
+        }
+    }
+    glyphs
+}
+
+struct Shaper<'a> {
+    table: LayoutTable<'a>,
+}
+
+impl Shaper<'_> {
+    /// Returns the lookups of `features` for the default language of the
+    /// Latin script, or of the default script if the font has none, in the
+    /// order they apply.
+    fn lookups(&self, features: &[&[u8; 4]]) -> Vec<u16> {
+        let scripts = &self.table.scripts;
+        let script = [b"latn", b"DFLT"]
+            .into_iter()
+            .find_map(|tag| scripts.find(Tag::from_bytes(tag)))
+            .or_else(|| scripts.get(0));
+        let Some(language) = script.and_then(|script| script.default_language) else {
+            return Vec::new();
+        };
+
+        let mut lookups: Vec<u16> = language
+            .feature_indices
+            .into_iter()
+            .filter_map(|index| self.table.features.get(index))
+            .filter(|feature| {
+                features
+                    .iter()
+                    .any(|tag| feature.tag == Tag::from_bytes(tag))
+            })
+            .flat_map(|feature| feature.lookup_indices)
+            .collect();
+        lookups.sort_unstable();
+        lookups.dedup();
+        lookups
+    }
+
+    /// Applies the first subtable of a lookup that matches at `position`;
+    /// returns the number of glyphs it consumed.
+    fn apply(
+        &self,
+        lookup: u16,
+        glyphs: &mut Vec<ShapedGlyph>,
+        position: usize,
+        depth: usize,
+    ) -> Option<usize> {
+        let lookup = self.table.lookups.get(lookup)?;
+        lookup
+            .subtables
+            .into_iter::<SubstitutionSubtable>()
+            .find_map(|subtable| self.apply_subtable(subtable, glyphs, position, depth))
+    }
+
+    fn apply_subtable(
+        &self,
+        subtable: SubstitutionSubtable,
+        glyphs: &mut Vec<ShapedGlyph>,
+        position: usize,
+        depth: usize,
+    ) -> Option<usize> {
+        let glyph = GlyphId(glyphs[position].id);
+        match subtable {
+            SubstitutionSubtable::Single(single) => {
+                glyphs[position].id = match single {
+                    SingleSubstitution::Format1 { coverage, delta } => {
+                        coverage.get(glyph)?;
+                        glyph.0.wrapping_add_signed(delta)
+                    }
+                    SingleSubstitution::Format2 {
+                        coverage,
+                        substitutes,
+                    } => substitutes.get(coverage.get(glyph)?)?.0,
+                };
+                Some(1)
+            }
+            SubstitutionSubtable::Ligature(ligatures) => {
+                let set = ligatures
+                    .ligature_sets
+                    .get(ligatures.coverage.get(glyph)?)?;
+                let ligature = set.into_iter().find(|ligature| {
+                    match_forward(
+                        glyphs,
+                        position + 1,
+                        ligature.components,
+                        |id, component| id == component.0,
+                    )
+                })?;
+                let components = ligature.components.len() as usize;
+                glyphs[position].id = ligature.glyph.0;
+                glyphs.drain(position + 1..=position + components);
+                Some(1)
+            }
+            SubstitutionSubtable::Context(context) => {
+                let (input, records) = match_context(context, glyphs, position)?;
+                self.apply_records(records, glyphs, position, depth);
+                Some(input)
+            }
+            SubstitutionSubtable::ChainContext(context) => {
+                let (input, records) = match_chain_context(context, glyphs, position)?;
+                self.apply_records(records, glyphs, position, depth);
+                Some(input)
+            }
+            // multiple, alternate and reverse substitutions form no ligatures
+            _ => None,
+        }
+    }
+
+    /// Applies the lookups of a matched context at their positions.
+    fn apply_records(
+        &self,
+        records: LazyArray16<SequenceLookupRecord>,
+        glyphs: &mut Vec<ShapedGlyph>,
+        position: usize,
+        depth: usize,
+    ) {
+        if depth >= MAX_NESTING {
+            return;
+        }
+        for record in records {
+            let at = position + record.sequence_index as usize;
+            if at < glyphs.len() {
+                self.apply(record.lookup_list_index, glyphs, at, depth + 1);
+            }
+        }
+    }
+}
+
+/// Matches a contextual subtable at `position`; returns the length of its
+/// input sequence and the lookups to apply.
+fn match_context<'a>(
+    context: ContextLookup<'a>,
+    glyphs: &[ShapedGlyph],
+    position: usize,
+) -> Option<(usize, LazyArray16<'a, SequenceLookupRecord>)> {
+    let glyph = GlyphId(glyphs[position].id);
+    let index = context.coverage().get(glyph)?;
+    match context {
+        ContextLookup::Format1 { sets, .. } => {
+            let rule = sets.get(index)?.into_iter().find(|rule| {
+                match_forward(glyphs, position + 1, rule.input, |id, value| id == value)
+            })?;
+            Some((rule.input.len() as usize + 1, rule.lookups))
+        }
+        ContextLookup::Format2 { classes, sets, .. } => {
+            let rule = sets.get(classes.get(glyph))?.into_iter().find(|rule| {
+                match_forward(glyphs, position + 1, rule.input, |id, class| {
+                    classes.get(GlyphId(id)) == class
+                })
+            })?;
+            Some((rule.input.len() as usize + 1, rule.lookups))
+        }
+        ContextLookup::Format3 {
+            coverages, lookups, ..
+        } => {
+            let matched = (0..coverages.len())
+                .all(|k| covered(glyphs, Some(position + 1 + k as usize), coverages.get(k)));
+            matched.then_some((coverages.len() as usize + 1, lookups))
+        }
+    }
+}
+
+/// Matches a chained contextual subtable at `position`, including the
+/// glyphs before and after its input sequence.
+fn match_chain_context<'a>(
+    context: ChainedContextLookup<'a>,
+    glyphs: &[ShapedGlyph],
+    position: usize,
+) -> Option<(usize, LazyArray16<'a, SequenceLookupRecord>)> {
+    let glyph = GlyphId(glyphs[position].id);
+    let index = context.coverage().get(glyph)?;
+    match context {
+        ChainedContextLookup::Format1 { sets, .. } => {
+            let same = |id: u16, value: u16| id == value;
+            let rule = sets.get(index)?.into_iter().find(|rule| {
+                let input = rule.input.len() as usize + 1;
+                match_backward(glyphs, position, rule.backtrack, same)
+                    && match_forward(glyphs, position + 1, rule.input, same)
+                    && match_forward(glyphs, position + input, rule.lookahead, same)
+            })?;
+            Some((rule.input.len() as usize + 1, rule.lookups))
+        }
+        ChainedContextLookup::Format2 {
+            backtrack_classes,
+            input_classes,
+            lookahead_classes,
+            sets,
+            ..
+        } => {
+            let rule = sets
+                .get(input_classes.get(glyph))?
+                .into_iter()
+                .find(|rule| {
+                    let input = rule.input.len() as usize + 1;
+                    match_backward(glyphs, position, rule.backtrack, |id, class| {
+                        backtrack_classes.get(GlyphId(id)) == class
+                    }) && match_forward(glyphs, position + 1, rule.input, |id, class| {
+                        input_classes.get(GlyphId(id)) == class
+                    }) && match_forward(glyphs, position + input, rule.lookahead, |id, class| {
+                        lookahead_classes.get(GlyphId(id)) == class
+                    })
+                })?;
+            Some((rule.input.len() as usize + 1, rule.lookups))
+        }
+        ChainedContextLookup::Format3 {
+            backtrack_coverages,
+            input_coverages,
+            lookahead_coverages,
+            lookups,
+            ..
+        } => {
+            let input = input_coverages.len() as usize + 1;
+            let backtrack = (0..backtrack_coverages.len()).all(|k| {
+                let at = position.checked_sub(k as usize + 1);
+                covered(glyphs, at, backtrack_coverages.get(k))
+            });
+            let inputs = (0..input_coverages.len()).all(|k| {
+                let at = Some(position + 1 + k as usize);
+                covered(glyphs, at, input_coverages.get(k))
+            });
+            let lookahead = (0..lookahead_coverages.len()).all(|k| {
+                let at = Some(position + input + k as usize);
+                covered(glyphs, at, lookahead_coverages.get(k))
+            });
+            let matched = backtrack && inputs && lookahead;
+            matched.then_some((input, lookups))
+        }
+    }
+}
+
+/// Returns true if there is a glyph at `at` and `coverage` contains it.
+fn covered(glyphs: &[ShapedGlyph], at: Option<usize>, coverage: Option<Coverage>) -> bool {
+    let glyph = at.and_then(|at| glyphs.get(at));
+    glyph
+        .zip(coverage)
+        .is_some_and(|(glyph, coverage)| coverage.contains(GlyphId(glyph.id)))
+}
+
+/// Returns true if the glyphs from `start` on match `values`.
+fn match_forward<T: ttf_parser::FromData>(
+    glyphs: &[ShapedGlyph],
+    start: usize,
+    values: LazyArray16<T>,
+    matches: impl Fn(u16, T) -> bool,
+) -> bool {
+    values.into_iter().enumerate().all(|(k, value)| {
+        glyphs
+            .get(start + k)
+            .is_some_and(|glyph| matches(glyph.id, value))
+    })
+}
+
+/// Returns true if the glyphs before `end`, nearest first, match `values`.
+fn match_backward(
+    glyphs: &[ShapedGlyph],
+    end: usize,
+    values: LazyArray16<u16>,
+    matches: impl Fn(u16, u16) -> bool,
+) -> bool {
+    values.into_iter().enumerate().all(|(k, value)| {
+        end.checked_sub(k + 1)
+            .is_some_and(|i| matches(glyphs[i].id, value))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Ligates `->` and `fi`, and turns `==` into a start and an end glyph.
+    const TEST_FONT: &[u8] = include_bytes!("../../tests/fixtures/fonts/ratbeam-test.ttf");
+
+    #[test]
+    fn column_keys_round_trip() {
+        let key = column_key("->", 1);
+        assert_eq!(parse_column_key(&key), Some(("->", 1)));
+        assert_eq!(parse_column_key("->"), None);
+        assert_eq!(parse_column_key("\u{1}x\u{1}->"), None);
+    }
+
+    #[test]
+    fn ligatures_and_contextual_alternates_are_shaped() {
+        let glyph = |id, cluster| ShapedGlyph { id, cluster };
+        assert_eq!(shape(TEST_FONT, &['-', '>']), vec![glyph(10, 0)]);
+        assert_eq!(
+            shape(TEST_FONT, &['=', '=']),
+            vec![glyph(12, 0), glyph(13, 1)]
+        );
+        assert_eq!(
+            shape(TEST_FONT, &['=', '-']),
+            vec![glyph(3, 0), glyph(2, 1)]
+        );
+        assert_eq!(cluster_glyph(TEST_FONT, &['f', 'i']), Some(11));
+    }
+
+    #[test]
+    fn fonts_that_fail_to_parse_shape_nothing() {
+        assert_eq!(shape(&[0; 16], &['-', '>']), Vec::new());
+        assert_eq!(ligatures(&[0; 16], &['-', '>']), Vec::<Range<usize>>::new());
+        assert_eq!(cluster_glyph(&[0; 16], &['🇳', '🇴']), None);
+    }
+}
@@ -0,0 +1,569 @@
+/// Line weights of the arms of the box-drawing characters U+2500..U+257F, as
+/// up, right, down and left digits: 0 none, 1 light, 2 heavy and 3 double.
+/// Arcs and diagonals are drawn separately and left blank.
+const BOX_LINES: [&str; 0x80] = [
+    "0101", "0202", "1010", "2020", "0101", "0202", "1010", "2020", // 2500
+    "0101", "0202", "1010", "2020", "0110", "0210", "0120", "0220", // 2508
+    "0011", "0012", "0021", "0022", "1100", "1200", "2100", "2200", // 2510
+    "1001", "1002", "2001", "2002", "1110", "1210", "2110", "1120", // 2518
+    "2120", "2210", "1220", "2220", "1011", "1012", "2011", "1021", // 2520
+    "2021", "2012", "1022", "2022", "0111", "0112", "0211", "0212", // 2528
+    "0121", "0122", "0221", "0222", "1101", "1102", "1201", "1202", // 2530
+    "2101", "2102", "2201", "2202", "1111", "1112", "1211", "1212", // 2538
+    "2111", "1121", "2121", "2112", "2211", "1122", "1221", "2212", // 2540
+    "1222", "2122", "2221", "2222", "0101", "0202", "1010", "2020", // 2548
+    "0303", "3030", "0310", "0130", "0330", "0013", "0031", "0033", // 2550
+    "1300", "3100", "3300", "1003", "3001", "3003", "1310", "3130", // 2558
+    "3330", "1013", "3031", "3033", "0313", "0131", "0333", "1303", // 2560
+    "3101", "3303", "1313", "3131", "3333", "0000", "0000", "0000", // 2568
+    "0000", "0000", "0000", "0000", "0001", "1000", "0100", "0010", // 2570
+    "0002", "2000", "0200", "0020", "0201", "1020", "0102", "2010", // 2578
+];
+
+/// Samples per pixel along each axis when drawing curves and diagonals.
+const SUBSAMPLES: i32 = 4;
+
+/// Returns true if `c` is drawn by [`draw`] rather than from a font: box
+/// drawing (U+2500), block elements (U+2580), braille (U+2800) and the
+/// powerline separators U+E0B0..U+E0B3.
+pub(super) fn supports(c: char) -> bool {
+    matches!(
+        c,
+        '\u{2500}'..='\u{259f}' | '\u{2800}'..='\u{28ff}' | '\u{e0b0}'..='\u{e0b3}'
+    )
+}
+
+/// Returns the coverage of `c` drawn to fill a cell of `cell_size`, row by
+/// row, so that lines and blocks join those of neighbouring cells without
+/// gaps.
+pub(super) fn draw(c: char, cell_size: (i32, i32)) -> Option<Vec<u8>> {
+    let mut canvas = Canvas::new(cell_size);
+    match c {
+        '\u{2500}'..='\u{257f}' => canvas.box_drawing(c),
+        '\u{2580}'..='\u{259f}' => canvas.block(c),
+        '\u{2800}'..='\u{28ff}' => canvas.braille(c as u32 - 0x2800),
+        '\u{e0b0}'..='\u{e0b3}' => canvas.powerline(c),
+        _ => return None,
+    }
+    Some(canvas.coverage)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Weight {
+    None,
+    Light,
+    Heavy,
+    Double,
+}
+
+impl Weight {
+    fn from_digit(digit: u8) -> Self {
+        match digit {
+            b'1' => Self::Light,
+            b'2' => Self::Heavy,
+            b'3' => Self::Double,
+            _ => Self::None,
+        }
+    }
+}
+
+/// The strokes of a line across the cell: two for double lines, otherwise
+/// one, with `first == second`.
+#[derive(Debug, Clone, Copy)]
+struct Strokes {
+    first: i32,
+    second: i32,
+    thickness: i32,
+}
+
+impl Strokes {
+    fn first_end(&self) -> i32 {
+        self.first + self.thickness
+    }
+
+    fn second_end(&self) -> i32 {
+        self.second + self.thickness
+    }
+
+    /// Returns the start of the first stroke if `first`, else of the second.
+    fn start(&self, first: bool) -> i32 {
+        if first { self.first } else { self.second }
+    }
+
+    /// Returns the end of the first stroke if `first`, else of the second.
+    fn end(&self, first: bool) -> i32 {
+        if first {
+            self.first_end()
+        } else {
+            self.second_end()
+        }
+    }
+}
+
+/// A coverage bitmap of one cell.
+struct Canvas {
+    width: i32,
+    height: i32,
+    coverage: Vec<u8>,
+}
+
+impl Canvas {
+    fn new((width, height): (i32, i32)) -> Self {
+        Self {
+            width,
+            height,
+            coverage: vec![0; (width.max(0) * height.max(0)) as usize],
+        }
+    }
+
+    /// Covers the rectangle from `(x0, y0)` to `(x1, y1)`, exclusive, with
+    /// at least `alpha`.
+    fn fill(&mut self, (x0, y0): (i32, i32), (x1, y1): (i32, i32), alpha: u8) {
+        for y in y0.max(0)..y1.min(self.height) {
+            for x in x0.max(0)..x1.min(self.width) {
+                let pixel = &mut self.coverage[(y * self.width + x) as usize];
+                *pixel = (*pixel).max(alpha);
+            }
+        }
+    }
+
+    fn clear(&mut self, (x0, y0): (i32, i32), (x1, y1): (i32, i32)) {
+        for y in y0.max(0)..y1.min(self.height) {
+            for x in x0.max(0)..x1.min(self.width) {
+                self.coverage[(y * self.width + x) as usize] = 0;
+            }
+        }
+    }
+
+    /// Covers each pixel by the fraction of its subsamples inside `shape`.
+    fn fill_shape(&mut self, shape: impl Fn(f32, f32) -> bool) {
+        let step = 1.0 / SUBSAMPLES as f32;
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let mut inside = 0;
+                for sy in 0..SUBSAMPLES {
+                    for sx in 0..SUBSAMPLES {
+                        let px = x as f32 + (sx as f32 + 0.5) * step;
+                        let py = y as f32 + (sy as f32 + 0.5) * step;
+                        inside += shape(px, py) as i32;
+                    }
+                }
+                let alpha = (inside * 255 / (SUBSAMPLES * SUBSAMPLES)) as u8;
+                self.fill((x, y), (x + 1, y + 1), alpha);
+            }
+        }
+    }
+
+    /// Thickness of light lines, growing with the cell width.
+    fn light(&self) -> i32 {
+        (self.width / 8).max(1)
+    }
+
+    /// Returns the strokes of a line of `weight` centered across `length`.
+    fn strokes(&self, weight: Weight, length: i32) -> Strokes {
+        let light = self.light();
+        match weight {
+            Weight::Double => {
+                let first = ((length - 3 * light) / 2).max(0);
+                Strokes {
+                    first,
+                    second: first + 2 * light,
+                    thickness: light,
+                }
+            }
+            _ => {
+                let thickness = if weight == Weight::Heavy {
+                    2 * light
+                } else {
+                    light
+                };
+                let first = (length - thickness) / 2;
+                Strokes {
+                    first,
+                    second: first,
+                    thickness,
+                }
+            }
+        }
+    }
+
+    fn box_drawing(&mut self, c: char) {
+        match c {
+            '╭' => self.arc(1, 1),
+            '╮' => self.arc(-1, 1),
+            '╯' => self.arc(-1, -1),
+            '╰' => self.arc(1, -1),
+            '╱' => self.diagonal(true),
+            '╲' => self.diagonal(false),
+            '╳' => {
+                self.diagonal(true);
+                self.diagonal(false);
+            }
+            _ => {
+                let digits = BOX_LINES[c as usize - 0x2500].as_bytes();
+                self.lines([0, 1, 2, 3].map(|arm| Weight::from_digit(digits[arm])));
+                match c {
+                    '┄' | '┅' => self.dashes(3, true),
+                    '┆' | '┇' => self.dashes(3, false),
+                    '┈' | '┉' => self.dashes(4, true),
+                    '┊' | '┋' => self.dashes(4, false),
+                    '╌' | '╍' => self.dashes(2, true),
+                    '╎' | '╏' => self.dashes(2, false),
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    /// Draws the arms of a box-drawing character from the cell's edges to
+    /// its center, joining them where they meet. The strokes of double
+    /// lines end at the inner or outer stroke of the perpendicular line,
+    /// depending on which arms are present.
+    fn lines(&mut self, [up, right, down, left]: [Weight; 4]) {
+        let (width, height) = (self.width, self.height);
+        let (has_up, has_right, has_down, has_left) = (
+            up != Weight::None,
+            right != Weight::None,
+            down != Weight::None,
+            left != Weight::None,
+        );
+        let vertical = match up.max(down) {
+            Weight::None => left.max(right),
+            weight => weight,
+        };
+        let horizontal = match left.max(right) {
+            Weight::None => up.max(down),
+            weight => weight,
+        };
+        // the strokes of the vertical line across the width, and of the
+        // horizontal line across the height
+        let v = self.strokes(vertical, width);
+        let h = self.strokes(horizontal, height);
+
+        if has_left {
+            let s = self.strokes(left, height);
+            if left == Weight::Double {
+                let end = v.end(has_up);
+                self.fill((0, s.first), (end, s.first_end()), 255);
+                let end = v.end(has_down);
+                self.fill((0, s.second), (end, s.second_end()), 255);
+            } else {
+                let through = has_up && has_down && !has_right;
+                let end = v.end(through);
+                self.fill((0, s.first), (end, s.first_end()), 255);
+            }
+        }
+        if has_right {
+            let s = self.strokes(right, height);
+            if right == Weight::Double {
+                let start = v.start(!has_up);
+                self.fill((start, s.first), (width, s.first_end()), 255);
+                let start = v.start(!has_down);
+                self.fill((start, s.second), (width, s.second_end()), 255);
+            } else {
+                let through = has_up && has_down && !has_left;
+                let start = v.start(!through);
+                self.fill((start, s.first), (width, s.first_end()), 255);
+            }
+        }
+        if has_up {
+            let s = self.strokes(up, width);
+            if up == Weight::Double {
+                let end = h.end(has_left);
+                self.fill((s.first, 0), (s.first_end(), end), 255);
+                let end = h.end(has_right);
+                self.fill((s.second, 0), (s.second_end(), end), 255);
+            } else {
+                let through = has_left && has_right && !has_down;
+                let end = h.end(through);
+                self.fill((s.first, 0), (s.first_end(), end), 255);
+            }
+        }
+        if has_down {
+            let s = self.strokes(down, width);
+            if down == Weight::Double {
+                let start = h.start(!has_left);
+                self.fill((s.first, start), (s.first_end(), height), 255);
+                let start = h.start(!has_right);
+                self.fill((s.second, start), (s.second_end(), height), 255);
+            } else {
+                let through = has_left && has_right && !has_up;
+                let start = h.start(!through);
+                self.fill((s.first, start), (s.first_end(), height), 255);
+            }
+        }
+    }
+
+    /// Cuts a line into `count` dashes, leaving the gap at the end of each
+    /// so that dashes repeat evenly across cells.
+    fn dashes(&mut self, count: i32, horizontal: bool) {
+        let length = if horizontal { self.width } else { self.height };
+        let gap = (length / count / 3).max(1);
+        for i in 1..=count {
+            let end = i * length / count;
+            if horizontal {
+                self.clear((end - gap, 0), (end, self.height));
+            } else {
+                self.clear((0, end - gap), (self.width, end));
+            }
+        }
+    }
+
+    /// Draws a rounded corner whose arms leave the cell to the right
+    /// (`dx = 1`) or left (`dx = -1`), and down (`dy = 1`) or up (`dy = -1`).
+    fn arc(&mut self, dx: i32, dy: i32) {
+        let v = self.strokes(Weight::Light, self.width);
+        let h = self.strokes(Weight::Light, self.height);
+        let half = v.thickness as f32 / 2.0;
+        let (cx, cy) = (v.first as f32 + half, h.first as f32 + half);
+        let radius = if dx > 0 { self.width as f32 - cx } else { cx }.min(if dy > 0 {
+            self.height as f32 - cy
+        } else {
+            cy
+        });
+        let (ox, oy) = (cx + dx as f32 * radius, cy + dy as f32 * radius);
+
+        // the straight remainders of the longer arm
+        if dx > 0 {
+            self.fill(
+                (ox.floor() as i32, h.first),
+                (self.width, h.first_end()),
+                255,
+            );
+        } else {
+            self.fill((0, h.first), (ox.ceil() as i32, h.first_end()), 255);
+        }
+        if dy > 0 {
+            self.fill(
+                (v.first, oy.floor() as i32),
+                (v.first_end(), self.height),
+                255,
+            );
+        } else {
+            self.fill((v.first, 0), (v.first_end(), oy.ceil() as i32), 255);
+        }
+
+        self.fill_shape(|x, y| {
+            let quarter = (x - ox) * dx as f32 <= 0.0 && (y - oy) * dy as f32 <= 0.0;
+            quarter && ((x - ox).hypot(y - oy) - radius).abs() <= half
+        });
+    }
+
+    /// Draws a light diagonal from corner to corner, rising to the right
+    /// if `rising`.
+    fn diagonal(&mut self, rising: bool) {
+        let (width, height) = (self.width as f32, self.height as f32);
+        let half = self.light() as f32 / 2.0;
+        let length = width.hypot(height);
+        self.fill_shape(|x, y| {
+            let distance = if rising {
+                height * x + width * y - width * height
+            } else {
+                height * x - width * y
+            };
+            distance.abs() / length <= half
+        });
+    }
+
+    /// Draws a powerline separator, a solid triangle or a chevron pointing
+    /// right or left, spanning the full height of the cell.
+    fn powerline(&mut self, c: char) {
+        let (width, height) = (self.width as f32, self.height as f32);
+        let half = self.light() as f32 / 2.0;
+        let pointing_right = matches!(c, '\u{e0b0}' | '\u{e0b1}');
+        let solid = matches!(c, '\u{e0b0}' | '\u{e0b2}');
+        let length = width.hypot(height / 2.0);
+        self.fill_shape(|x, y| {
+            // fold onto the upper half of a triangle pointing right
+            let x = if pointing_right { x } else { width - x };
+            let y = y.min(height - y);
+            // positive below the edge from the top left corner to the tip
+            let distance = (width * y - height / 2.0 * x) / length;
+            if solid {
+                distance >= 0.0
+            } else {
+                distance.abs() <= half
+            }
+        });
+    }
+
+    /// Draws a block element; halves and quadrants split the cell at the
+    /// same row and column.
+    fn block(&mut self, c: char) {
+        let (width, height) = (self.width, self.height);
+        let eighths = |n: i32, length: i32| (length * n + 4) / 8;
+        let (mid_x, mid_y) = (eighths(4, width), height - eighths(4, height));
+        match c {
+            '▀' => self.fill((0, 0), (width, mid_y), 255),
+            '\u{2581}'..='\u{2588}' => {
+                let top = height - eighths(c as i32 - 0x2580, height);
+                self.fill((0, top), (width, height), 255);
+            }
+            '\u{2589}'..='\u{258f}' => {
+                let right = eighths(0x2590 - c as i32, width);
+                self.fill((0, 0), (right, height), 255);
+            }
+            '▐' => self.fill((mid_x, 0), (width, height), 255),
+            '░' => self.fill((0, 0), (width, height), 64),
+            '▒' => self.fill((0, 0), (width, height), 128),
+            '▓' => self.fill((0, 0), (width, height), 191),
+            '▔' => self.fill((0, 0), (width, eighths(1, height)), 255),
+            '▕' => self.fill((width - eighths(1, width), 0), (width, height), 255),
+            _ => {
+                // upper left, upper right, lower left and lower right bits
+                let quadrants = match c {
+                    '▖' => 0b0100,
+                    '▗' => 0b1000,
+                    '▘' => 0b0001,
+                    '▙' => 0b1101,
+                    '▚' => 0b1001,
+                    '▛' => 0b0111,
+                    '▜' => 0b1011,
+                    '▝' => 0b0010,
+                    '▞' => 0b0110,
+                    _ => 0b1110,
+                };
+                let cells = [
+                    ((0, 0), (mid_x, mid_y)),
+                    ((mid_x, 0), (width, mid_y)),
+                    ((0, mid_y), (mid_x, height)),
+                    ((mid_x, mid_y), (width, height)),
+                ];
+                for (bit, (from, to)) in cells.into_iter().enumerate() {
+                    if quadrants & (1 << bit) != 0 {
+                        self.fill(from, to, 255);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Draws the dots of a braille pattern as squares on a 2x4 grid.
+    fn braille(&mut self, pattern: u32) {
+        // dots 1-3 and 7 run down the left column, 4-6 and 8 the right
+        const DOTS: [(i32, i32); 8] = [
+            (0, 0),
+            (0, 1),
+            (0, 2),
+            (1, 0),
+            (1, 1),
+            (1, 2),
+            (0, 3),
+            (1, 3),
+        ];
+        let size = (self.width / 4).min(self.height / 8).max(1);
+        for (bit, (column, row)) in DOTS.into_iter().enumerate() {
+            if pattern & (1 << bit) != 0 {
+                let x = self.width * (2 * column + 1) / 4 - size / 2;
+                let y = self.height * (2 * row + 1) / 8 - size / 2;
+                self.fill((x, y), (x + size, y + size), 255);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rows(coverage: &[u8], width: i32) -> Vec<&[u8]> {
+        coverage.chunks(width as usize).collect()
+    }
+
+    #[test]
+    fn lines_reach_the_cell_edges() {
+        let (width, height) = (9, 18);
+        let horizontal = draw('─', (width, height)).unwrap();
+        let covered: Vec<usize> = rows(&horizontal, width)
+            .iter()
+            .enumerate()
+            .filter(|(_, row)| row.iter().all(|&alpha| alpha == 255))
+            .map(|(y, _)| y)
+            .collect();
+        assert_eq!(covered, vec![8]);
+
+        let vertical = draw('│', (width, height)).unwrap();
+        assert!(rows(&vertical, width).iter().all(|row| row[4] == 255));
+
+        // a corner joins its arms without a gap
+        let corner = draw('┌', (width, height)).unwrap();
+        let corner = rows(&corner, width);
+        assert!(corner[8][4..].iter().all(|&alpha| alpha == 255));
+        assert!(corner[8..].iter().all(|row| row[4] == 255));
+        assert!(corner[..8].iter().all(|row| row.iter().all(|&a| a == 0)));
+    }
+
+    #[test]
+    fn double_corners_join_inner_and_outer_strokes() {
+        let (width, height) = (9, 18);
+        let corner = draw('╔', (width, height)).unwrap();
+        let corner = rows(&corner, width);
+        // strokes at columns 3 and 5, rows 7 and 9
+        assert_eq!(corner[7], &[0, 0, 0, 255, 255, 255, 255, 255, 255]);
+        assert_eq!(corner[8], &[0, 0, 0, 255, 0, 0, 0, 0, 0]);
+        assert_eq!(corner[9], &[0, 0, 0, 255, 0, 255, 255, 255, 255]);
+        assert_eq!(corner[17], &[0, 0, 0, 255, 0, 255, 0, 0, 0]);
+    }
+
+    #[test]
+    fn blocks_split_the_cell_consistently() {
+        let (width, height) = (9, 17);
+        let full = draw('█', (width, height)).unwrap();
+        assert!(full.iter().all(|&alpha| alpha == 255));
+
+        let upper = draw('▀', (width, height)).unwrap();
+        let lower = draw('▄', (width, height)).unwrap();
+        assert!(upper.iter().zip(&lower).all(|(a, b)| a ^ b == 255));
+
+        let quadrants = draw('▚', (width, height)).unwrap();
+        let others = draw('▞', (width, height)).unwrap();
+        assert!(quadrants.iter().zip(&others).all(|(a, b)| a ^ b == 255));
+        assert_eq!(draw('▒', (width, height)).unwrap()[0], 128);
+    }
+
+    #[test]
+    fn braille_dots_follow_the_pattern_bits() {
+        let (width, height) = (8, 16);
+        let dots = draw('⡁', (width, height)).unwrap(); // dots 1 and 7
+        let dots = rows(&dots, width);
+        assert_eq!(dots[1], &[0, 255, 255, 0, 0, 0, 0, 0]);
+        assert_eq!(dots[13], &[0, 255, 255, 0, 0, 0, 0, 0]);
+        assert!(dots[5].iter().all(|&alpha| alpha == 0));
+
+        let blank = draw('\u{2800}', (width, height)).unwrap();
+        assert!(blank.iter().all(|&alpha| alpha == 0));
+    }
+
+    #[test]
+    fn powerline_triangles_span_the_cell_height() {
+        let (width, height) = (8, 16);
+        let right = draw('\u{e0b0}', (width, height)).unwrap();
+        let right = rows(&right, width);
+        // the left edge is covered but for the antialiased corners
+        assert!(right[1..15].iter().all(|row| row[0] == 255));
+        assert!(right[0][0] > 0 && right[15][0] > 0);
+        assert_eq!(right[0][7], 0);
+        assert_eq!(right[15][7], 0);
+        assert_eq!(right[7][6], 255);
+        assert!(right[7][7] > 0 && right[8][7] > 0);
+
+        let left = draw('\u{e0b2}', (width, height)).unwrap();
+        let mirrored: Vec<u8> = left
+            .chunks(width as usize)
+            .flat_map(|row| row.iter().rev().copied())
+            .collect();
+        assert_eq!(mirrored, right.concat());
+
+        let chevron = draw('\u{e0b1}', (width, height)).unwrap();
+        let chevron = rows(&chevron, width);
+        assert_eq!(chevron[7][0], 0);
+        assert!(chevron[7][7] > 0);
+    }
+
+    #[test]
+    fn only_supported_chars_are_drawn() {
+        assert!(supports('╳') && supports('▟') && supports('⣿'));
+        assert!(!supports('a') && !supports('\u{25a0}'));
+        assert_eq!(draw('a', (8, 16)), None);
+    }
+}
@@ -0,0 +1,293 @@
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::backend::BeamtermBackend;
+use crate::capture::CapturedFrame;
+use crate::error::Error;
+
+/// Records rendered frames to numbered PNG files or an animated PNG.
+///
+/// Call [`record`](Self::record) once per rendered frame; every N-th frame is
+/// captured offscreen, see [`every`](Self::every). Animated PNGs are written by
+/// [`finish`](Self::finish), played back at the configured
+/// [`frame_rate`](Self::frame_rate).
+///
+/// ```ignore
+/// let mut recorder = FrameRecorder::apng("waves.png").every(2).frame_rate(30);
+/// // after each frame is drawn
+/// recorder.record(terminal.backend())?;
+/// // on exit
+/// recorder.finish()?;
+/// ```
+pub struct FrameRecorder {
+    output: Output,
+    every: u32,
+    frame_rate: u16,
+    /// Rendered frames seen by [`record`](Self::record).
+    rendered: u64,
+    /// Frames written or, for animated output, spilled.
+    recorded: usize,
+}
+
+enum Output {
+    PngSequence(PathBuf),
+    Apng {
+        path: PathBuf,
+        /// Temporary directory holding the frames until they are assembled.
+        frames_dir: PathBuf,
+        /// Size of the first frame, which all others must match.
+        size: Option<(u32, u32)>,
+    },
+}
+
+/// Distinguishes the spill directories of recorders in one process.
+static NEXT_SPILL: AtomicU64 = AtomicU64::new(0);
+
+impl FrameRecorder {
+    /// Writes each recorded frame to `frame-00000.png`, `frame-00001.png`, ...
+    /// in `dir`, creating the directory if needed.
+    pub fn png_sequence(dir: impl Into<PathBuf>) -> Self {
+        Self::with_output(Output::PngSequence(dir.into()))
+    }
+
+    /// Writes all recorded frames to a single animated PNG at `path`.
+    ///
+    /// Frames are spilled to PNG files in a temporary directory until
+    /// [`finish`](Self::finish) assembles them, so memory use does not grow
+    /// with the length of the recording.
+    pub fn apng(path: impl Into<PathBuf>) -> Self {
+        let frames_dir = std::env::temp_dir().join(format!(
+            "ratbeam-apng-{}-{}",
+            std::process::id(),
+            NEXT_SPILL.fetch_add(1, Ordering::Relaxed)
+        ));
+        Self::with_output(Output::Apng {
+            path: path.into(),
+            frames_dir,
+            size: None,
+        })
+    }
+
+    fn with_output(output: Output) -> Self {
+        Self {
+            output,
+            every: 1,
+            frame_rate: 30,
+            rendered: 0,
+            recorded: 0,
+        }
+    }
+
+    /// Records only every `n`-th rendered frame; defaults to every frame.
+    pub fn every(mut self, n: u32) -> Self {
+        self.every = n.max(1);
+        self
+    }
+
+    /// Sets the playback rate of animated output, in frames per second;
+    /// defaults to 30.
+    pub fn frame_rate(mut self, fps: u16) -> Self {
+        self.frame_rate = fps.max(1);
+        self
+    }
+
+    /// Returns the number of frames recorded so far.
+    pub fn recorded_frames(&self) -> usize {
+        self.recorded
+    }
+
+    /// Counts a rendered frame, capturing it from `backend` if it is due.
+    ///
+    /// Returns true if the frame was recorded.
+    pub fn record(&mut self, backend: &BeamtermBackend) -> Result<bool, Error> {
+        let due = self.rendered.is_multiple_of(self.every as u64);
+        self.rendered += 1;
+        if !due {
+            return Ok(false);
+        }
+
+        let frame = backend.capture_frame()?;
+        self.push_frame(frame)?;
+        Ok(true)
+    }
+
+    /// Records an already captured frame, regardless of [`every`](Self::every).
+    pub fn push_frame(&mut self, frame: CapturedFrame) -> Result<(), Error> {
+        match &mut self.output {
+            Output::PngSequence(dir) => {
+                std::fs::create_dir_all(&*dir)?;
+                write_png(&frame_path(dir, self.recorded), &frame)?;
+            }
+            Output::Apng {
+                frames_dir, size, ..
+            } => {
+                let frame_size = (frame.width(), frame.height());
+                if size.is_some_and(|size| size != frame_size) {
+                    return Err(Error::Other(
+                        "animated PNG frames must all have the same size".to_string(),
+                    ));
+                }
+                std::fs::create_dir_all(&*frames_dir)?;
+                write_png(&frame_path(frames_dir, self.recorded), &frame)?;
+                *size = Some(frame_size);
+            }
+        }
+
+        self.recorded += 1;
+        Ok(())
+    }
+
+    /// Completes the recording, writing animated output, and returns the
+    /// number of recorded frames.
+    pub fn finish(self) -> Result<usize, Error> {
+        if let Output::Apng {
+            path,
+            frames_dir,
+            size: Some(size),
+        } = &self.output
+        {
+            write_apng(path, frames_dir, self.recorded, *size, self.frame_rate)?;
+        }
+
+        Ok(self.recorded)
+    }
+}
+
+impl Drop for FrameRecorder {
+    fn drop(&mut self) {
+        if let Output::Apng { frames_dir, .. } = &self.output {
+            let _ = std::fs::remove_dir_all(frames_dir);
+        }
+    }
+}
+
+fn frame_path(dir: &Path, index: usize) -> PathBuf {
+    dir.join(format!("frame-{index:05}.png"))
+}
+
+fn write_png(path: &Path, frame: &CapturedFrame) -> Result<(), Error> {
+    let file = BufWriter::new(File::create(path)?);
+    let mut encoder = png::Encoder::new(file, frame.width(), frame.height());
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(frame.pixels())?;
+    writer.finish()?;
+    Ok(())
+}
+
+/// Assembles the `count` frames spilled to `frames_dir`, one at a time.
+fn write_apng(
+    path: &Path,
+    frames_dir: &Path,
+    count: usize,
+    (width, height): (u32, u32),
+    frame_rate: u16,
+) -> Result<(), Error> {
+    let file = BufWriter::new(File::create(path)?);
+    let mut encoder = png::Encoder::new(file, width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    // loop forever
+    encoder.set_animated(count as u32, 0)?;
+    encoder.set_frame_delay(1, frame_rate)?;
+
+    let mut writer = encoder.write_header()?;
+    let mut pixels = Vec::new();
+    for index in 0..count {
+        let file = BufReader::new(File::open(frame_path(frames_dir, index))?);
+        let mut reader = png::Decoder::new(file).read_info().map_err(spill_error)?;
+        pixels.resize(reader.output_buffer_size().unwrap_or_default(), 0);
+        let info = reader.next_frame(&mut pixels).map_err(spill_error)?;
+        writer.write_image_data(&pixels[..info.buffer_size()])?;
+    }
+    writer.finish()?;
+    Ok(())
+}
+
+fn spill_error(err: png::DecodingError) -> Error {
+    Error::Other(format!("spilled frame: {err}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::BufReader;
+
+    use super::*;
+
+    fn frame(width: u32, height: u32, value: u8) -> CapturedFrame {
+        let pixels = vec![value; width as usize * height as usize * 4];
+        CapturedFrame::new(width, height, pixels).unwrap()
+    }
+
+    fn temp_path(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("ratbeam-{}-{name}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    #[test]
+    fn png_sequence_writes_numbered_files() {
+        let dir = temp_path("sequence");
+        let mut recorder = FrameRecorder::png_sequence(&dir);
+        recorder.push_frame(frame(3, 2, 10)).unwrap();
+        recorder.push_frame(frame(3, 2, 20)).unwrap();
+        assert_eq!(recorder.finish().unwrap(), 2);
+
+        let decoder = png::Decoder::new(BufReader::new(
+            File::open(dir.join("frame-00001.png")).unwrap(),
+        ));
+        let mut reader = decoder.read_info().unwrap();
+        let mut pixels = vec![0; reader.output_buffer_size().unwrap()];
+        let info = reader.next_frame(&mut pixels).unwrap();
+        assert_eq!((info.width, info.height), (3, 2));
+        assert!(pixels.iter().all(|&p| p == 20));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn apng_holds_all_frames() {
+        let path = temp_path("anim.png");
+        let mut recorder = FrameRecorder::apng(&path).frame_rate(10);
+        for value in 0..3 {
+            recorder.push_frame(frame(2, 2, value)).unwrap();
+        }
+        recorder.finish().unwrap();
+
+        let decoder = png::Decoder::new(BufReader::new(File::open(&path).unwrap()));
+        let reader = decoder.read_info().unwrap();
+        let control = reader.info().animation_control.unwrap();
+        assert_eq!(control.num_frames, 3);
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn apng_spills_frames_until_finished() {
+        let path = temp_path("spilled.png");
+        let mut recorder = FrameRecorder::apng(&path);
+        recorder.push_frame(frame(2, 2, 0)).unwrap();
+        let Output::Apng { frames_dir, .. } = &recorder.output else {
+            unreachable!();
+        };
+        let frames_dir = frames_dir.clone();
+        assert!(frame_path(&frames_dir, 0).exists());
+
+        recorder.finish().unwrap();
+        assert!(!frames_dir.exists());
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn apng_rejects_size_changes() {
+        let mut recorder = FrameRecorder::apng(temp_path("unused.png"));
+        recorder.push_frame(frame(2, 2, 0)).unwrap();
+        assert!(recorder.push_frame(frame(3, 2, 0)).is_err());
+        assert_eq!(recorder.recorded_frames(), 1);
+    }
+}
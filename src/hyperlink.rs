@@ -0,0 +1,213 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use ratatui::buffer::Cell;
+use ratatui::layout::{Position, Rect};
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+/// OSC 8 introducer, followed by `params;URI` and a string terminator.
+const OSC8_PREFIX: &str = "\x1b]8;";
+
+/// Splits an OSC 8 wrapped cell symbol into its URI and visible text.
+///
+/// Widgets emit hyperlinks as `ESC ] 8 ; params ; URI ST text ESC ] 8 ; ; ST`,
+/// where `ST` is either `BEL` or `ESC \`. Returns `None` for plain symbols.
+pub(crate) fn parse_osc8(symbol: &str) -> Option<(&str, &str)> {
+    let rest = symbol.strip_prefix(OSC8_PREFIX)?;
+
+    // skip the (unused) params
+    let (_params, rest) = rest.split_once(';')?;
+    let (uri, rest) = split_at_terminator(rest)?;
+
+    // the closing sequence is optional; some widgets omit it
+    let text = match rest.find(OSC8_PREFIX) {
+        Some(end) => &rest[..end],
+        None => rest,
+    };
+
+    Some((uri, text))
+}
+
+/// Expands a cell carrying an OSC 8 hyperlink into plain cells, one per column
+/// of the link text, returning the URI and the cells.
+///
+/// The expanded cells keep the style of the original cell. Wide graphemes are
+/// followed by a blank cell, as in a ratatui buffer.
+pub(crate) fn expand_osc8(cell: &Cell) -> Option<(&str, Vec<Cell>)> {
+    let (uri, text) = parse_osc8(cell.symbol())?;
+
+    let mut cells = Vec::new();
+    for grapheme in text.graphemes(true) {
+        let mut expanded = cell.clone();
+        expanded.set_symbol(grapheme);
+        cells.push(expanded);
+
+        for _ in 1..grapheme.width() {
+            let mut blank = cell.clone();
+            blank.set_symbol(" ");
+            cells.push(blank);
+        }
+    }
+
+    Some((uri, cells))
+}
+
+/// Splits `s` at the first OSC string terminator (`BEL` or `ESC \`).
+fn split_at_terminator(s: &str) -> Option<(&str, &str)> {
+    let bel = s.find('\x07').map(|i| (i, 1));
+    let esc = s.find("\x1b\\").map(|i| (i, 2));
+
+    let (idx, len) = match (bel, esc) {
+        (Some(a), Some(b)) => a.min(b),
+        (a, b) => a.or(b)?,
+    };
+
+    Some((&s[..idx], &s[idx + len..]))
+}
+
+/// Hyperlink URIs attached to grid cells.
+#[derive(Debug, Default)]
+pub(crate) struct Hyperlinks {
+    cells: HashMap<Position, Rc<str>>,
+}
+
+impl Hyperlinks {
+    /// Attaches `uri` to every cell in `area`.
+    pub(crate) fn insert(&mut self, area: Rect, uri: &str) {
+        let uri: Rc<str> = uri.into();
+        for position in area.positions() {
+            self.cells.insert(position, uri.clone());
+        }
+    }
+
+    /// Detaches any hyperlink from the cells in `area`.
+    pub(crate) fn remove(&mut self, area: Rect) {
+        if self.cells.is_empty() {
+            return;
+        }
+
+        for position in area.positions() {
+            self.cells.remove(&position);
+        }
+    }
+
+    /// Detaches the hyperlink at `position` from all cells it was attached
+    /// to by the same [`insert`](Self::insert).
+    pub(crate) fn remove_link_at(&mut self, position: Position) {
+        if let Some(uri) = self.cells.get(&position).cloned() {
+            self.cells.retain(|_, u| !Rc::ptr_eq(u, &uri));
+        }
+    }
+
+    /// Detaches all hyperlinks.
+    pub(crate) fn clear(&mut self) {
+        self.cells.clear();
+    }
+
+    /// Returns the URI attached to the cell at `position`.
+    pub(crate) fn get(&self, position: Position) -> Option<&Rc<str>> {
+        self.cells.get(&position)
+    }
+
+    /// Returns all cells linking to `uri`.
+    pub(crate) fn cells_with_uri<'a>(
+        &'a self,
+        uri: &'a str,
+    ) -> impl Iterator<Item = Position> + 'a {
+        self.cells
+            .iter()
+            .filter(move |(_, u)| u.as_ref() == uri)
+            .map(|(position, _)| *position)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ratatui::style::Color;
+
+    use super::*;
+
+    #[test]
+    fn parse_osc8_bel_terminated() {
+        let symbol = "\x1b]8;;https://ratatui.rs\x07ra\x1b]8;;\x07";
+        assert_eq!(parse_osc8(symbol), Some(("https://ratatui.rs", "ra")));
+    }
+
+    #[test]
+    fn parse_osc8_st_terminated() {
+        let symbol = "\x1b]8;id=1;https://ratatui.rs\x1b\\tui\x1b]8;;\x1b\\";
+        assert_eq!(parse_osc8(symbol), Some(("https://ratatui.rs", "tui")));
+    }
+
+    #[test]
+    fn parse_osc8_without_closing_sequence() {
+        let symbol = "\x1b]8;;file:///tmp\x07x";
+        assert_eq!(parse_osc8(symbol), Some(("file:///tmp", "x")));
+    }
+
+    #[test]
+    fn parse_osc8_rejects_plain_symbols() {
+        assert_eq!(parse_osc8("a"), None);
+        assert_eq!(parse_osc8("\x1b]8;;unterminated"), None);
+    }
+
+    #[test]
+    fn expand_osc8_spreads_text_over_cells() {
+        let mut cell = Cell::default();
+        cell.set_symbol("\x1b]8;;https://ratatui.rs\x07a中b\x1b]8;;\x07");
+        cell.set_fg(Color::Cyan);
+
+        let (uri, cells) = expand_osc8(&cell).unwrap();
+        assert_eq!(uri, "https://ratatui.rs");
+
+        let symbols: Vec<&str> = cells.iter().map(Cell::symbol).collect();
+        assert_eq!(symbols, ["a", "中", " ", "b"]);
+        assert!(cells.iter().all(|c| c.fg == Color::Cyan));
+    }
+
+    #[test]
+    fn expand_osc8_ignores_plain_cells() {
+        let mut cell = Cell::default();
+        cell.set_symbol("x");
+        assert_eq!(expand_osc8(&cell), None);
+    }
+
+    #[test]
+    fn hyperlinks_insert_and_remove() {
+        let mut links = Hyperlinks::default();
+        links.insert(Rect::new(2, 1, 3, 1), "https://example.com");
+
+        assert_eq!(links.get(Position::new(1, 1)), None);
+        assert_eq!(
+            links.get(Position::new(2, 1)).map(AsRef::as_ref),
+            Some("https://example.com")
+        );
+        assert_eq!(links.cells_with_uri("https://example.com").count(), 3);
+
+        links.remove(Rect::new(3, 1, 1, 1));
+        assert_eq!(links.get(Position::new(3, 1)), None);
+        assert_eq!(links.cells_with_uri("https://example.com").count(), 2);
+
+        links.clear();
+        assert_eq!(links.get(Position::new(2, 1)), None);
+    }
+    #[test]
+    fn shorter_link_replaces_longer_link() {
+        let mut links = Hyperlinks::default();
+        links.insert(Rect::new(0, 0, 6, 1), "https://example.com/long");
+        links.insert(Rect::new(6, 0, 2, 1), "https://example.com/long");
+
+        links.remove_link_at(Position::new(0, 0));
+        links.insert(Rect::new(0, 0, 2, 1), "https://example.com");
+
+        assert_eq!(
+            links.get(Position::new(1, 0)).map(AsRef::as_ref),
+            Some("https://example.com")
+        );
+        assert_eq!(links.get(Position::new(2, 0)), None);
+        assert_eq!(links.get(Position::new(5, 0)), None);
+        // a separate link to the same URI is kept
+        assert_eq!(links.cells_with_uri("https://example.com/long").count(), 2);
+    }
+}
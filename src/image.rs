@@ -0,0 +1,492 @@
+use beamterm_core::{GlslVersion, RenderContext, TerminalGrid};
+use glow::HasContext;
+use ratatui::{buffer::Buffer, layout::Rect, widgets::Widget};
+
+use crate::error::Error;
+
+const VERTEX_SHADER: &str = "
+layout(location = 0) in vec2 a_pos;
+layout(location = 1) in vec2 a_tex_coord;
+out vec2 v_tex_coord;
+
+void main() {
+    v_tex_coord = a_tex_coord;
+    gl_Position = vec4(a_pos, 0.0, 1.0);
+}
+";
+
+const FRAGMENT_SHADER: &str = "
+uniform sampler2D u_image;
+in vec2 v_tex_coord;
+out vec4 frag_color;
+
+void main() {
+    frag_color = texture(u_image, v_tex_coord);
+}
+";
+
+/// An RGBA8 image to draw into a region of cells, see
+/// [`BeamtermBackend::place_image`](crate::BeamtermBackend::place_image).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InlineImage {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+impl InlineImage {
+    /// Creates an image from tightly packed RGBA8 pixels with straight
+    /// alpha, top row first.
+    ///
+    /// Returns `None` if the image is empty or `pixels` does not hold
+    /// `width * height` pixels.
+    pub fn new(width: u32, height: u32, pixels: Vec<u8>) -> Option<Self> {
+        let expected = width as usize * height as usize * 4;
+        (expected > 0 && pixels.len() == expected).then_some(Self {
+            width,
+            height,
+            pixels,
+        })
+    }
+
+    /// Width in pixels.
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Height in pixels.
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// RGBA8 pixels, top row first.
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+}
+
+/// How an image is scaled into the cells of its area.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum ImageFit {
+    /// Scaled to fit inside the area with its aspect ratio kept, centered;
+    /// the rest of the area stays blank.
+    #[default]
+    Fit,
+    /// Scaled to cover the area with its aspect ratio kept, centered; the
+    /// overflowing edges are cropped.
+    Fill,
+    /// Stretched over the area.
+    Stretch,
+}
+
+/// Identifies an image placed on a [`BeamtermBackend`](crate::BeamtermBackend).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ImageId(u64);
+
+/// A [`Widget`] reserving the cells of an image placed with
+/// [`BeamtermBackend::place_image`](crate::BeamtermBackend::place_image).
+///
+/// The cells are reset and marked to be skipped, so ratatui leaves the
+/// area to the image instead of drawing over it; once the placeholder is
+/// no longer rendered, the cells are drawn again as usual.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ImagePlaceholder;
+
+impl Widget for ImagePlaceholder {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        for position in area.intersection(buf.area).positions() {
+            let cell = &mut buf[position];
+            cell.reset();
+            cell.set_skip(true);
+        }
+    }
+}
+
+/// An image with the cells it is drawn into.
+struct Placement {
+    id: ImageId,
+    area: Rect,
+    fit: ImageFit,
+    image: InlineImage,
+    texture: glow::Texture,
+}
+
+/// The images placed on a grid, drawn as textured quads over its cells.
+///
+/// The pixels of each image are kept to upload them again after a context
+/// loss.
+#[derive(Default)]
+pub(crate) struct ImageLayer {
+    placements: Vec<Placement>,
+    next_id: u64,
+    /// Created with the first image.
+    gpu: Option<ImageGpu>,
+}
+
+impl ImageLayer {
+    /// Uploads `image` and places it into `area`.
+    pub(crate) fn place(
+        &mut self,
+        gl: &glow::Context,
+        image: InlineImage,
+        area: Rect,
+        fit: ImageFit,
+    ) -> Result<ImageId, Error> {
+        if self.gpu.is_none() {
+            let glsl_version = if gl.version().is_embedded {
+                GlslVersion::Es300
+            } else {
+                GlslVersion::Gl330
+            };
+            self.gpu = Some(ImageGpu::new(gl, &glsl_version)?);
+        }
+
+        let texture = upload(gl, &image)?;
+        let id = ImageId(self.next_id);
+        self.next_id += 1;
+        self.placements.push(Placement {
+            id,
+            area,
+            fit,
+            image,
+            texture,
+        });
+        Ok(id)
+    }
+
+    /// Moves image `id` into `area`. Returns false for unknown images.
+    pub(crate) fn move_to(&mut self, id: ImageId, area: Rect, fit: ImageFit) -> bool {
+        let placement = self.placements.iter_mut().find(|p| p.id == id);
+        placement
+            .map(|placement| {
+                placement.area = area;
+                placement.fit = fit;
+            })
+            .is_some()
+    }
+
+    /// Removes image `id`. Returns false for unknown images.
+    pub(crate) fn remove(&mut self, gl: &glow::Context, id: ImageId) -> bool {
+        let Some(index) = self.placements.iter().position(|p| p.id == id) else {
+            return false;
+        };
+
+        let placement = self.placements.remove(index);
+        unsafe { gl.delete_texture(placement.texture) };
+        true
+    }
+
+    /// Recreates the program and textures after a context loss.
+    pub(crate) fn recreate(
+        &mut self,
+        gl: &glow::Context,
+        glsl_version: &GlslVersion,
+    ) -> Result<(), Error> {
+        // the old resources died with the context
+        self.gpu = None;
+        if self.placements.is_empty() {
+            return Ok(());
+        }
+
+        self.gpu = Some(ImageGpu::new(gl, glsl_version)?);
+        for placement in &mut self.placements {
+            placement.texture = upload(gl, &placement.image)?;
+        }
+        Ok(())
+    }
+
+    /// Draws the images over the cells of `grid`, into the viewport the
+    /// grid was drawn into.
+    pub(crate) fn draw(&self, context: &mut RenderContext, grid: &TerminalGrid) {
+        let Some(gpu) = self.gpu.as_ref().filter(|_| !self.placements.is_empty()) else {
+            return;
+        };
+
+        let gl = context.gl;
+        let (cell_width, cell_height) = grid.cell_size();
+        let (canvas_width, canvas_height) = grid.canvas_size();
+        let to_ndc = |x: f32, y: f32| {
+            [
+                x / canvas_width as f32 * 2.0 - 1.0,
+                1.0 - y / canvas_height as f32 * 2.0,
+            ]
+        };
+
+        context.state.active_texture(gl, glow::TEXTURE0);
+        unsafe {
+            gl.use_program(Some(gpu.program));
+            gl.bind_vertex_array(Some(gpu.vertex_array));
+            gl.bind_buffer(glow::ARRAY_BUFFER, Some(gpu.vertex_buffer));
+            gl.uniform_1_i32(gpu.sampler.as_ref(), 0);
+            // straight alpha over the cells; the cells' alpha is kept
+            gl.enable(glow::BLEND);
+            gl.blend_func_separate(
+                glow::SRC_ALPHA,
+                glow::ONE_MINUS_SRC_ALPHA,
+                glow::ONE,
+                glow::ONE_MINUS_SRC_ALPHA,
+            );
+
+            for placement in &self.placements {
+                let area = placement.area;
+                let area = [
+                    (area.left() as i32 * cell_width) as f32,
+                    (area.top() as i32 * cell_height) as f32,
+                    (area.right() as i32 * cell_width) as f32,
+                    (area.bottom() as i32 * cell_height) as f32,
+                ];
+                let image = (placement.image.width, placement.image.height);
+                let Quad {
+                    position,
+                    tex_coords,
+                } = quad(placement.fit, image, area);
+
+                let [left, top, right, bottom] = position;
+                let [u0, v0, u1, v1] = tex_coords;
+                let vertices: Vec<u8> = [
+                    (to_ndc(left, top), [u0, v0]),
+                    (to_ndc(left, bottom), [u0, v1]),
+                    (to_ndc(right, top), [u1, v0]),
+                    (to_ndc(right, bottom), [u1, v1]),
+                ]
+                .into_iter()
+                .flat_map(|(position, tex_coord)| [position, tex_coord].concat())
+                .flat_map(f32::to_ne_bytes)
+                .collect();
+
+                gl.bind_texture(glow::TEXTURE_2D, Some(placement.texture));
+                gl.buffer_data_u8_slice(glow::ARRAY_BUFFER, &vertices, glow::STREAM_DRAW);
+                gl.draw_arrays(glow::TRIANGLE_STRIP, 0, 4);
+            }
+
+            gl.blend_func(glow::ONE, glow::ZERO);
+            gl.disable(glow::BLEND);
+            gl.bind_texture(glow::TEXTURE_2D, None);
+            gl.bind_buffer(glow::ARRAY_BUFFER, None);
+            gl.bind_vertex_array(None);
+            gl.use_program(None);
+        }
+    }
+
+    /// Releases the textures and program.
+    pub(crate) fn delete(&mut self, gl: &glow::Context) {
+        for placement in self.placements.drain(..) {
+            unsafe { gl.delete_texture(placement.texture) };
+        }
+        if let Some(gpu) = self.gpu.take() {
+            gpu.delete(gl);
+        }
+    }
+}
+
+/// The program and vertex buffer drawing the quads of images.
+struct ImageGpu {
+    program: glow::Program,
+    vertex_array: glow::VertexArray,
+    vertex_buffer: glow::Buffer,
+    sampler: Option<glow::UniformLocation>,
+}
+
+impl ImageGpu {
+    fn new(gl: &glow::Context, glsl_version: &GlslVersion) -> Result<Self, Error> {
+        let program = link_program(
+            gl,
+            &[glsl_version.vertex_preamble(), VERTEX_SHADER].concat(),
+            &[glsl_version.fragment_preamble(), FRAGMENT_SHADER].concat(),
+        )?;
+
+        unsafe {
+            let previous_vertex_array = gl.get_parameter_vertex_array(glow::VERTEX_ARRAY_BINDING);
+            let previous_buffer = gl.get_parameter_buffer(glow::ARRAY_BUFFER_BINDING);
+
+            let vertex_array = gl.create_vertex_array().map_err(Error::Other)?;
+            let vertex_buffer = gl.create_buffer().map_err(Error::Other)?;
+            gl.bind_vertex_array(Some(vertex_array));
+            gl.bind_buffer(glow::ARRAY_BUFFER, Some(vertex_buffer));
+            // interleaved position and texture coordinates
+            let stride = 4 * size_of::<f32>() as i32;
+            gl.enable_vertex_attrib_array(0);
+            gl.vertex_attrib_pointer_f32(0, 2, glow::FLOAT, false, stride, 0);
+            gl.enable_vertex_attrib_array(1);
+            let offset = 2 * size_of::<f32>() as i32;
+            gl.vertex_attrib_pointer_f32(1, 2, glow::FLOAT, false, stride, offset);
+            gl.bind_vertex_array(previous_vertex_array);
+            gl.bind_buffer(glow::ARRAY_BUFFER, previous_buffer);
+
+            Ok(Self {
+                program,
+                vertex_array,
+                vertex_buffer,
+                sampler: gl.get_uniform_location(program, "u_image"),
+            })
+        }
+    }
+
+    fn delete(self, gl: &glow::Context) {
+        unsafe {
+            gl.delete_program(self.program);
+            gl.delete_vertex_array(self.vertex_array);
+            gl.delete_buffer(self.vertex_buffer);
+        }
+    }
+}
+
+fn link_program(
+    gl: &glow::Context,
+    vertex_source: &str,
+    fragment_source: &str,
+) -> Result<glow::Program, Error> {
+    unsafe {
+        let program = gl.create_program().map_err(Error::Other)?;
+        let mut shaders = Vec::new();
+        for (kind, source) in [
+            (glow::VERTEX_SHADER, vertex_source),
+            (glow::FRAGMENT_SHADER, fragment_source),
+        ] {
+            let shader = gl.create_shader(kind).map_err(Error::Other)?;
+            gl.shader_source(shader, source);
+            gl.compile_shader(shader);
+            if !gl.get_shader_compile_status(shader) {
+                let log = gl.get_shader_info_log(shader);
+                gl.delete_shader(shader);
+                shaders
+                    .into_iter()
+                    .for_each(|shader| gl.delete_shader(shader));
+                gl.delete_program(program);
+                return Err(Error::Other(format!("image shader: {log}")));
+            }
+            gl.attach_shader(program, shader);
+            shaders.push(shader);
+        }
+
+        gl.link_program(program);
+        for shader in shaders {
+            gl.delete_shader(shader);
+        }
+        if !gl.get_program_link_status(program) {
+            let log = gl.get_program_info_log(program);
+            gl.delete_program(program);
+            return Err(Error::Other(format!("image program: {log}")));
+        }
+        Ok(program)
+    }
+}
+
+/// Uploads `image` into a mipmapped texture, preserving the host's binding.
+fn upload(gl: &glow::Context, image: &InlineImage) -> Result<glow::Texture, Error> {
+    unsafe {
+        let previous = gl.get_parameter_texture(glow::TEXTURE_BINDING_2D);
+
+        let texture = gl.create_texture().map_err(Error::Other)?;
+        gl.bind_texture(glow::TEXTURE_2D, Some(texture));
+        gl.tex_image_2d(
+            glow::TEXTURE_2D,
+            0,
+            glow::RGBA8 as i32,
+            image.width as i32,
+            image.height as i32,
+            0,
+            glow::RGBA,
+            glow::UNSIGNED_BYTE,
+            glow::PixelUnpackData::Slice(Some(&image.pixels)),
+        );
+        gl.generate_mipmap(glow::TEXTURE_2D);
+        for (parameter, value) in [
+            (glow::TEXTURE_MIN_FILTER, glow::LINEAR_MIPMAP_LINEAR),
+            (glow::TEXTURE_MAG_FILTER, glow::LINEAR),
+            (glow::TEXTURE_WRAP_S, glow::CLAMP_TO_EDGE),
+            (glow::TEXTURE_WRAP_T, glow::CLAMP_TO_EDGE),
+        ] {
+            gl.tex_parameter_i32(glow::TEXTURE_2D, parameter, value as i32);
+        }
+        gl.bind_texture(glow::TEXTURE_2D, previous);
+        Ok(texture)
+    }
+}
+
+/// Where an image is drawn, as left, top, right and bottom pixels, and the
+/// part of it drawn there, as texture coordinates.
+#[derive(Debug, PartialEq)]
+struct Quad {
+    position: [f32; 4],
+    tex_coords: [f32; 4],
+}
+
+/// Returns the quad of an image of `image` pixels scaled by `fit` into
+/// `area`, given as left, top, right and bottom pixels.
+fn quad(fit: ImageFit, image: (u32, u32), area: [f32; 4]) -> Quad {
+    let [left, top, right, bottom] = area;
+    let (width, height) = (right - left, bottom - top);
+    let scale_x = width / image.0 as f32;
+    let scale_y = height / image.1 as f32;
+
+    match fit {
+        ImageFit::Stretch => Quad {
+            position: area,
+            tex_coords: [0.0, 0.0, 1.0, 1.0],
+        },
+        ImageFit::Fit => {
+            let scale = scale_x.min(scale_y);
+            let x = left + (width - image.0 as f32 * scale) / 2.0;
+            let y = top + (height - image.1 as f32 * scale) / 2.0;
+            Quad {
+                position: [x, y, right - (x - left), bottom - (y - top)],
+                tex_coords: [0.0, 0.0, 1.0, 1.0],
+            }
+        }
+        ImageFit::Fill => {
+            let scale = scale_x.max(scale_y);
+            // fractions of the image cropped on each side
+            let u = (1.0 - scale_x / scale) / 2.0;
+            let v = (1.0 - scale_y / scale) / 2.0;
+            Quad {
+                position: area,
+                tex_coords: [u, v, 1.0 - u, 1.0 - v],
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn inline_image_checks_pixel_count() {
+        assert!(InlineImage::new(2, 2, vec![0; 16]).is_some());
+        assert!(InlineImage::new(2, 2, vec![0; 15]).is_none());
+        assert!(InlineImage::new(0, 2, Vec::new()).is_none());
+    }
+
+    #[test]
+    fn fit_centers_the_image_inside_the_area() {
+        let quad = quad(ImageFit::Fit, (200, 100), [10.0, 20.0, 110.0, 120.0]);
+        assert_eq!(quad.position, [10.0, 45.0, 110.0, 95.0]);
+        assert_eq!(quad.tex_coords, [0.0, 0.0, 1.0, 1.0]);
+    }
+
+    #[test]
+    fn fill_crops_the_overflowing_edges() {
+        let quad = quad(ImageFit::Fill, (200, 100), [10.0, 20.0, 110.0, 120.0]);
+        assert_eq!(quad.position, [10.0, 20.0, 110.0, 120.0]);
+        assert_eq!(quad.tex_coords, [0.25, 0.0, 0.75, 1.0]);
+    }
+
+    #[test]
+    fn stretch_covers_the_area() {
+        let quad = quad(ImageFit::Stretch, (200, 100), [0.0, 0.0, 30.0, 40.0]);
+        assert_eq!(quad.position, [0.0, 0.0, 30.0, 40.0]);
+        assert_eq!(quad.tex_coords, [0.0, 0.0, 1.0, 1.0]);
+    }
+
+    #[test]
+    fn placeholder_skips_its_cells() {
+        let mut buf = Buffer::with_lines(["abc", "def"]);
+        ImagePlaceholder.render(Rect::new(1, 0, 5, 1), &mut buf);
+
+        assert!(!buf[(0, 0)].skip);
+        assert!(buf[(1, 0)].skip && buf[(2, 0)].skip);
+        assert_eq!(buf[(1, 0)].symbol(), " ");
+        assert!(!buf[(1, 1)].skip);
+    }
+}
@@ -0,0 +1,509 @@
+use ratatui::layout::Direction;
+
+use crate::compositor::{PaneId, Viewport};
+
+/// Panes smaller than this many pixels along a split cannot be produced by
+/// dragging a divider.
+const MIN_PANE_SIZE: i32 = 16;
+
+/// A tiling layout dividing an area between panes by recursive splits.
+///
+/// Each split divides its area in two along a [`Direction`]: `Horizontal`
+/// places the panes side by side, `Vertical` stacks them. The layout only
+/// computes pixel rectangles; apply them to a [`Compositor`](crate::Compositor)
+/// with [`apply_layout`](crate::Compositor::apply_layout):
+///
+/// ```no_run
+/// # use ratatui::layout::Direction;
+/// # use ratbeam::{Compositor, SplitLayout, Viewport};
+/// # fn run(compositor: &mut Compositor, x: f64, y: f64) -> Result<(), ratbeam::Error> {
+/// let first = compositor.add_pane(Viewport::default())?;
+/// let mut layout = SplitLayout::new(first, Viewport::new(0, 0, 1280, 720));
+/// let second = compositor.add_pane(Viewport::default())?;
+/// layout.split(first, Direction::Horizontal, second);
+/// compositor.apply_layout(&layout)?;
+///
+/// // on mouse press, drag a divider or focus the clicked pane
+/// if !layout.start_drag(x, y) {
+///     if let Some(pane) = compositor.pane_at(x, y) {
+///         compositor.focus(pane);
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+///
+/// A zoomed pane covers the whole area while the others are hidden behind
+/// empty viewports.
+#[derive(Debug, Clone)]
+pub struct SplitLayout {
+    root: Node,
+    area: Viewport,
+    divider_width: i32,
+    zoomed: Option<PaneId>,
+    /// Path to the split whose divider is being dragged.
+    drag: Option<Vec<Side>>,
+}
+
+#[derive(Debug, Clone)]
+enum Node {
+    Pane(PaneId),
+    Split {
+        direction: Direction,
+        /// Share of the area, excluding the divider, given to `first`.
+        ratio: f32,
+        first: Box<Node>,
+        second: Box<Node>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Side {
+    First,
+    Second,
+}
+
+impl SplitLayout {
+    /// Creates a layout in which `pane` covers all of `area`.
+    pub fn new(pane: PaneId, area: Viewport) -> Self {
+        Self {
+            root: Node::Pane(pane),
+            area,
+            divider_width: 4,
+            zoomed: None,
+            drag: None,
+        }
+    }
+
+    /// Sets the gap between split panes in physical pixels. Defaults to 4.
+    pub fn set_divider_width(&mut self, width: i32) {
+        self.divider_width = width.max(0);
+    }
+
+    /// Returns the area covered by the layout.
+    pub fn area(&self) -> Viewport {
+        self.area
+    }
+
+    /// Sets the area covered by the layout, typically the whole window.
+    /// Splits keep their ratios.
+    pub fn set_area(&mut self, area: Viewport) {
+        self.area = area;
+    }
+
+    /// Splits the area of `target`, placing `pane` after it in `direction`
+    /// with half of the space. Returns false if `target` is not in the layout
+    /// or `pane` already is.
+    pub fn split(&mut self, target: PaneId, direction: Direction, pane: PaneId) -> bool {
+        if self.contains(pane) {
+            return false;
+        }
+        let Some(node) = self.root.find_mut(target) else {
+            return false;
+        };
+
+        *node = Node::Split {
+            direction,
+            ratio: 0.5,
+            first: Box::new(Node::Pane(target)),
+            second: Box::new(Node::Pane(pane)),
+        };
+        self.zoomed = None;
+        self.drag = None;
+        true
+    }
+
+    /// Removes `pane`, giving its space to its sibling. Returns false if the
+    /// pane is not in the layout or is the only one.
+    pub fn remove(&mut self, pane: PaneId) -> bool {
+        if !self.root.remove(pane) {
+            return false;
+        }
+        if self.zoomed == Some(pane) {
+            self.zoomed = None;
+        }
+        self.drag = None;
+        true
+    }
+
+    /// Returns true if `pane` is in the layout.
+    pub fn contains(&self, pane: PaneId) -> bool {
+        self.panes().contains(&pane)
+    }
+
+    /// Returns the panes of the layout, from left to right and top to bottom.
+    pub fn panes(&self) -> Vec<PaneId> {
+        let mut panes = Vec::new();
+        self.root.collect_panes(&mut panes);
+        panes
+    }
+
+    /// Returns the zoomed pane.
+    pub fn zoomed(&self) -> Option<PaneId> {
+        self.zoomed
+    }
+
+    /// Zooms `pane` to cover the whole area, or restores the layout if it
+    /// is already zoomed. Returns false if the pane is not in the layout.
+    pub fn toggle_zoom(&mut self, pane: PaneId) -> bool {
+        if !self.contains(pane) {
+            return false;
+        }
+        self.zoomed = if self.zoomed == Some(pane) {
+            None
+        } else {
+            Some(pane)
+        };
+        self.drag = None;
+        true
+    }
+
+    /// Returns the viewport of every pane in the layout.
+    pub fn viewports(&self) -> Vec<(PaneId, Viewport)> {
+        let mut viewports = Vec::new();
+        self.root
+            .layout(self.area, self.divider_width, &mut |node, area| {
+                if let Node::Pane(id) = node {
+                    viewports.push((*id, area));
+                }
+            });
+
+        if let Some(zoomed) = self.zoomed {
+            for (id, viewport) in &mut viewports {
+                *viewport = if *id == zoomed {
+                    self.area
+                } else {
+                    Viewport::default()
+                };
+            }
+        }
+        viewports
+    }
+
+    /// Returns the rectangles of the dividers between panes, for the host to
+    /// paint. A divider separating side by side panes has the direction
+    /// `Horizontal`.
+    pub fn dividers(&self) -> Vec<(Direction, Viewport)> {
+        if self.zoomed.is_some() {
+            return Vec::new();
+        }
+
+        let mut dividers = Vec::new();
+        self.root
+            .layout(self.area, self.divider_width, &mut |node, area| {
+                if let Node::Split {
+                    direction, ratio, ..
+                } = node
+                {
+                    let divider = split_area(area, *direction, *ratio, self.divider_width).1;
+                    dividers.push((*direction, divider));
+                }
+            });
+        dividers
+    }
+
+    /// Returns the direction of the divider at a pixel position, to pick a
+    /// matching pointer shape.
+    pub fn divider_at(&self, x: f64, y: f64) -> Option<Direction> {
+        self.divider_path_at(x, y)
+            .and_then(|path| self.root.at_path(&path))
+            .and_then(|node| match node {
+                Node::Split { direction, .. } => Some(*direction),
+                Node::Pane(_) => None,
+            })
+    }
+
+    /// Starts dragging the divider at a pixel position. Returns false if
+    /// there is none, in which case the press belongs to a pane.
+    pub fn start_drag(&mut self, x: f64, y: f64) -> bool {
+        self.drag = self.divider_path_at(x, y);
+        self.drag.is_some()
+    }
+
+    /// Moves the dragged divider to a pixel position. Returns true if the
+    /// layout changed and should be applied again.
+    pub fn drag_to(&mut self, x: f64, y: f64) -> bool {
+        let Some(path) = self.drag.clone() else {
+            return false;
+        };
+        let Some((area, node)) = self.root.area_at_path(self.area, self.divider_width, &path)
+        else {
+            return false;
+        };
+        let Node::Split {
+            direction, ratio, ..
+        } = node
+        else {
+            return false;
+        };
+
+        let (start, length, position) = match direction {
+            Direction::Horizontal => (area.x, area.width, x),
+            Direction::Vertical => (area.y, area.height, y),
+        };
+        let available = (length - self.divider_width).max(0);
+        if available < 2 * MIN_PANE_SIZE {
+            return false;
+        }
+
+        let first = (position - start as f64 - self.divider_width as f64 / 2.0).round() as i32;
+        let first = first.clamp(MIN_PANE_SIZE, available - MIN_PANE_SIZE);
+        let new_ratio = first as f32 / available as f32;
+        let changed = split_length(available, *ratio) != first;
+        *ratio = new_ratio;
+        changed
+    }
+
+    /// Ends a divider drag.
+    pub fn end_drag(&mut self) {
+        self.drag = None;
+    }
+
+    /// Returns true while a divider is being dragged.
+    pub fn is_dragging(&self) -> bool {
+        self.drag.is_some()
+    }
+
+    fn divider_path_at(&self, x: f64, y: f64) -> Option<Vec<Side>> {
+        if self.zoomed.is_some() {
+            return None;
+        }
+
+        let mut path = Vec::new();
+        let mut node = &self.root;
+        let mut area = self.area;
+        while let Node::Split {
+            direction,
+            ratio,
+            first,
+            second,
+        } = node
+        {
+            let (first_area, divider, second_area) =
+                split_area(area, *direction, *ratio, self.divider_width);
+            if divider.contains(x, y) {
+                return Some(path);
+            } else if first_area.contains(x, y) {
+                path.push(Side::First);
+                node = first;
+                area = first_area;
+            } else if second_area.contains(x, y) {
+                path.push(Side::Second);
+                node = second;
+                area = second_area;
+            } else {
+                return None;
+            }
+        }
+        None
+    }
+}
+
+impl Node {
+    fn find_mut(&mut self, pane: PaneId) -> Option<&mut Node> {
+        match self {
+            Node::Pane(id) if *id == pane => Some(self),
+            Node::Pane(_) => None,
+            Node::Split { first, second, .. } => {
+                first.find_mut(pane).or_else(|| second.find_mut(pane))
+            }
+        }
+    }
+
+    /// Removes `pane` from below this node, replacing its parent split with
+    /// the sibling.
+    fn remove(&mut self, pane: PaneId) -> bool {
+        let Node::Split { first, second, .. } = self else {
+            return false;
+        };
+
+        let sibling = match (&**first, &**second) {
+            (Node::Pane(id), _) if *id == pane => second,
+            (_, Node::Pane(id)) if *id == pane => first,
+            _ => return first.remove(pane) || second.remove(pane),
+        };
+        let sibling = std::mem::replace(&mut **sibling, Node::Pane(pane));
+        *self = sibling;
+        true
+    }
+
+    fn collect_panes(&self, panes: &mut Vec<PaneId>) {
+        match self {
+            Node::Pane(id) => panes.push(*id),
+            Node::Split { first, second, .. } => {
+                first.collect_panes(panes);
+                second.collect_panes(panes);
+            }
+        }
+    }
+
+    /// Visits this node and all below it with the area each covers.
+    fn layout(&self, area: Viewport, divider_width: i32, visit: &mut impl FnMut(&Node, Viewport)) {
+        visit(self, area);
+        if let Node::Split {
+            direction,
+            ratio,
+            first,
+            second,
+        } = self
+        {
+            let (first_area, _, second_area) = split_area(area, *direction, *ratio, divider_width);
+            first.layout(first_area, divider_width, visit);
+            second.layout(second_area, divider_width, visit);
+        }
+    }
+
+    fn at_path(&self, path: &[Side]) -> Option<&Node> {
+        let mut node = self;
+        for side in path {
+            let Node::Split { first, second, .. } = node else {
+                return None;
+            };
+            node = match side {
+                Side::First => first,
+                Side::Second => second,
+            };
+        }
+        Some(node)
+    }
+
+    /// Returns the node at `path` along with the area it covers.
+    fn area_at_path(
+        &mut self,
+        mut area: Viewport,
+        divider_width: i32,
+        path: &[Side],
+    ) -> Option<(Viewport, &mut Node)> {
+        let mut node = self;
+        for side in path {
+            let Node::Split {
+                direction,
+                ratio,
+                first,
+                second,
+            } = node
+            else {
+                return None;
+            };
+            let (first_area, _, second_area) = split_area(area, *direction, *ratio, divider_width);
+            (node, area) = match side {
+                Side::First => (&mut **first, first_area),
+                Side::Second => (&mut **second, second_area),
+            };
+        }
+        Some((area, node))
+    }
+}
+
+/// Returns the length of the first part when splitting `available` pixels.
+fn split_length(available: i32, ratio: f32) -> i32 {
+    ((available as f32 * ratio).round() as i32).clamp(0, available)
+}
+
+/// Splits `area` into the first pane, the divider and the second pane.
+fn split_area(
+    area: Viewport,
+    direction: Direction,
+    ratio: f32,
+    divider_width: i32,
+) -> (Viewport, Viewport, Viewport) {
+    let length = match direction {
+        Direction::Horizontal => area.width,
+        Direction::Vertical => area.height,
+    };
+    let divider_width = divider_width.min(length.max(0));
+    let available = (length - divider_width).max(0);
+    let first = split_length(available, ratio);
+    let second = available - first;
+
+    match direction {
+        Direction::Horizontal => (
+            Viewport::new(area.x, area.y, first, area.height),
+            Viewport::new(area.x + first, area.y, divider_width, area.height),
+            Viewport::new(area.x + first + divider_width, area.y, second, area.height),
+        ),
+        Direction::Vertical => (
+            Viewport::new(area.x, area.y, area.width, first),
+            Viewport::new(area.x, area.y + first, area.width, divider_width),
+            Viewport::new(area.x, area.y + first + divider_width, area.width, second),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn layout() -> SplitLayout {
+        // [ a | b / c ], 100x100 with 4px dividers
+        let mut layout = SplitLayout::new(PaneId(0), Viewport::new(0, 0, 104, 104));
+        assert!(layout.split(PaneId(0), Direction::Horizontal, PaneId(1)));
+        assert!(layout.split(PaneId(1), Direction::Vertical, PaneId(2)));
+        layout
+    }
+
+    #[test]
+    fn splits_assign_viewports() {
+        assert_eq!(
+            layout().viewports(),
+            vec![
+                (PaneId(0), Viewport::new(0, 0, 50, 104)),
+                (PaneId(1), Viewport::new(54, 0, 50, 50)),
+                (PaneId(2), Viewport::new(54, 54, 50, 50)),
+            ]
+        );
+        assert_eq!(
+            layout().dividers(),
+            vec![
+                (Direction::Horizontal, Viewport::new(50, 0, 4, 104)),
+                (Direction::Vertical, Viewport::new(54, 50, 50, 4)),
+            ]
+        );
+    }
+
+    #[test]
+    fn dragging_a_divider_resizes_panes() {
+        let mut layout = layout();
+        assert!(!layout.start_drag(20.0, 20.0));
+        assert_eq!(layout.divider_at(60.0, 52.0), Some(Direction::Vertical));
+
+        assert!(layout.start_drag(60.0, 52.0));
+        assert!(layout.drag_to(60.0, 72.0));
+        layout.end_drag();
+        assert_eq!(layout.viewports()[2].1, Viewport::new(54, 74, 50, 30));
+
+        // clamped to the minimum pane size
+        assert!(layout.start_drag(51.0, 10.0));
+        assert!(layout.drag_to(0.0, 10.0));
+        assert_eq!(layout.viewports()[0].1, Viewport::new(0, 0, 16, 104));
+    }
+
+    #[test]
+    fn zoom_hides_other_panes() {
+        let mut layout = layout();
+        assert!(layout.toggle_zoom(PaneId(1)));
+        let viewports = layout.viewports();
+        assert_eq!(viewports[1].1, layout.area());
+        assert!(viewports[0].1.is_empty() && viewports[2].1.is_empty());
+        assert!(layout.dividers().is_empty());
+
+        assert!(layout.toggle_zoom(PaneId(1)));
+        assert_eq!(layout.zoomed(), None);
+    }
+
+    #[test]
+    fn removing_a_pane_gives_space_to_its_sibling() {
+        let mut layout = layout();
+        assert!(layout.remove(PaneId(1)));
+        assert_eq!(
+            layout.viewports(),
+            vec![
+                (PaneId(0), Viewport::new(0, 0, 50, 104)),
+                (PaneId(2), Viewport::new(54, 0, 50, 104)),
+            ]
+        );
+        assert!(layout.remove(PaneId(0)));
+        assert!(!layout.remove(PaneId(2)));
+        assert_eq!(layout.panes(), vec![PaneId(2)]);
+    }
+}
@@ -1,6 +1,50 @@
+mod atlas;
 mod backend;
+mod capture;
+mod clipboard;
 mod color;
+mod compositor;
+mod coverage;
+mod detect;
 mod error;
+mod event;
+mod export;
+#[cfg(feature = "font")]
+mod font;
+#[cfg(feature = "frame-recorder")]
+mod frame_recorder;
+mod hyperlink;
+mod image;
+mod input;
+mod layout;
+#[cfg(all(unix, feature = "pty"))]
+mod pty;
+mod recording;
+mod replay;
+mod target;
+mod vt;
 
+pub use atlas::SharedAtlas;
 pub use backend::BeamtermBackend;
+pub use capture::CapturedFrame;
+#[cfg(feature = "system-clipboard")]
+pub use clipboard::SystemClipboard;
+pub use clipboard::{Clipboard, MemoryClipboard};
+pub use compositor::{Compositor, PaneId, Viewport};
+pub use coverage::{AtlasCoverage, SymbolGroup};
+pub use detect::{DetectedMatch, LinkDetector, MatchKind};
 pub use error::Error;
+pub use event::Event;
+#[cfg(feature = "font")]
+pub use font::{AtlasBuilder, FallbackAtlas, FontFamily, GlyphSet, RuntimeAtlas};
+#[cfg(feature = "frame-recorder")]
+pub use frame_recorder::FrameRecorder;
+pub use image::{ImageFit, ImageId, ImagePlaceholder, InlineImage};
+pub use input::{Key, Modifiers, MouseButton};
+pub use layout::SplitLayout;
+#[cfg(all(unix, feature = "pty"))]
+pub use pty::TerminalWidget;
+pub use recording::AsciicastRecorder;
+pub use replay::{Cast, CastEvent, CastEventKind, CastPlayer};
+pub use target::RenderTarget;
+pub use vt::VtScreen;
@@ -0,0 +1,423 @@
+use std::{
+    fs::File,
+    io::{Read, Write},
+    os::{
+        fd::{AsRawFd, FromRawFd, OwnedFd},
+        unix::process::CommandExt,
+    },
+    process::{Child, Command, Stdio},
+    sync::mpsc::{self, Receiver, TryRecvError},
+    thread,
+};
+
+use ratatui::{
+    buffer::Buffer,
+    layout::{Position, Rect},
+    widgets::Widget,
+};
+
+use crate::error::Error;
+use crate::input::{Key, Modifiers};
+use crate::vt::VtScreen;
+
+/// Lines of scrollback history kept by default.
+const DEFAULT_SCROLLBACK: usize = 1000;
+
+/// A child process running in a pseudo-terminal, rendered as a ratatui widget.
+///
+/// Output of the program is read on a background thread and applied to a
+/// [`VtScreen`] by [`poll`](Self::poll), which should be called once per
+/// frame. Rendering `&mut TerminalWidget` into an area resizes the terminal
+/// to fit it.
+///
+/// The view can be scrolled back into the scrollback history; sending keys
+/// or pasted text returns it to the bottom.
+///
+/// ```no_run
+/// # use std::process::Command;
+/// # use ratatui::{Terminal, backend::TestBackend};
+/// # use ratbeam::TerminalWidget;
+/// # let mut terminal = Terminal::new(TestBackend::new(80, 24))?;
+/// let mut shell = TerminalWidget::spawn(Command::new("/bin/sh"), 80, 24)?;
+/// shell.poll();
+/// terminal.draw(|frame| {
+///     let area = frame.area();
+///     frame.render_widget(&mut shell, area);
+///     if let Some(cursor) = shell.cursor_position(area) {
+///         frame.set_cursor_position(cursor);
+///     }
+/// })?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub struct TerminalWidget {
+    screen: VtScreen,
+    master: File,
+    child: Child,
+    output: Receiver<Vec<u8>>,
+    /// Set once the reader thread saw the end of the output.
+    closed: bool,
+    /// Lines the view is scrolled back into the scrollback history.
+    scroll_offset: usize,
+}
+
+impl TerminalWidget {
+    /// Spawns `command` in a new pseudo-terminal of `cols` columns and `rows` rows.
+    ///
+    /// The program's standard streams are attached to the terminal, which
+    /// becomes its controlling terminal. `TERM` defaults to `xterm-256color`.
+    pub fn spawn(mut command: Command, cols: u16, rows: u16) -> Result<Self, Error> {
+        let cols = cols.max(1);
+        let rows = rows.max(1);
+        let (master, slave) = open_pty(cols, rows)?;
+
+        if !command.get_envs().any(|(key, _)| key == "TERM") {
+            command.env("TERM", "xterm-256color");
+        }
+        command
+            .stdin(Stdio::from(slave.try_clone()?))
+            .stdout(Stdio::from(slave.try_clone()?))
+            .stderr(Stdio::from(slave));
+
+        unsafe {
+            command.pre_exec(|| {
+                // start a new session with the terminal as its controlling tty
+                if libc::setsid() == -1 {
+                    return Err(std::io::Error::last_os_error());
+                }
+                if libc::ioctl(0, libc::TIOCSCTTY as _, 0) == -1 {
+                    return Err(std::io::Error::last_os_error());
+                }
+                Ok(())
+            });
+        }
+
+        let child = command.spawn()?;
+        // the slave ends are owned by the command; close them so reads of the
+        // master fail once the child exits
+        drop(command);
+
+        let master = File::from(master);
+        let mut reader = master.try_clone()?;
+        let (tx, output) = mpsc::channel();
+        thread::Builder::new()
+            .name("ratbeam-pty".to_string())
+            .spawn(move || {
+                let mut buf = [0; 8192];
+                // linux reports EIO rather than EOF after the child exits
+                while let Ok(n @ 1..) = reader.read(&mut buf) {
+                    if tx.send(buf[..n].to_vec()).is_err() {
+                        break;
+                    }
+                }
+            })?;
+
+        let mut screen = VtScreen::new(cols, rows);
+        screen.set_scrollback_limit(DEFAULT_SCROLLBACK);
+
+        Ok(Self {
+            screen,
+            master,
+            child,
+            output,
+            closed: false,
+            scroll_offset: 0,
+        })
+    }
+
+    /// Applies the output written by the program since the last call.
+    ///
+    /// Replies to terminal queries are written back to the program. Returns
+    /// true if the screen may have changed.
+    pub fn poll(&mut self) -> bool {
+        let history = self.screen.scrollback_len();
+        let mut changed = false;
+        loop {
+            match self.output.try_recv() {
+                Ok(bytes) => {
+                    self.screen.process(&bytes);
+                    changed = true;
+                }
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    self.closed = true;
+                    break;
+                }
+            }
+        }
+
+        // keep a scrolled back view on the same lines
+        if self.scroll_offset > 0 {
+            let added = self.screen.scrollback_len().saturating_sub(history);
+            self.scroll_offset = (self.scroll_offset + added).min(self.screen.scrollback_len());
+        }
+
+        let responses = self.screen.take_responses();
+        if !responses.is_empty() {
+            let _ = self.write(&responses);
+        }
+        changed
+    }
+
+    /// Returns the terminal screen.
+    pub fn screen(&self) -> &VtScreen {
+        &self.screen
+    }
+
+    /// Sets the number of lines kept in the scrollback history. Defaults to 1000.
+    pub fn set_scrollback(&mut self, lines: usize) {
+        self.screen.set_scrollback_limit(lines);
+        self.scroll_offset = self.scroll_offset.min(lines);
+    }
+
+    /// Returns how many lines the view is scrolled back.
+    pub fn scroll_offset(&self) -> usize {
+        self.scroll_offset
+    }
+
+    /// Scrolls the view back into the scrollback history.
+    pub fn scroll_up(&mut self, lines: usize) {
+        self.scroll_offset = (self.scroll_offset + lines).min(self.screen.scrollback_len());
+    }
+
+    /// Scrolls the view towards the live screen.
+    pub fn scroll_down(&mut self, lines: usize) {
+        self.scroll_offset = self.scroll_offset.saturating_sub(lines);
+    }
+
+    /// Returns the view to the live screen.
+    pub fn scroll_to_bottom(&mut self) {
+        self.scroll_offset = 0;
+    }
+
+    /// Returns the title set by the program.
+    pub fn title(&self) -> &str {
+        self.screen.title()
+    }
+
+    /// Returns the OS process id of the child.
+    pub fn pid(&self) -> u32 {
+        self.child.id()
+    }
+
+    /// Returns true until the program exited and all of its output was polled.
+    pub fn is_running(&mut self) -> bool {
+        !self.closed || matches!(self.child.try_wait(), Ok(None))
+    }
+
+    /// Writes raw bytes to the program's input.
+    pub fn write(&mut self, bytes: &[u8]) -> Result<(), Error> {
+        self.master.write_all(bytes)?;
+        Ok(())
+    }
+
+    /// Sends a key press, encoded as an xterm would.
+    pub fn send_key(&mut self, key: Key, modifiers: Modifiers) -> Result<(), Error> {
+        let bytes = self.screen.encode_key(key, modifiers);
+        self.scroll_to_bottom();
+        self.write(&bytes)
+    }
+
+    /// Sends pasted text, bracketed if the program enabled bracketed paste.
+    pub fn paste(&mut self, text: &str) -> Result<(), Error> {
+        let bytes = self.screen.encode_paste(text);
+        self.scroll_to_bottom();
+        self.write(&bytes)
+    }
+
+    /// Resizes the terminal and notifies the program with `SIGWINCH`.
+    pub fn resize(&mut self, cols: u16, rows: u16) -> Result<(), Error> {
+        let cols = cols.max(1);
+        let rows = rows.max(1);
+        if self.screen.size() == (cols, rows) {
+            return Ok(());
+        }
+
+        self.screen.resize(cols, rows);
+        let size = winsize(cols, rows);
+        if unsafe { libc::ioctl(self.master.as_raw_fd(), libc::TIOCSWINSZ as _, &size) } == -1 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+        Ok(())
+    }
+
+    /// Returns the cursor position when the widget is rendered into `area`,
+    /// or `None` while the cursor is hidden or outside the area.
+    pub fn cursor_position(&self, area: Rect) -> Option<Position> {
+        let cursor = self.screen.cursor()?;
+        let y = (cursor.y as usize + self.scroll_offset).try_into().ok()?;
+        let position = Position::new(area.x + cursor.x, area.y.checked_add(y)?);
+        area.contains(position).then_some(position)
+    }
+}
+
+impl Widget for &mut TerminalWidget {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        if area.is_empty() {
+            return;
+        }
+
+        let _ = self.resize(area.width, area.height);
+        let screen = self.screen.buffer();
+        let history = self.screen.scrollback_len();
+        let area = area.intersection(buf.area);
+        for y in 0..area.height {
+            let row = match (y as usize).checked_sub(self.scroll_offset) {
+                Some(row) if row < screen.area.height as usize => {
+                    let start = screen.index_of(0, row as u16);
+                    &screen.content[start..start + screen.area.width as usize]
+                }
+                Some(_) => break,
+                None => self
+                    .screen
+                    .scrollback_line(history - self.scroll_offset + y as usize)
+                    .unwrap_or_default(),
+            };
+
+            for (x, cell) in row.iter().take(area.width as usize).enumerate() {
+                buf[(area.x + x as u16, area.y + y)] = cell.clone();
+            }
+        }
+    }
+}
+
+impl Drop for TerminalWidget {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+fn winsize(cols: u16, rows: u16) -> libc::winsize {
+    libc::winsize {
+        ws_row: rows,
+        ws_col: cols,
+        ws_xpixel: 0,
+        ws_ypixel: 0,
+    }
+}
+
+/// Opens a pseudo-terminal pair, returning the master and slave ends.
+fn open_pty(cols: u16, rows: u16) -> Result<(OwnedFd, OwnedFd), Error> {
+    let mut master = -1;
+    let mut slave = -1;
+    let size = winsize(cols, rows);
+    let result = unsafe {
+        libc::openpty(
+            &mut master,
+            &mut slave,
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+            &size as *const _ as *mut _,
+        )
+    };
+    if result == -1 {
+        return Err(std::io::Error::last_os_error().into());
+    }
+
+    let (master, slave) = unsafe { (OwnedFd::from_raw_fd(master), OwnedFd::from_raw_fd(slave)) };
+    for fd in [&master, &slave] {
+        // keep the descriptors out of other spawned processes
+        if unsafe { libc::fcntl(fd.as_raw_fd(), libc::F_SETFD, libc::FD_CLOEXEC) } == -1 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+    }
+    Ok((master, slave))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::*;
+
+    fn row(screen: &VtScreen, y: u16) -> String {
+        let buffer = screen.buffer();
+        (0..buffer.area.width)
+            .map(|x| buffer[(x, y)].symbol())
+            .collect::<String>()
+            .trim_end()
+            .to_string()
+    }
+
+    /// Polls until `condition` holds, failing after a few seconds.
+    fn poll_until(term: &mut TerminalWidget, condition: impl Fn(&VtScreen) -> bool) {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !condition(term.screen()) {
+            assert!(
+                Instant::now() < deadline,
+                "timed out: {:?}",
+                row(term.screen(), 0)
+            );
+            term.poll();
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    fn sh(script: &str) -> Command {
+        let mut command = Command::new("/bin/sh");
+        command.args(["-c", script]);
+        command
+    }
+
+    #[test]
+    fn displays_program_output() {
+        let mut term = TerminalWidget::spawn(sh("printf 'hello\\033[1;31m!'"), 20, 3).unwrap();
+        poll_until(&mut term, |screen| row(screen, 0) == "hello!");
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while term.is_running() {
+            assert!(Instant::now() < deadline);
+            term.poll();
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    #[test]
+    fn forwards_input() {
+        let mut term = TerminalWidget::spawn(sh("read line; echo \"got $line\""), 20, 3).unwrap();
+        term.write(b"abc").unwrap();
+        term.send_key(Key::Enter, Modifiers::NONE).unwrap();
+        poll_until(&mut term, |screen| row(screen, 1) == "got abc");
+    }
+
+    #[test]
+    fn resize_reaches_the_program() {
+        let mut term = TerminalWidget::spawn(sh("read line; stty size"), 20, 3).unwrap();
+        term.resize(30, 5).unwrap();
+        term.send_key(Key::Enter, Modifiers::NONE).unwrap();
+        poll_until(&mut term, |screen| row(screen, 1) == "5 30");
+    }
+
+    #[test]
+    fn scrolls_back_into_history() {
+        let mut term = TerminalWidget::spawn(sh("printf '1\\n2\\n3\\n4'; sleep 5"), 4, 2).unwrap();
+        poll_until(&mut term, |screen| row(screen, 1) == "4");
+
+        term.scroll_up(10);
+        assert_eq!(term.scroll_offset(), 2);
+
+        let area = Rect::new(0, 0, 4, 2);
+        let mut buf = Buffer::empty(area);
+        (&mut term).render(area, &mut buf);
+        assert_eq!(buf[(0, 0)].symbol(), "1");
+        assert_eq!(buf[(0, 1)].symbol(), "2");
+        assert_eq!(term.cursor_position(area), None);
+
+        term.send_key(Key::Char('x'), Modifiers::NONE).unwrap();
+        assert_eq!(term.scroll_offset(), 0);
+    }
+
+    #[test]
+    fn renders_into_area() {
+        let mut term = TerminalWidget::spawn(sh("printf ab; sleep 5"), 10, 2).unwrap();
+        poll_until(&mut term, |screen| row(screen, 0) == "ab");
+
+        let mut buf = Buffer::empty(Rect::new(0, 0, 10, 4));
+        let area = Rect::new(2, 1, 6, 2);
+        (&mut term).render(area, &mut buf);
+        assert_eq!(term.screen().size(), (6, 2));
+        assert_eq!(buf[(2, 1)].symbol(), "a");
+        assert_eq!(buf[(3, 1)].symbol(), "b");
+        assert_eq!(term.cursor_position(area), Some(Position::new(4, 1)));
+    }
+}
@@ -0,0 +1,379 @@
+use std::fmt::Write as _;
+use std::io::Write;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use ratatui::{
+    backend::{Backend, ClearType, WindowSize},
+    buffer::Cell,
+    layout::{Position, Size},
+    style::{Color, Modifier},
+};
+use unicode_width::UnicodeWidthStr;
+
+use crate::error::Error;
+use crate::export::push_sgr;
+
+/// A [`Backend`] wrapper recording every flushed frame to an asciicast v2 file.
+///
+/// Each flush appends an output event holding the cells changed since the
+/// previous flush, encoded as ANSI escape sequences and timestamped relative
+/// to the start of the recording. The resulting `.cast` file can be replayed
+/// with `asciinema play` in any terminal.
+///
+/// ```ignore
+/// let file = File::create("session.cast")?;
+/// let backend = AsciicastRecorder::new(BeamtermBackend::new(grid, gl), file)?;
+/// let mut terminal = Terminal::new(backend)?;
+/// ```
+pub struct AsciicastRecorder<B, W: Write> {
+    inner: B,
+    writer: W,
+    start: Instant,
+    size: Size,
+    /// ANSI-encoded output accumulated since the last flush.
+    pending: String,
+    /// Where the cursor of the replaying terminal is, if known.
+    replay_cursor: Option<Position>,
+    /// Style of the last encoded cell, if known.
+    replay_style: Option<(Color, Color, Modifier)>,
+    /// Whether the cursor of the replaying terminal is shown, if known.
+    replay_cursor_visible: Option<bool>,
+}
+
+impl<B, W> AsciicastRecorder<B, W>
+where
+    B: Backend,
+    W: Write,
+    Error: From<B::Error>,
+{
+    /// Wraps `inner`, writing the asciicast header to `writer`.
+    pub fn new(inner: B, mut writer: W) -> Result<Self, Error> {
+        let size = inner.size()?;
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+
+        writeln!(
+            writer,
+            r#"{{"version": 2, "width": {}, "height": {}, "timestamp": {timestamp}, "env": {{"TERM": "xterm-256color"}}}}"#,
+            size.width, size.height,
+        )?;
+
+        Ok(Self {
+            inner,
+            writer,
+            start: Instant::now(),
+            size,
+            pending: String::new(),
+            replay_cursor: None,
+            replay_style: None,
+            replay_cursor_visible: None,
+        })
+    }
+
+    /// Returns a reference to the wrapped backend.
+    pub fn inner(&self) -> &B {
+        &self.inner
+    }
+
+    /// Returns a mutable reference to the wrapped backend.
+    pub fn inner_mut(&mut self) -> &mut B {
+        &mut self.inner
+    }
+
+    /// Flushes the writer and returns the wrapped backend and writer.
+    pub fn finish(mut self) -> Result<(B, W), Error> {
+        self.writer.flush()?;
+        Ok((self.inner, self.writer))
+    }
+
+    /// Appends the ANSI encoding of a changed cell to the pending output.
+    fn encode_cell(&mut self, x: u16, y: u16, cell: &Cell) {
+        let position = Position::new(x, y);
+        if self.replay_cursor != Some(position) {
+            push_cursor_position(&mut self.pending, position);
+        }
+
+        let style = (cell.fg, cell.bg, cell.modifier);
+        if self.replay_style != Some(style) {
+            push_sgr(&mut self.pending, cell);
+            self.replay_style = Some(style);
+        }
+
+        self.pending.push_str(cell.symbol());
+
+        let width = cell.symbol().width().max(1) as u16;
+        self.replay_cursor = Some(Position::new(x.saturating_add(width), y));
+    }
+
+    /// Appends a cursor visibility sequence if the visibility changes.
+    fn encode_cursor_visibility(&mut self, visible: bool) {
+        if self.replay_cursor_visible != Some(visible) {
+            self.pending
+                .push_str(if visible { "\x1b[?25h" } else { "\x1b[?25l" });
+            self.replay_cursor_visible = Some(visible);
+        }
+    }
+
+    /// Writes a timestamped event to the cast file.
+    fn write_event(&mut self, code: &str, data: &str) -> Result<(), Error> {
+        let elapsed = self.start.elapsed().as_secs_f64();
+        writeln!(
+            self.writer,
+            "[{elapsed:.6}, \"{code}\", \"{}\"]",
+            json_escape(data)
+        )?;
+        Ok(())
+    }
+}
+
+impl<B, W> Backend for AsciicastRecorder<B, W>
+where
+    B: Backend,
+    W: Write,
+    Error: From<B::Error>,
+{
+    type Error = Error;
+
+    fn draw<'a, I>(&mut self, content: I) -> Result<(), Self::Error>
+    where
+        I: Iterator<Item = (u16, u16, &'a Cell)>,
+    {
+        let cells: Vec<_> = content.collect();
+        for &(x, y, cell) in &cells {
+            self.encode_cell(x, y, cell);
+        }
+
+        self.inner.draw(cells.into_iter())?;
+        Ok(())
+    }
+
+    fn hide_cursor(&mut self) -> Result<(), Self::Error> {
+        self.encode_cursor_visibility(false);
+        self.inner.hide_cursor()?;
+        Ok(())
+    }
+
+    fn show_cursor(&mut self) -> Result<(), Self::Error> {
+        self.encode_cursor_visibility(true);
+        self.inner.show_cursor()?;
+        Ok(())
+    }
+
+    fn get_cursor_position(&mut self) -> Result<Position, Self::Error> {
+        Ok(self.inner.get_cursor_position()?)
+    }
+
+    fn set_cursor_position<P: Into<Position>>(&mut self, position: P) -> Result<(), Self::Error> {
+        let position = position.into();
+        push_cursor_position(&mut self.pending, position);
+        self.replay_cursor = Some(position);
+        self.inner.set_cursor_position(position)?;
+        Ok(())
+    }
+
+    fn clear(&mut self) -> Result<(), Self::Error> {
+        self.pending.push_str("\x1b[0m\x1b[2J");
+        self.replay_style = None;
+        self.inner.clear()?;
+        Ok(())
+    }
+
+    fn clear_region(&mut self, clear_type: ClearType) -> Result<(), Self::Error> {
+        let erase = match clear_type {
+            ClearType::All => return self.clear(),
+            ClearType::AfterCursor => "\x1b[J",
+            ClearType::BeforeCursor => "\x1b[1J",
+            ClearType::CurrentLine => "\x1b[2K",
+            ClearType::UntilNewLine => "\x1b[K",
+        };
+
+        // the region is relative to the cursor of the wrapped backend
+        let position = self.inner.get_cursor_position()?;
+        if self.replay_cursor != Some(position) {
+            push_cursor_position(&mut self.pending, position);
+            self.replay_cursor = Some(position);
+        }
+        self.pending.push_str("\x1b[0m");
+        self.pending.push_str(erase);
+        self.replay_style = None;
+        self.inner.clear_region(clear_type)?;
+        Ok(())
+    }
+
+    fn size(&self) -> Result<Size, Self::Error> {
+        Ok(self.inner.size()?)
+    }
+
+    fn window_size(&mut self) -> Result<WindowSize, Self::Error> {
+        Ok(self.inner.window_size()?)
+    }
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        let size = self.inner.size()?;
+        if size != self.size {
+            self.size = size;
+            self.write_event("r", &format!("{}x{}", size.width, size.height))?;
+        }
+
+        if !self.pending.is_empty() {
+            let pending = std::mem::take(&mut self.pending);
+            self.write_event("o", &pending)?;
+        }
+
+        self.inner.flush()?;
+        Ok(())
+    }
+}
+
+/// Appends a cursor position (CUP) sequence; ANSI coordinates are 1-based.
+fn push_cursor_position(ansi: &mut String, position: Position) {
+    let _ = write!(ansi, "\x1b[{};{}H", position.y + 1, position.x + 1);
+}
+
+/// Escapes a string for inclusion in a JSON string literal.
+fn json_escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for ch in s.chars() {
+        match ch {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if c < ' ' || c == '\x7f' => {
+                let _ = write!(escaped, "\\u{:04x}", c as u32);
+            }
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+
+    use ratatui::{Terminal, backend::TestBackend, style::Stylize, text::Line};
+
+    use super::*;
+
+    // `TestBackend` cannot fail
+    impl From<Infallible> for Error {
+        fn from(never: Infallible) -> Self {
+            match never {}
+        }
+    }
+
+    fn recorded_lines(output: Vec<u8>) -> Vec<String> {
+        String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(str::to_string)
+            .collect()
+    }
+
+    /// Strips the timestamp from an event line.
+    fn event_payload(line: &str) -> &str {
+        line.split_once(", ").map(|(_, rest)| rest).unwrap()
+    }
+
+    #[test]
+    fn json_escape_control_characters() {
+        assert_eq!(json_escape("a\"b\\c\n"), "a\\\"b\\\\c\\n");
+        assert_eq!(json_escape("\x1b[0m"), "\\u001b[0m");
+    }
+
+    #[test]
+    fn writes_header() {
+        let recorder = AsciicastRecorder::new(TestBackend::new(20, 4), Vec::new()).unwrap();
+        let (_, output) = recorder.finish().unwrap();
+
+        let lines = recorded_lines(output);
+        assert_eq!(lines.len(), 1);
+        assert!(lines[0].starts_with(r#"{"version": 2, "width": 20, "height": 4, "timestamp": "#));
+    }
+
+    #[test]
+    fn records_changed_cells_per_frame() {
+        let mut output = Vec::new();
+        let recorder = AsciicastRecorder::new(TestBackend::new(8, 2), &mut output).unwrap();
+        let mut terminal = Terminal::new(recorder).unwrap();
+
+        terminal
+            .draw(|frame| frame.render_widget(Line::from("hi"), frame.area()))
+            .unwrap();
+        terminal
+            .draw(|frame| frame.render_widget(Line::from("ho".red()), frame.area()))
+            .unwrap();
+        drop(terminal);
+
+        let lines = recorded_lines(output);
+        let frames: Vec<&str> = lines[1..]
+            .iter()
+            .map(|line| event_payload(line))
+            .filter(|payload| payload.starts_with("\"o\""))
+            .collect();
+
+        assert_eq!(frames.len(), 2);
+        assert!(frames[0].contains("\\u001b[1;1H\\u001b[0mhi"));
+        // only the cells that changed, in their new style
+        assert!(frames[1].contains("\\u001b[1;1H\\u001b[0;38;2;128;0;0mho"));
+    }
+
+    #[test]
+    fn records_resize_events() {
+        let mut output = Vec::new();
+        let recorder = AsciicastRecorder::new(TestBackend::new(8, 2), &mut output).unwrap();
+        let mut terminal = Terminal::new(recorder).unwrap();
+
+        terminal.backend_mut().inner_mut().resize(10, 3);
+        terminal.draw(|_| {}).unwrap();
+        drop(terminal);
+
+        let lines = recorded_lines(output);
+        assert!(
+            lines
+                .iter()
+                .any(|line| event_payload(line) == "\"r\", \"10x3\"]")
+        );
+    }
+    #[test]
+    fn records_cursor_visibility_changes_only() {
+        let mut output = Vec::new();
+        let recorder = AsciicastRecorder::new(TestBackend::new(8, 2), &mut output).unwrap();
+        let mut terminal = Terminal::new(recorder).unwrap();
+
+        for _ in 0..3 {
+            terminal.draw(|_| {}).unwrap();
+        }
+        terminal.show_cursor().unwrap();
+        terminal.backend_mut().flush().unwrap();
+        drop(terminal);
+
+        let output = String::from_utf8(output).unwrap();
+        assert_eq!(output.matches("\\u001b[?25l").count(), 1);
+        assert_eq!(output.matches("\\u001b[?25h").count(), 1);
+    }
+
+    #[test]
+    fn records_region_clears_at_the_cursor() {
+        let mut output = Vec::new();
+        let mut recorder = AsciicastRecorder::new(TestBackend::new(8, 2), &mut output).unwrap();
+
+        recorder.set_cursor_position((2, 1)).unwrap();
+        recorder.clear_region(ClearType::AfterCursor).unwrap();
+        recorder.clear_region(ClearType::BeforeCursor).unwrap();
+        recorder.clear_region(ClearType::CurrentLine).unwrap();
+        recorder.clear_region(ClearType::UntilNewLine).unwrap();
+        recorder.flush().unwrap();
+        drop(recorder);
+
+        let lines = recorded_lines(output);
+        assert_eq!(
+            event_payload(&lines[1]),
+            "\"o\", \"\\u001b[2;3H\\u001b[0m\\u001b[J\\u001b[0m\\u001b[1J\\u001b[0m\\u001b[2K\\u001b[0m\\u001b[K\"]"
+        );
+    }
+}
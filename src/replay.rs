@@ -0,0 +1,371 @@
+use ratatui::{buffer::Buffer, layout::Position};
+
+use crate::error::Error;
+use crate::vt::VtScreen;
+
+/// A parsed asciicast v2 recording, as written by
+/// [`AsciicastRecorder`](crate::AsciicastRecorder).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cast {
+    /// Initial terminal width, in columns.
+    pub width: u16,
+    /// Initial terminal height, in rows.
+    pub height: u16,
+    /// Output and resize events, in recording order.
+    pub events: Vec<CastEvent>,
+}
+
+/// A timestamped event of a [`Cast`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct CastEvent {
+    /// Seconds since the start of the recording.
+    pub time: f64,
+    pub kind: CastEventKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CastEventKind {
+    /// Text and escape sequences written to the terminal.
+    Output(String),
+    /// The terminal was resized to `width` columns and `height` rows.
+    Resize { width: u16, height: u16 },
+}
+
+impl Cast {
+    /// Parses the contents of an asciicast v2 `.cast` file.
+    ///
+    /// Input and marker events are skipped.
+    pub fn parse(cast: &str) -> Result<Self, Error> {
+        let mut lines = cast
+            .lines()
+            .enumerate()
+            .filter(|(_, l)| !l.trim().is_empty());
+
+        let (_, header) = lines
+            .next()
+            .ok_or_else(|| Error::Cast("missing header".to_string()))?;
+        if header_field(header, "version") != Some(2) {
+            return Err(Error::Cast("only asciicast v2 is supported".to_string()));
+        }
+
+        let size = |key| {
+            header_field(header, key)
+                .and_then(|v| u16::try_from(v).ok())
+                .ok_or_else(|| Error::Cast(format!("header is missing the {key}")))
+        };
+        let width = size("width")?;
+        let height = size("height")?;
+
+        let mut events = Vec::new();
+        for (idx, line) in lines {
+            let invalid = || Error::Cast(format!("line {}: invalid event", idx + 1));
+            let (time, code, data) = parse_event(line).ok_or_else(invalid)?;
+
+            let kind = match code.as_str() {
+                "o" => CastEventKind::Output(data),
+                "r" => {
+                    let (w, h) = data.split_once('x').ok_or_else(invalid)?;
+                    CastEventKind::Resize {
+                        width: w.parse().map_err(|_| invalid())?,
+                        height: h.parse().map_err(|_| invalid())?,
+                    }
+                }
+                _ => continue,
+            };
+            events.push(CastEvent { time, kind });
+        }
+
+        Ok(Self {
+            width,
+            height,
+            events,
+        })
+    }
+
+    /// Returns the time of the last event, in seconds.
+    pub fn duration(&self) -> f64 {
+        self.events.last().map_or(0.0, |event| event.time)
+    }
+}
+
+/// Plays back a [`Cast`], reconstructing the screen at any point in time.
+///
+/// The screen is a ratatui [`Buffer`] that can be copied into a frame, which
+/// makes replays render through the same path as live applications.
+pub struct CastPlayer {
+    cast: Cast,
+    /// Number of events applied to the screen.
+    applied: usize,
+    time: f64,
+    screen: VtScreen,
+}
+
+impl CastPlayer {
+    /// Creates a player positioned at the start of `cast`.
+    pub fn new(cast: Cast) -> Self {
+        let screen = VtScreen::new(cast.width, cast.height);
+        Self {
+            cast,
+            applied: 0,
+            time: 0.0,
+            screen,
+        }
+    }
+
+    /// Returns the recording being played.
+    pub fn cast(&self) -> &Cast {
+        &self.cast
+    }
+
+    /// Returns the playback position, in seconds.
+    pub fn time(&self) -> f64 {
+        self.time
+    }
+
+    /// Returns true once all events have been played.
+    pub fn is_finished(&self) -> bool {
+        self.applied == self.cast.events.len()
+    }
+
+    /// Returns the screen content at the playback position.
+    pub fn screen(&self) -> &Buffer {
+        self.screen.buffer()
+    }
+
+    /// Returns the cursor position, or `None` while the cursor is hidden.
+    pub fn cursor(&self) -> Option<Position> {
+        self.screen.cursor()
+    }
+
+    /// Moves the playback position forward by `seconds`.
+    pub fn advance(&mut self, seconds: f64) {
+        self.seek(self.time + seconds);
+    }
+
+    /// Moves the playback position to `time`, clamped to the recording.
+    pub fn seek(&mut self, time: f64) {
+        let time = time.clamp(0.0, self.cast.duration());
+        if time < self.time {
+            self.rewind();
+        }
+
+        while self
+            .cast
+            .events
+            .get(self.applied)
+            .is_some_and(|event| event.time <= time)
+        {
+            self.apply_next();
+        }
+        self.time = time;
+    }
+
+    /// Plays exactly one event. Returns false at the end of the recording.
+    pub fn step_forward(&mut self) -> bool {
+        let Some(event) = self.cast.events.get(self.applied) else {
+            return false;
+        };
+
+        self.time = event.time;
+        self.apply_next();
+        true
+    }
+
+    /// Undoes the last played event. Returns false at the start of the recording.
+    pub fn step_backward(&mut self) -> bool {
+        let Some(target) = self.applied.checked_sub(1) else {
+            return false;
+        };
+
+        self.rewind();
+        while self.applied < target {
+            self.apply_next();
+        }
+        self.time = target
+            .checked_sub(1)
+            .map_or(0.0, |idx| self.cast.events[idx].time);
+        true
+    }
+
+    fn rewind(&mut self) {
+        self.screen = VtScreen::new(self.cast.width, self.cast.height);
+        self.applied = 0;
+        self.time = 0.0;
+    }
+
+    fn apply_next(&mut self) {
+        match &self.cast.events[self.applied].kind {
+            CastEventKind::Output(data) => self.screen.process(data.as_bytes()),
+            CastEventKind::Resize { width, height } => self.screen.resize(*width, *height),
+        }
+        self.applied += 1;
+    }
+}
+
+/// Reads an integer field of the header object.
+fn header_field(header: &str, key: &str) -> Option<u64> {
+    let start = header.find(&format!("\"{key}\""))? + key.len() + 2;
+    let value = header[start..].trim_start().strip_prefix(':')?.trim_start();
+    let end = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    value[..end].parse().ok()
+}
+
+/// Parses an event line of the form `[time, "code", "data"]`.
+fn parse_event(line: &str) -> Option<(f64, String, String)> {
+    let rest = line.trim().strip_prefix('[')?.strip_suffix(']')?;
+    let (time, rest) = rest.split_once(',')?;
+    let time = time.trim().parse().ok()?;
+
+    let (code, rest) = parse_json_string(rest.trim_start())?;
+    let rest = rest.trim_start().strip_prefix(',')?;
+    let (data, rest) = parse_json_string(rest.trim_start())?;
+
+    rest.trim().is_empty().then_some((time, code, data))
+}
+
+/// Parses a JSON string literal at the start of `s`, returning it and the rest.
+fn parse_json_string(s: &str) -> Option<(String, &str)> {
+    let mut chars = s.strip_prefix('"')?.char_indices();
+    let mut value = String::new();
+    while let Some((idx, ch)) = chars.next() {
+        match ch {
+            '"' => return Some((value, &s[idx + 2..])),
+            '\\' => {
+                let escaped = match chars.next()?.1 {
+                    'n' => '\n',
+                    'r' => '\r',
+                    't' => '\t',
+                    'b' => '\x08',
+                    'f' => '\x0c',
+                    'u' => {
+                        let hex: String = (0..4)
+                            .map(|_| chars.next().map(|(_, c)| c))
+                            .collect::<Option<_>>()?;
+                        let code = u32::from_str_radix(&hex, 16).ok()?;
+                        // surrogate pairs are not produced by the recorder
+                        char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER)
+                    }
+                    c => c,
+                };
+                value.push(escaped);
+            }
+            c => value.push(c),
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use ratatui::{
+        Terminal,
+        backend::TestBackend,
+        layout::Rect,
+        style::{Color, Modifier, Stylize},
+        text::Line,
+    };
+
+    use super::*;
+    use crate::AsciicastRecorder;
+
+    const CAST: &str = r#"{"version": 2, "width": 6, "height": 2, "timestamp": 0}
+[0.100000, "o", "\u001b[1;1H\u001b[0mab"]
+[0.500000, "i", "q"]
+[1.000000, "o", "\u001b[2;3H\u001b[0;1;38;2;255;0;0mcd"]
+[2.000000, "r", "8x3"]
+"#;
+
+    fn row(buffer: &Buffer, y: u16) -> String {
+        (0..buffer.area.width)
+            .map(|x| buffer[(x, y)].symbol())
+            .collect()
+    }
+
+    #[test]
+    fn parse_cast() {
+        let cast = Cast::parse(CAST).unwrap();
+        assert_eq!((cast.width, cast.height), (6, 2));
+        assert_eq!(cast.events.len(), 3);
+        assert_eq!(
+            cast.events[0].kind,
+            CastEventKind::Output("\x1b[1;1H\x1b[0mab".to_string())
+        );
+        assert_eq!(
+            cast.events[2].kind,
+            CastEventKind::Resize {
+                width: 8,
+                height: 3
+            }
+        );
+        assert_eq!(cast.duration(), 2.0);
+    }
+
+    #[test]
+    fn parse_rejects_malformed_input() {
+        assert!(Cast::parse("").is_err());
+        assert!(Cast::parse(r#"{"version": 1, "width": 2, "height": 2}"#).is_err());
+        assert!(
+            Cast::parse("{\"version\": 2, \"width\": 2, \"height\": 2}\n[0.1, \"o\"]").is_err()
+        );
+    }
+
+    #[test]
+    fn seek_forward_and_back() {
+        let mut player = CastPlayer::new(Cast::parse(CAST).unwrap());
+
+        player.seek(1.5);
+        assert_eq!(row(player.screen(), 0), "ab    ");
+        assert_eq!(row(player.screen(), 1), "  cd  ");
+        let cell = &player.screen()[(2, 1)];
+        assert_eq!(cell.fg, Color::Rgb(255, 0, 0));
+        assert!(cell.modifier.contains(Modifier::BOLD));
+
+        player.seek(0.2);
+        assert_eq!(row(player.screen(), 1), "      ");
+        assert_eq!(player.time(), 0.2);
+    }
+
+    #[test]
+    fn step_through_events() {
+        let mut player = CastPlayer::new(Cast::parse(CAST).unwrap());
+
+        assert!(player.step_forward());
+        assert!(player.step_forward());
+        assert!(player.step_forward());
+        assert!(player.is_finished());
+        assert_eq!(player.screen().area, Rect::new(0, 0, 8, 3));
+        assert!(!player.step_forward());
+
+        assert!(player.step_backward());
+        assert_eq!(player.screen().area, Rect::new(0, 0, 6, 2));
+        assert_eq!(player.time(), 1.0);
+        assert_eq!(row(player.screen(), 1), "  cd  ");
+    }
+
+    #[test]
+    fn replays_recorded_session() {
+        let mut output = Vec::new();
+        let recorder = AsciicastRecorder::new(TestBackend::new(10, 2), &mut output).unwrap();
+        let mut terminal = Terminal::new(recorder).unwrap();
+        terminal
+            .draw(|frame| frame.render_widget(Line::from("héllo 中".green()), frame.area()))
+            .unwrap();
+        let expected = terminal.backend().inner().buffer().clone();
+        drop(terminal);
+
+        let cast = Cast::parse(&String::from_utf8(output).unwrap()).unwrap();
+        let mut player = CastPlayer::new(cast);
+        player.seek(f64::MAX);
+
+        for position in expected.area.positions() {
+            assert_eq!(
+                player.screen()[position].symbol(),
+                expected[position].symbol()
+            );
+        }
+        assert_eq!(player.screen()[(0, 0)].fg, Color::Rgb(0, 128, 0));
+    }
+}
@@ -0,0 +1,412 @@
+use beamterm_core::{Drawable, GlState, RenderContext, TerminalGrid};
+use glow::HasContext;
+
+use crate::error::Error;
+use crate::image::ImageLayer;
+
+/// Where [`BeamtermBackend::render_to`](crate::BeamtermBackend::render_to)
+/// draws the grid inside a host application.
+///
+/// The grid is drawn at its canvas size, see [`TerminalGrid::canvas_size`],
+/// with its lower-left corner at `x`, `y` in the GL window coordinates of the
+/// framebuffer.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RenderTarget {
+    framebuffer: Option<glow::Framebuffer>,
+    x: i32,
+    y: i32,
+    clear_color: Option<[f32; 4]>,
+}
+
+impl RenderTarget {
+    /// Targets `framebuffer`, or the default framebuffer for `None`.
+    pub fn new(framebuffer: Option<glow::Framebuffer>) -> Self {
+        Self {
+            framebuffer,
+            x: 0,
+            y: 0,
+            clear_color: None,
+        }
+    }
+
+    /// Places the lower-left corner of the grid at `x`, `y`.
+    pub fn at(mut self, x: i32, y: i32) -> Self {
+        self.x = x;
+        self.y = y;
+        self
+    }
+
+    /// Clears the area covered by the grid to `rgba` before drawing. By
+    /// default the grid is drawn over the existing content.
+    pub fn with_clear_color(mut self, rgba: [f32; 4]) -> Self {
+        self.clear_color = Some(rgba);
+        self
+    }
+
+    /// Returns the targeted framebuffer; `None` is the default framebuffer.
+    pub fn framebuffer(&self) -> Option<glow::Framebuffer> {
+        self.framebuffer
+    }
+}
+
+/// An owned RGBA8 texture with a framebuffer for rendering into it.
+pub(crate) struct TextureTarget {
+    texture: glow::Texture,
+    framebuffer: glow::Framebuffer,
+    size: (i32, i32),
+}
+
+impl TextureTarget {
+    /// Allocates a texture of `size` pixels, preserving the host's bindings.
+    pub(crate) fn new(gl: &glow::Context, size: (i32, i32)) -> Result<Self, Error> {
+        let (width, height) = size;
+        unsafe {
+            let previous_texture = gl.get_parameter_texture(glow::TEXTURE_BINDING_2D);
+            let previous_framebuffer = gl.get_parameter_framebuffer(glow::DRAW_FRAMEBUFFER_BINDING);
+
+            let texture = gl.create_texture().map_err(Error::Other)?;
+            gl.bind_texture(glow::TEXTURE_2D, Some(texture));
+            gl.tex_image_2d(
+                glow::TEXTURE_2D,
+                0,
+                glow::RGBA8 as i32,
+                width,
+                height,
+                0,
+                glow::RGBA,
+                glow::UNSIGNED_BYTE,
+                glow::PixelUnpackData::Slice(None),
+            );
+            for (parameter, value) in [
+                (glow::TEXTURE_MIN_FILTER, glow::LINEAR),
+                (glow::TEXTURE_MAG_FILTER, glow::LINEAR),
+                (glow::TEXTURE_WRAP_S, glow::CLAMP_TO_EDGE),
+                (glow::TEXTURE_WRAP_T, glow::CLAMP_TO_EDGE),
+            ] {
+                gl.tex_parameter_i32(glow::TEXTURE_2D, parameter, value as i32);
+            }
+            gl.bind_texture(glow::TEXTURE_2D, previous_texture);
+
+            let framebuffer = match gl.create_framebuffer() {
+                Ok(framebuffer) => framebuffer,
+                Err(e) => {
+                    gl.delete_texture(texture);
+                    return Err(Error::Other(e));
+                }
+            };
+            gl.bind_framebuffer(glow::DRAW_FRAMEBUFFER, Some(framebuffer));
+            gl.framebuffer_texture_2d(
+                glow::DRAW_FRAMEBUFFER,
+                glow::COLOR_ATTACHMENT0,
+                glow::TEXTURE_2D,
+                Some(texture),
+                0,
+            );
+            let status = gl.check_framebuffer_status(glow::DRAW_FRAMEBUFFER);
+            gl.bind_framebuffer(glow::DRAW_FRAMEBUFFER, previous_framebuffer);
+
+            let target = Self {
+                texture,
+                framebuffer,
+                size,
+            };
+            if status != glow::FRAMEBUFFER_COMPLETE {
+                target.delete(gl);
+                return Err(Error::Other("incomplete texture framebuffer".to_string()));
+            }
+            Ok(target)
+        }
+    }
+
+    pub(crate) fn texture(&self) -> glow::Texture {
+        self.texture
+    }
+
+    pub(crate) fn size(&self) -> (i32, i32) {
+        self.size
+    }
+
+    pub(crate) fn framebuffer(&self) -> glow::Framebuffer {
+        self.framebuffer
+    }
+
+    /// Returns a [`RenderTarget`] covering the whole texture.
+    pub(crate) fn render_target(&self) -> RenderTarget {
+        RenderTarget::new(Some(self.framebuffer)).with_clear_color([0.0, 0.0, 0.0, 1.0])
+    }
+
+    pub(crate) fn delete(self, gl: &glow::Context) {
+        unsafe {
+            gl.delete_framebuffer(self.framebuffer);
+            gl.delete_texture(self.texture);
+        }
+    }
+}
+
+/// Host GL state touched while drawing the grid.
+#[cfg_attr(test, derive(Debug, PartialEq))]
+struct SavedState {
+    framebuffer: Option<glow::Framebuffer>,
+    viewport: [i32; 4],
+    scissor_box: [i32; 4],
+    clear_color: [f32; 4],
+    color_mask: [bool; 4],
+    depth_mask: bool,
+    scissor_test: bool,
+    blend: bool,
+    /// Source and destination RGB and alpha factors.
+    blend_func: [u32; 4],
+    /// RGB and alpha blend equations.
+    blend_equation: [u32; 2],
+    depth_test: bool,
+    stencil_test: bool,
+    cull_face: bool,
+    program: Option<glow::Program>,
+    vertex_array: Option<glow::VertexArray>,
+    array_buffer: Option<glow::Buffer>,
+    uniform_buffer: Option<glow::Buffer>,
+    active_texture: u32,
+    /// The 2D array texture bound to texture unit 0.
+    texture_array: Option<glow::Texture>,
+    /// The 2D texture bound to texture unit 0.
+    texture_2d: Option<glow::Texture>,
+}
+
+impl SavedState {
+    unsafe fn save(gl: &glow::Context) -> Self {
+        unsafe {
+            let mut viewport = [0; 4];
+            gl.get_parameter_i32_slice(glow::VIEWPORT, &mut viewport);
+            let mut scissor_box = [0; 4];
+            gl.get_parameter_i32_slice(glow::SCISSOR_BOX, &mut scissor_box);
+            let mut clear_color = [0.0; 4];
+            gl.get_parameter_f32_slice(glow::COLOR_CLEAR_VALUE, &mut clear_color);
+
+            let active_texture = gl.get_parameter_i32(glow::ACTIVE_TEXTURE) as u32;
+            gl.active_texture(glow::TEXTURE0);
+            let texture_array = gl.get_parameter_texture(glow::TEXTURE_BINDING_2D_ARRAY);
+            let texture_2d = gl.get_parameter_texture(glow::TEXTURE_BINDING_2D);
+            let blend_func = [
+                glow::BLEND_SRC_RGB,
+                glow::BLEND_DST_RGB,
+                glow::BLEND_SRC_ALPHA,
+                glow::BLEND_DST_ALPHA,
+            ]
+            .map(|parameter| gl.get_parameter_i32(parameter) as u32);
+            let blend_equation = [glow::BLEND_EQUATION_RGB, glow::BLEND_EQUATION_ALPHA]
+                .map(|parameter| gl.get_parameter_i32(parameter) as u32);
+
+            Self {
+                framebuffer: gl.get_parameter_framebuffer(glow::DRAW_FRAMEBUFFER_BINDING),
+                viewport,
+                scissor_box,
+                clear_color,
+                color_mask: gl.get_parameter_bool_array(glow::COLOR_WRITEMASK),
+                depth_mask: gl.get_parameter_bool(glow::DEPTH_WRITEMASK),
+                scissor_test: gl.is_enabled(glow::SCISSOR_TEST),
+                blend: gl.is_enabled(glow::BLEND),
+                blend_func,
+                blend_equation,
+                depth_test: gl.is_enabled(glow::DEPTH_TEST),
+                stencil_test: gl.is_enabled(glow::STENCIL_TEST),
+                cull_face: gl.is_enabled(glow::CULL_FACE),
+                program: gl.get_parameter_program(glow::CURRENT_PROGRAM),
+                vertex_array: gl.get_parameter_vertex_array(glow::VERTEX_ARRAY_BINDING),
+                array_buffer: gl.get_parameter_buffer(glow::ARRAY_BUFFER_BINDING),
+                uniform_buffer: gl.get_parameter_buffer(glow::UNIFORM_BUFFER_BINDING),
+                active_texture,
+                texture_array,
+                texture_2d,
+            }
+        }
+    }
+
+    unsafe fn restore(self, gl: &glow::Context) {
+        unsafe {
+            gl.bind_framebuffer(glow::DRAW_FRAMEBUFFER, self.framebuffer);
+            let [x, y, w, h] = self.viewport;
+            gl.viewport(x, y, w, h);
+            let [x, y, w, h] = self.scissor_box;
+            gl.scissor(x, y, w, h);
+            let [r, g, b, a] = self.clear_color;
+            gl.clear_color(r, g, b, a);
+            let [r, g, b, a] = self.color_mask;
+            gl.color_mask(r, g, b, a);
+            gl.depth_mask(self.depth_mask);
+            let [src_rgb, dst_rgb, src_alpha, dst_alpha] = self.blend_func;
+            gl.blend_func_separate(src_rgb, dst_rgb, src_alpha, dst_alpha);
+            let [rgb, alpha] = self.blend_equation;
+            gl.blend_equation_separate(rgb, alpha);
+
+            for (capability, enabled) in [
+                (glow::SCISSOR_TEST, self.scissor_test),
+                (glow::BLEND, self.blend),
+                (glow::DEPTH_TEST, self.depth_test),
+                (glow::STENCIL_TEST, self.stencil_test),
+                (glow::CULL_FACE, self.cull_face),
+            ] {
+                set_enabled(gl, capability, enabled);
+            }
+
+            gl.use_program(self.program);
+            gl.bind_vertex_array(self.vertex_array);
+            gl.bind_buffer(glow::ARRAY_BUFFER, self.array_buffer);
+            gl.bind_buffer(glow::UNIFORM_BUFFER, self.uniform_buffer);
+            gl.active_texture(glow::TEXTURE0);
+            gl.bind_texture(glow::TEXTURE_2D_ARRAY, self.texture_array);
+            gl.bind_texture(glow::TEXTURE_2D, self.texture_2d);
+            gl.active_texture(self.active_texture);
+        }
+    }
+}
+
+unsafe fn set_enabled(gl: &glow::Context, capability: u32, enabled: bool) {
+    unsafe {
+        if enabled {
+            gl.enable(capability);
+        } else {
+            gl.disable(capability);
+        }
+    }
+}
+
+/// Draws `grid` and the `images` over it into `target`, restoring the host's GL state afterwards.
+///
+/// A separate [`GlState`] tracks the state set here, as the host's changes
+/// would invalidate a long-lived one.
+pub(crate) fn render_to(
+    gl: &glow::Context,
+    grid: &TerminalGrid,
+    images: &ImageLayer,
+    target: &RenderTarget,
+) -> Result<(), Error> {
+    let (width, height) = grid.canvas_size();
+    if width <= 0 || height <= 0 {
+        return Ok(());
+    }
+
+    unsafe {
+        let saved = SavedState::save(gl);
+
+        gl.bind_framebuffer(glow::DRAW_FRAMEBUFFER, target.framebuffer);
+        for capability in [
+            glow::BLEND,
+            glow::DEPTH_TEST,
+            glow::STENCIL_TEST,
+            glow::CULL_FACE,
+        ] {
+            gl.disable(capability);
+        }
+        gl.color_mask(true, true, true, true);
+        gl.blend_equation(glow::FUNC_ADD);
+
+        // SavedState::save left texture unit 0 active, as GlState assumes
+        let mut gl_state = GlState::new(gl);
+        gl_state.viewport(gl, target.x, target.y, width, height);
+        if let Some([r, g, b, a]) = target.clear_color {
+            gl.enable(glow::SCISSOR_TEST);
+            gl.scissor(target.x, target.y, width, height);
+            gl.clear_color(r, g, b, a);
+            gl.clear(glow::COLOR_BUFFER_BIT);
+        }
+        gl.disable(glow::SCISSOR_TEST);
+
+        let mut ctx = RenderContext {
+            gl,
+            state: &mut gl_state,
+        };
+        let result = grid.prepare(&mut ctx);
+        if result.is_ok() {
+            grid.draw(&mut ctx);
+        }
+        grid.cleanup(&mut ctx);
+        if result.is_ok() {
+            images.draw(&mut ctx, grid);
+        }
+
+        saved.restore(gl);
+        result?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use glutin::api::egl::{context::PossiblyCurrentContext, device::Device, display::Display};
+    use glutin::config::{ConfigSurfaceTypes, ConfigTemplateBuilder};
+    use glutin::context::{ContextApi, ContextAttributesBuilder, Version};
+    use glutin::display::GlDisplay;
+
+    use super::*;
+
+    /// Makes a surfaceless OpenGL 3.3 context current on the first EGL
+    /// device, if there is one.
+    fn headless_gl() -> Option<(glow::Context, PossiblyCurrentContext)> {
+        let device = Device::query_devices().ok()?.next()?;
+        let display = unsafe { Display::with_device(&device, None) }.ok()?;
+        let template = ConfigTemplateBuilder::new()
+            .with_surface_type(ConfigSurfaceTypes::empty())
+            .build();
+        let config = unsafe { display.find_configs(template) }.ok()?.next()?;
+        let attributes = ContextAttributesBuilder::new()
+            .with_context_api(ContextApi::OpenGl(Some(Version::new(3, 3))))
+            .build(None);
+        let context = unsafe { display.create_context(&config, &attributes) }
+            .ok()?
+            .make_current_surfaceless()
+            .ok()?;
+        let gl = unsafe {
+            glow::Context::from_loader_function_cstr(|name| display.get_proc_address(name))
+        };
+        Some((gl, context))
+    }
+
+    #[test]
+    fn saved_state_round_trip() {
+        let Some((gl, _context)) = headless_gl() else {
+            eprintln!("no EGL device, skipping");
+            return;
+        };
+
+        unsafe {
+            gl.viewport(1, 2, 30, 40);
+            gl.color_mask(true, false, true, false);
+            gl.depth_mask(false);
+            gl.blend_func_separate(glow::SRC_ALPHA, glow::ONE, glow::ZERO, glow::ONE);
+            gl.blend_equation_separate(glow::FUNC_SUBTRACT, glow::MAX);
+            gl.enable(glow::BLEND);
+            let saved = SavedState::save(&gl);
+            let expected = SavedState::save(&gl);
+
+            gl.viewport(0, 0, 8, 8);
+            gl.color_mask(true, true, true, true);
+            gl.depth_mask(true);
+            gl.blend_func(glow::ONE, glow::ZERO);
+            gl.blend_equation(glow::FUNC_ADD);
+            gl.disable(glow::BLEND);
+            saved.restore(&gl);
+
+            assert_eq!(SavedState::save(&gl), expected);
+            assert_eq!(
+                gl.get_parameter_bool_array::<4>(glow::COLOR_WRITEMASK),
+                [true, false, true, false]
+            );
+            assert!(!gl.get_parameter_bool(glow::DEPTH_WRITEMASK));
+            assert_eq!(
+                gl.get_parameter_i32(glow::BLEND_EQUATION_ALPHA) as u32,
+                glow::MAX
+            );
+        }
+    }
+
+    #[test]
+    fn render_target_builder() {
+        let target = RenderTarget::new(None)
+            .at(10, 20)
+            .with_clear_color([0.0, 0.0, 0.0, 1.0]);
+        assert_eq!(target.framebuffer(), None);
+        assert_eq!((target.x, target.y), (10, 20));
+        assert_eq!(target.clear_color, Some([0.0, 0.0, 0.0, 1.0]));
+    }
+}
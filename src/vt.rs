@@ -0,0 +1,5 @@
+mod keys;
+mod parser;
+mod screen;
+
+pub use screen::VtScreen;
@@ -0,0 +1,157 @@
+use crate::input::{Key, Modifiers};
+
+/// Encodes a key press as the byte sequence an xterm sends to the program.
+///
+/// `application_cursor` selects the DECCKM variants of the cursor keys.
+pub(crate) fn encode_key(key: Key, modifiers: Modifiers, application_cursor: bool) -> Vec<u8> {
+    // xterm's modifier parameter: 1 + shift + 2 * alt + 4 * ctrl
+    let modifier_param =
+        1 + modifiers.shift as u8 + 2 * modifiers.alt as u8 + 4 * modifiers.ctrl as u8;
+
+    let mut bytes = match key {
+        Key::Char(ch) => return encode_char(ch, modifiers),
+        Key::Enter => vec![b'\r'],
+        Key::Tab if modifiers.shift => b"\x1b[Z".to_vec(),
+        Key::Tab => vec![b'\t'],
+        Key::Backspace if modifiers.ctrl => vec![0x08],
+        Key::Backspace => vec![0x7f],
+        Key::Escape => vec![0x1b],
+        Key::Up => cursor_key(b'A', modifier_param, application_cursor),
+        Key::Down => cursor_key(b'B', modifier_param, application_cursor),
+        Key::Right => cursor_key(b'C', modifier_param, application_cursor),
+        Key::Left => cursor_key(b'D', modifier_param, application_cursor),
+        Key::Home => cursor_key(b'H', modifier_param, application_cursor),
+        Key::End => cursor_key(b'F', modifier_param, application_cursor),
+        Key::Insert => tilde_key(2, modifier_param),
+        Key::Delete => tilde_key(3, modifier_param),
+        Key::PageUp => tilde_key(5, modifier_param),
+        Key::PageDown => tilde_key(6, modifier_param),
+        Key::F(n @ 1..=4) => {
+            let final_byte = b'P' + n - 1;
+            if modifier_param > 1 {
+                format!("\x1b[1;{modifier_param}{}", final_byte as char).into_bytes()
+            } else {
+                vec![0x1b, b'O', final_byte]
+            }
+        }
+        Key::F(n @ 5..=12) => {
+            const CODES: [u8; 8] = [15, 17, 18, 19, 20, 21, 23, 24];
+            tilde_key(CODES[n as usize - 5], modifier_param)
+        }
+        Key::F(_) => Vec::new(),
+    };
+
+    // alt prefixes the keys that carry no modifier parameter with ESC
+    if modifiers.alt && matches!(key, Key::Enter | Key::Tab | Key::Backspace | Key::Escape) {
+        bytes.insert(0, 0x1b);
+    }
+    bytes
+}
+
+fn encode_char(ch: char, modifiers: Modifiers) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    if modifiers.alt {
+        bytes.push(0x1b);
+    }
+
+    let control = match ch {
+        _ if !modifiers.ctrl => None,
+        'a'..='z' | 'A'..='Z' => Some(ch.to_ascii_lowercase() as u8 - b'a' + 1),
+        '@' | ' ' | '2' => Some(0x00),
+        '[' | '3' => Some(0x1b),
+        '\\' | '4' => Some(0x1c),
+        ']' | '5' => Some(0x1d),
+        '^' | '6' => Some(0x1e),
+        '_' | '7' | '/' => Some(0x1f),
+        '?' | '8' => Some(0x7f),
+        _ => None,
+    };
+
+    match control {
+        Some(byte) => bytes.push(byte),
+        None => bytes.extend_from_slice(ch.encode_utf8(&mut [0; 4]).as_bytes()),
+    }
+    bytes
+}
+
+fn cursor_key(final_byte: u8, modifier_param: u8, application_cursor: bool) -> Vec<u8> {
+    if modifier_param > 1 {
+        format!("\x1b[1;{modifier_param}{}", final_byte as char).into_bytes()
+    } else if application_cursor {
+        vec![0x1b, b'O', final_byte]
+    } else {
+        vec![0x1b, b'[', final_byte]
+    }
+}
+
+fn tilde_key(code: u8, modifier_param: u8) -> Vec<u8> {
+    if modifier_param > 1 {
+        format!("\x1b[{code};{modifier_param}~").into_bytes()
+    } else {
+        format!("\x1b[{code}~").into_bytes()
+    }
+}
+
+/// Encodes pasted text, wrapping it in bracketed paste markers if requested.
+///
+/// Line breaks are sent as carriage returns, as typed.
+pub(crate) fn encode_paste(text: &str, bracketed: bool) -> Vec<u8> {
+    let text = text.replace("\r\n", "\r").replace('\n', "\r");
+    if bracketed {
+        // a pasted end marker would let the text escape the bracket
+        let text = text.replace("\x1b[201~", "");
+        format!("\x1b[200~{text}\x1b[201~").into_bytes()
+    } else {
+        text.into_bytes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SHIFT: Modifiers = Modifiers {
+        shift: true,
+        ctrl: false,
+        alt: false,
+    };
+    const ALT: Modifiers = Modifiers {
+        shift: false,
+        ctrl: false,
+        alt: true,
+    };
+
+    #[test]
+    fn plain_and_control_characters() {
+        assert_eq!(
+            encode_key(Key::Char('é'), Modifiers::NONE, false),
+            "é".as_bytes()
+        );
+        assert_eq!(encode_key(Key::Char('c'), Modifiers::CTRL, false), [0x03]);
+        assert_eq!(encode_key(Key::Char('['), Modifiers::CTRL, false), [0x1b]);
+        assert_eq!(encode_key(Key::Char('x'), ALT, false), b"\x1bx");
+    }
+
+    #[test]
+    fn cursor_keys_follow_decckm() {
+        assert_eq!(encode_key(Key::Up, Modifiers::NONE, false), b"\x1b[A");
+        assert_eq!(encode_key(Key::Up, Modifiers::NONE, true), b"\x1bOA");
+        assert_eq!(encode_key(Key::Left, Modifiers::CTRL, true), b"\x1b[1;5D");
+    }
+
+    #[test]
+    fn editing_and_function_keys() {
+        assert_eq!(encode_key(Key::Delete, Modifiers::NONE, false), b"\x1b[3~");
+        assert_eq!(encode_key(Key::PageUp, SHIFT, false), b"\x1b[5;2~");
+        assert_eq!(encode_key(Key::F(1), Modifiers::NONE, false), b"\x1bOP");
+        assert_eq!(encode_key(Key::F(12), Modifiers::NONE, false), b"\x1b[24~");
+        assert_eq!(encode_key(Key::Tab, SHIFT, false), b"\x1b[Z");
+        assert_eq!(encode_key(Key::Backspace, ALT, false), b"\x1b\x7f");
+    }
+
+    #[test]
+    fn paste_brackets_and_normalizes_newlines() {
+        assert_eq!(encode_paste("a\nb", false), b"a\rb");
+        assert_eq!(encode_paste("x\x1b[201~y", true), b"\x1b[200~xy\x1b[201~");
+    }
+}
@@ -0,0 +1,410 @@
+/// Upper bound on collected parameter and intermediate bytes; longer
+/// sequences are parsed to completion but not dispatched.
+const MAX_SEQUENCE_LEN: usize = 256;
+/// Upper bound on the payload of an OSC string.
+const MAX_OSC_LEN: usize = 4096;
+
+/// Receives the actions recognized by the [`Parser`].
+pub(crate) trait Perform {
+    /// Draws a printable character.
+    fn print(&mut self, ch: char);
+
+    /// Executes a C0 control character.
+    fn execute(&mut self, byte: u8);
+
+    /// Dispatches a control sequence `CSI intermediates params action`.
+    ///
+    /// Private markers such as `?` are reported as intermediates. Each
+    /// parameter holds its `:`-separated sub-parameters; omitted values are 0.
+    fn csi_dispatch(&mut self, params: &[Vec<u16>], intermediates: &[u8], action: char);
+
+    /// Dispatches an escape sequence `ESC intermediates byte`.
+    fn esc_dispatch(&mut self, intermediates: &[u8], byte: u8);
+
+    /// Dispatches an operating system command, split at `;`.
+    fn osc_dispatch(&mut self, params: &[&[u8]]);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Ground,
+    Escape,
+    EscapeIntermediate,
+    CsiEntry,
+    CsiParam,
+    CsiIntermediate,
+    CsiIgnore,
+    OscString,
+    /// DCS, SOS, PM and APC strings, which are consumed and ignored.
+    IgnoredString,
+}
+
+/// A VT500-series parser, after the state machine described at
+/// <https://vt100.net/emu/dec_ansi_parser>, decoding UTF-8 in the ground state.
+///
+/// 8-bit C1 controls are not recognized, as they collide with UTF-8.
+#[derive(Debug)]
+pub(crate) struct Parser {
+    state: State,
+    params: Vec<u8>,
+    intermediates: Vec<u8>,
+    osc: Vec<u8>,
+    /// Set when a sequence exceeded the collection limits.
+    overflow: bool,
+    utf8: Utf8Decoder,
+}
+
+impl Default for Parser {
+    fn default() -> Self {
+        Self {
+            state: State::Ground,
+            params: Vec::new(),
+            intermediates: Vec::new(),
+            osc: Vec::new(),
+            overflow: false,
+            utf8: Utf8Decoder::default(),
+        }
+    }
+}
+
+impl Parser {
+    /// Feeds `bytes` through the state machine.
+    pub(crate) fn advance(&mut self, performer: &mut impl Perform, bytes: &[u8]) {
+        for &byte in bytes {
+            self.advance_byte(performer, byte);
+        }
+    }
+
+    fn advance_byte(&mut self, performer: &mut impl Perform, byte: u8) {
+        if self.state == State::Ground {
+            if self.utf8.is_pending() && byte & 0xc0 != 0x80 {
+                // truncated sequence; the byte starts something new
+                self.utf8.reset();
+                performer.print(char::REPLACEMENT_CHARACTER);
+            }
+            if byte >= 0x80 {
+                if let Some(ch) = self.utf8.push(byte) {
+                    performer.print(ch);
+                }
+                return;
+            }
+        }
+
+        // transitions from anywhere
+        match byte {
+            0x18 | 0x1a => {
+                performer.execute(byte);
+                self.state = State::Ground;
+                return;
+            }
+            0x1b => {
+                if self.state == State::OscString {
+                    self.dispatch_osc(performer);
+                }
+                self.clear();
+                self.state = State::Escape;
+                return;
+            }
+            _ => {}
+        }
+
+        match self.state {
+            State::Ground => match byte {
+                0x00..=0x1f => performer.execute(byte),
+                0x7f => {}
+                _ => performer.print(byte as char),
+            },
+            State::Escape => match byte {
+                0x00..=0x1f => performer.execute(byte),
+                0x20..=0x2f => {
+                    self.collect(byte);
+                    self.state = State::EscapeIntermediate;
+                }
+                b'[' => self.state = State::CsiEntry,
+                b']' => self.state = State::OscString,
+                b'P' | b'X' | b'^' | b'_' => self.state = State::IgnoredString,
+                0x7f => {}
+                _ => {
+                    performer.esc_dispatch(&self.intermediates, byte);
+                    self.state = State::Ground;
+                }
+            },
+            State::EscapeIntermediate => match byte {
+                0x00..=0x1f => performer.execute(byte),
+                0x20..=0x2f => self.collect(byte),
+                0x7f => {}
+                _ => {
+                    if !self.overflow {
+                        performer.esc_dispatch(&self.intermediates, byte);
+                    }
+                    self.state = State::Ground;
+                }
+            },
+            State::CsiEntry | State::CsiParam | State::CsiIntermediate => match byte {
+                0x00..=0x1f => performer.execute(byte),
+                b'0'..=b'9' | b';' | b':' if self.state != State::CsiIntermediate => {
+                    self.collect_param(byte);
+                    self.state = State::CsiParam;
+                }
+                b'<'..=b'?' if self.state == State::CsiEntry => {
+                    // private marker
+                    self.collect(byte);
+                    self.state = State::CsiParam;
+                }
+                0x20..=0x2f => {
+                    self.collect(byte);
+                    self.state = State::CsiIntermediate;
+                }
+                0x30..=0x3f => self.state = State::CsiIgnore,
+                0x40..=0x7e => {
+                    self.dispatch_csi(performer, byte as char);
+                    self.state = State::Ground;
+                }
+                _ => {}
+            },
+            State::CsiIgnore => match byte {
+                0x00..=0x1f => performer.execute(byte),
+                0x40..=0x7e => self.state = State::Ground,
+                _ => {}
+            },
+            State::OscString => match byte {
+                0x07 => {
+                    self.dispatch_osc(performer);
+                    self.state = State::Ground;
+                }
+                0x00..=0x1f => {}
+                _ if self.osc.len() < MAX_OSC_LEN => self.osc.push(byte),
+                _ => self.overflow = true,
+            },
+            State::IgnoredString => {
+                if byte == 0x07 {
+                    self.state = State::Ground;
+                }
+            }
+        }
+    }
+
+    fn clear(&mut self) {
+        self.params.clear();
+        self.intermediates.clear();
+        self.osc.clear();
+        self.overflow = false;
+    }
+
+    fn collect(&mut self, byte: u8) {
+        if self.intermediates.len() < MAX_SEQUENCE_LEN {
+            self.intermediates.push(byte);
+        } else {
+            self.overflow = true;
+        }
+    }
+
+    fn collect_param(&mut self, byte: u8) {
+        if self.params.len() < MAX_SEQUENCE_LEN {
+            self.params.push(byte);
+        } else {
+            self.overflow = true;
+        }
+    }
+
+    fn dispatch_csi(&mut self, performer: &mut impl Perform, action: char) {
+        if self.overflow {
+            return;
+        }
+
+        let params = std::str::from_utf8(&self.params).unwrap_or_default();
+        let params: Vec<Vec<u16>> = if params.is_empty() {
+            Vec::new()
+        } else {
+            params
+                .split(';')
+                .map(|param| {
+                    param
+                        .split(':')
+                        .map(|sub| {
+                            sub.parse::<u32>()
+                                .map_or(0, |v| v.min(u16::MAX as u32) as u16)
+                        })
+                        .collect()
+                })
+                .collect()
+        };
+
+        performer.csi_dispatch(&params, &self.intermediates, action);
+    }
+
+    fn dispatch_osc(&mut self, performer: &mut impl Perform) {
+        if self.overflow {
+            return;
+        }
+
+        let params: Vec<&[u8]> = self.osc.split(|&b| b == b';').collect();
+        performer.osc_dispatch(&params);
+    }
+}
+
+/// An incremental UTF-8 decoder; malformed input decodes to U+FFFD.
+#[derive(Debug, Default)]
+struct Utf8Decoder {
+    bytes: [u8; 4],
+    len: usize,
+    needed: usize,
+}
+
+impl Utf8Decoder {
+    fn is_pending(&self) -> bool {
+        self.len > 0
+    }
+
+    fn reset(&mut self) {
+        self.len = 0;
+    }
+
+    /// Pushes a non-ASCII byte, returning the character it completes.
+    fn push(&mut self, byte: u8) -> Option<char> {
+        if self.len > 0 {
+            self.bytes[self.len] = byte;
+            self.len += 1;
+            if self.len < self.needed {
+                return None;
+            }
+
+            let ch = std::str::from_utf8(&self.bytes[..self.len])
+                .ok()
+                .and_then(|s| s.chars().next())
+                .unwrap_or(char::REPLACEMENT_CHARACTER);
+            self.len = 0;
+            return Some(ch);
+        }
+
+        self.needed = match byte {
+            0xc2..=0xdf => 2,
+            0xe0..=0xef => 3,
+            0xf0..=0xf4 => 4,
+            _ => return Some(char::REPLACEMENT_CHARACTER),
+        };
+        self.bytes[0] = byte;
+        self.len = 1;
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct Recorder {
+        actions: Vec<String>,
+    }
+
+    impl Perform for Recorder {
+        fn print(&mut self, ch: char) {
+            self.actions.push(format!("print {ch}"));
+        }
+
+        fn execute(&mut self, byte: u8) {
+            self.actions.push(format!("execute {byte:#04x}"));
+        }
+
+        fn csi_dispatch(&mut self, params: &[Vec<u16>], intermediates: &[u8], action: char) {
+            let intermediates = String::from_utf8_lossy(intermediates);
+            self.actions
+                .push(format!("csi {intermediates}{params:?} {action}"));
+        }
+
+        fn esc_dispatch(&mut self, intermediates: &[u8], byte: u8) {
+            let intermediates = String::from_utf8_lossy(intermediates);
+            self.actions
+                .push(format!("esc {intermediates}{}", byte as char));
+        }
+
+        fn osc_dispatch(&mut self, params: &[&[u8]]) {
+            let params: Vec<_> = params.iter().map(|p| String::from_utf8_lossy(p)).collect();
+            self.actions.push(format!("osc {}", params.join("|")));
+        }
+    }
+
+    fn parse(input: &[u8]) -> Vec<String> {
+        let mut recorder = Recorder::default();
+        Parser::default().advance(&mut recorder, input);
+        recorder.actions
+    }
+
+    #[test]
+    fn prints_utf8_and_executes_controls() {
+        assert_eq!(
+            parse("aé中\r\n".as_bytes()),
+            [
+                "print a",
+                "print é",
+                "print 中",
+                "execute 0x0d",
+                "execute 0x0a"
+            ]
+        );
+    }
+
+    #[test]
+    fn utf8_split_across_calls() {
+        let mut recorder = Recorder::default();
+        let mut parser = Parser::default();
+        let bytes = "中".as_bytes();
+        parser.advance(&mut recorder, &bytes[..1]);
+        parser.advance(&mut recorder, &bytes[1..]);
+        assert_eq!(recorder.actions, ["print 中"]);
+    }
+
+    #[test]
+    fn malformed_utf8_is_replaced() {
+        assert_eq!(
+            parse(b"\xffa\xe4b"),
+            ["print \u{fffd}", "print a", "print \u{fffd}", "print b"]
+        );
+        assert_eq!(
+            parse(b"\xe4\xe4\xb8\xad|"),
+            ["print \u{fffd}", "print 中", "print |"]
+        );
+    }
+
+    #[test]
+    fn csi_params_and_private_markers() {
+        assert_eq!(
+            parse(b"\x1b[1;22H\x1b[?1049h\x1b[m\x1b[38:2::1:2:3m"),
+            [
+                "csi [[1], [22]] H",
+                "csi ?[[1049]] h",
+                "csi [] m",
+                "csi [[38, 2, 0, 1, 2, 3]] m"
+            ]
+        );
+    }
+
+    #[test]
+    fn controls_execute_inside_sequences() {
+        assert_eq!(parse(b"\x1b[2\x08C"), ["execute 0x08", "csi [[2]] C"]);
+    }
+
+    #[test]
+    fn esc_sequences() {
+        assert_eq!(parse(b"\x1b7\x1b(0\x1b#8"), ["esc 7", "esc (0", "esc #8"]);
+    }
+
+    #[test]
+    fn osc_with_bel_and_st() {
+        assert_eq!(
+            parse(b"\x1b]0;title\x07\x1b]2;other\x1b\\"),
+            ["osc 0|title", "osc 2|other", "esc \\"]
+        );
+    }
+
+    #[test]
+    fn dcs_strings_are_ignored() {
+        assert_eq!(parse(b"\x1bPq#0;1;2\x1b\\x"), ["esc \\", "print x"]);
+    }
+
+    #[test]
+    fn cancel_aborts_sequence() {
+        assert_eq!(parse(b"\x1b[12\x18x"), ["execute 0x18", "print x"]);
+    }
+}
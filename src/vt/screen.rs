@@ -0,0 +1,1162 @@
+use std::collections::VecDeque;
+use std::fmt::Write as _;
+
+use ratatui::{
+    buffer::{Buffer, Cell},
+    layout::{Position, Rect},
+    style::{Color, Modifier},
+};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+use super::keys;
+use super::parser::{Parser, Perform};
+use crate::input::{Key, Modifiers};
+
+/// Columns between default tab stops.
+const TAB_WIDTH: u16 = 8;
+
+/// A terminal screen driven by a VT100/xterm byte stream.
+///
+/// Bytes written by a program are parsed with [`process`](Self::process) into
+/// a ratatui [`Buffer`]. Supported are cursor movement, SGR attributes with
+/// 16, 256 and truecolor palettes, erasing, insertion and deletion, scroll
+/// regions, tab stops, the DEC line-drawing charset and the alternate screen.
+///
+/// Changed cells are tracked, see [`take_damage`](Self::take_damage), and can
+/// be uploaded with [`BeamtermBackend::draw_vt`](crate::BeamtermBackend::draw_vt).
+///
+/// Lines scrolled off the top of the primary screen are kept in a scrollback
+/// history once a limit is set with [`set_scrollback_limit`](Self::set_scrollback_limit).
+pub struct VtScreen {
+    parser: Parser,
+    term: Term,
+}
+
+impl VtScreen {
+    /// Creates a blank screen of `cols` columns and `rows` rows.
+    pub fn new(cols: u16, rows: u16) -> Self {
+        Self {
+            parser: Parser::default(),
+            term: Term::new(cols.max(1), rows.max(1)),
+        }
+    }
+
+    /// Parses `bytes` and applies them to the screen.
+    ///
+    /// Escape sequences may be split across calls.
+    pub fn process(&mut self, bytes: &[u8]) {
+        self.parser.advance(&mut self.term, bytes);
+    }
+
+    /// Returns the visible screen content.
+    pub fn buffer(&self) -> &Buffer {
+        &self.term.grid
+    }
+
+    /// Returns the size of the screen in columns and rows.
+    pub fn size(&self) -> (u16, u16) {
+        (self.term.cols(), self.term.rows())
+    }
+
+    /// Returns the cursor position, or `None` while the cursor is hidden.
+    pub fn cursor(&self) -> Option<Position> {
+        let cursor = &self.term.cursor;
+        self.term
+            .modes
+            .cursor_visible
+            .then_some(Position::new(cursor.x, cursor.y))
+    }
+
+    /// Resizes the screen, keeping the cursor row visible.
+    ///
+    /// Content is cropped or padded; lines are not reflowed.
+    pub fn resize(&mut self, cols: u16, rows: u16) {
+        self.term.resize(cols.max(1), rows.max(1));
+    }
+
+    /// Sets the number of lines kept in the scrollback history, dropping
+    /// the oldest lines above the limit. Defaults to 0.
+    pub fn set_scrollback_limit(&mut self, lines: usize) {
+        self.term.scrollback_limit = lines;
+        self.term.trim_scrollback();
+    }
+
+    /// Returns the number of lines in the scrollback history.
+    pub fn scrollback_len(&self) -> usize {
+        self.term.scrollback.len()
+    }
+
+    /// Returns a line of the scrollback history, where 0 is the oldest.
+    ///
+    /// Lines keep the width the screen had when they scrolled off.
+    pub fn scrollback_line(&self, idx: usize) -> Option<&[Cell]> {
+        self.term.scrollback.get(idx).map(Vec::as_slice)
+    }
+
+    /// Returns the window title set by the program.
+    pub fn title(&self) -> &str {
+        &self.term.title
+    }
+
+    /// Returns true while the alternate screen is active.
+    pub fn is_alternate_screen(&self) -> bool {
+        self.term.primary.is_some()
+    }
+
+    /// Returns true if cursor keys should send application sequences (DECCKM).
+    pub fn application_cursor_keys(&self) -> bool {
+        self.term.modes.application_cursor
+    }
+
+    /// Returns true if pasted text should be wrapped in bracketed paste markers.
+    pub fn bracketed_paste(&self) -> bool {
+        self.term.modes.bracketed_paste
+    }
+
+    /// Encodes a key press as the bytes to send to the program, following
+    /// the cursor key mode it selected.
+    pub fn encode_key(&self, key: Key, modifiers: Modifiers) -> Vec<u8> {
+        keys::encode_key(key, modifiers, self.application_cursor_keys())
+    }
+
+    /// Encodes pasted text as the bytes to send to the program, bracketed if
+    /// it enabled bracketed paste.
+    pub fn encode_paste(&self, text: &str) -> Vec<u8> {
+        keys::encode_paste(text, self.bracketed_paste())
+    }
+
+    /// Returns the positions of all cells changed since the last call.
+    pub fn take_damage(&mut self) -> Vec<Position> {
+        let cols = self.term.cols() as usize;
+        let damaged = self
+            .term
+            .damage
+            .iter()
+            .enumerate()
+            .filter(|(_, damaged)| **damaged)
+            .map(|(idx, _)| Position::new((idx % cols) as u16, (idx / cols) as u16))
+            .collect();
+
+        self.term.damage.fill(false);
+        damaged
+    }
+
+    /// Returns replies to device status and attribute queries, to be written
+    /// back to the program.
+    pub fn take_responses(&mut self) -> Vec<u8> {
+        std::mem::take(&mut self.term.responses)
+    }
+}
+
+/// Character sets selectable with `ESC (` and `ESC )`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Charset {
+    Ascii,
+    /// DEC special graphics, mapping ASCII to line-drawing characters.
+    DecGraphics,
+}
+
+impl Charset {
+    fn map(self, ch: char) -> char {
+        match self {
+            Charset::Ascii => ch,
+            Charset::DecGraphics => dec_graphics(ch),
+        }
+    }
+}
+
+/// The cell attributes applied to printed text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Pen {
+    fg: Color,
+    bg: Color,
+    modifier: Modifier,
+}
+
+impl Default for Pen {
+    fn default() -> Self {
+        Self {
+            fg: Color::Reset,
+            bg: Color::Reset,
+            modifier: Modifier::empty(),
+        }
+    }
+}
+
+/// Cursor state, as saved and restored by DECSC and DECRC.
+#[derive(Debug, Clone, Copy)]
+struct Cursor {
+    x: u16,
+    y: u16,
+    pen: Pen,
+    /// Set after printing in the last column; the next character wraps.
+    pending_wrap: bool,
+    origin_mode: bool,
+    charsets: [Charset; 2],
+    /// Index of the charset invoked into GL by SI and SO.
+    active_charset: usize,
+}
+
+impl Default for Cursor {
+    fn default() -> Self {
+        Self {
+            x: 0,
+            y: 0,
+            pen: Pen::default(),
+            pending_wrap: false,
+            origin_mode: false,
+            charsets: [Charset::Ascii; 2],
+            active_charset: 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Modes {
+    autowrap: bool,
+    insert: bool,
+    linefeed_newline: bool,
+    cursor_visible: bool,
+    application_cursor: bool,
+    bracketed_paste: bool,
+}
+
+impl Default for Modes {
+    fn default() -> Self {
+        Self {
+            autowrap: true,
+            insert: false,
+            linefeed_newline: false,
+            cursor_visible: true,
+            application_cursor: false,
+            bracketed_paste: false,
+        }
+    }
+}
+
+/// Terminal state, receiving the actions of the parser.
+struct Term {
+    grid: Buffer,
+    /// The primary screen while the alternate screen is active.
+    primary: Option<Buffer>,
+    cursor: Cursor,
+    saved_cursor: Option<Cursor>,
+    /// The cursor saved on entering the alternate screen with mode 1049.
+    saved_primary_cursor: Option<Cursor>,
+    /// First row of the scroll region.
+    scroll_top: u16,
+    /// Last row of the scroll region, inclusive.
+    scroll_bottom: u16,
+    modes: Modes,
+    tab_stops: Vec<bool>,
+    damage: Vec<bool>,
+    responses: Vec<u8>,
+    title: String,
+    last_printed: Option<char>,
+    /// Lines scrolled off the primary screen, oldest first.
+    scrollback: VecDeque<Vec<Cell>>,
+    scrollback_limit: usize,
+}
+
+impl Term {
+    fn new(cols: u16, rows: u16) -> Self {
+        let cells = cols as usize * rows as usize;
+        Self {
+            grid: Buffer::empty(Rect::new(0, 0, cols, rows)),
+            primary: None,
+            cursor: Cursor::default(),
+            saved_cursor: None,
+            saved_primary_cursor: None,
+            scroll_top: 0,
+            scroll_bottom: rows - 1,
+            modes: Modes::default(),
+            tab_stops: default_tab_stops(cols),
+            damage: vec![true; cells],
+            responses: Vec::new(),
+            title: String::new(),
+            last_printed: None,
+            scrollback: VecDeque::new(),
+            scrollback_limit: 0,
+        }
+    }
+
+    fn cols(&self) -> u16 {
+        self.grid.area.width
+    }
+
+    fn rows(&self) -> u16 {
+        self.grid.area.height
+    }
+
+    fn index(&self, x: u16, y: u16) -> usize {
+        y as usize * self.cols() as usize + x as usize
+    }
+
+    /// Returns an erased cell; erasing fills with the current background.
+    fn blank(&self) -> Cell {
+        let mut cell = Cell::default();
+        cell.bg = self.cursor.pen.bg;
+        cell
+    }
+
+    fn set_cell(&mut self, x: u16, y: u16, cell: Cell) {
+        let idx = self.index(x, y);
+        self.grid.content[idx] = cell;
+        self.damage[idx] = true;
+    }
+
+    /// Erases the cells from `start` to `end` (exclusive) of row `y`.
+    fn erase_cells(&mut self, y: u16, start: u16, end: u16) {
+        let end = end.min(self.cols());
+        for x in start..end {
+            self.set_cell(x, y, self.blank());
+        }
+    }
+
+    fn erase_rows(&mut self, start: u16, end: u16) {
+        for y in start..end.min(self.rows()) {
+            self.erase_cells(y, 0, self.cols());
+        }
+    }
+
+    fn damage_rows(&mut self, start: u16, end: u16) {
+        let range = self.index(0, start)..self.index(0, end);
+        self.damage[range].fill(true);
+    }
+
+    fn resize(&mut self, cols: u16, rows: u16) {
+        if (cols, rows) == (self.cols(), self.rows()) {
+            return;
+        }
+
+        // keep the cursor row on screen by dropping rows from the top
+        let shift = (self.cursor.y + 1).saturating_sub(rows);
+        if self.primary.is_none() {
+            self.push_scrollback(0, shift);
+        }
+        self.grid = resized(&self.grid, cols, rows, shift);
+        if let Some(primary) = &self.primary {
+            self.primary = Some(resized(primary, cols, rows, 0));
+        }
+
+        for cursor in [Some(&mut self.cursor), self.saved_cursor.as_mut()]
+            .into_iter()
+            .flatten()
+        {
+            cursor.x = cursor.x.min(cols - 1);
+            cursor.y = cursor.y.saturating_sub(shift).min(rows - 1);
+            cursor.pending_wrap = false;
+        }
+        // the primary screen is resized in place, without dropping rows
+        if let Some(cursor) = self.saved_primary_cursor.as_mut() {
+            cursor.x = cursor.x.min(cols - 1);
+            cursor.y = cursor.y.min(rows - 1);
+            cursor.pending_wrap = false;
+        }
+
+        self.scroll_top = 0;
+        self.scroll_bottom = rows - 1;
+        self.tab_stops = default_tab_stops(cols);
+        self.damage = vec![true; cols as usize * rows as usize];
+    }
+
+    fn print_char(&mut self, ch: char) {
+        let ch = self.cursor.charsets[self.cursor.active_charset].map(ch);
+        let width = ch.width().unwrap_or(0) as u16;
+        if width == 0 {
+            self.combine(ch);
+            return;
+        }
+
+        if self.cursor.pending_wrap && self.modes.autowrap {
+            self.cursor.x = 0;
+            self.index_down();
+        }
+        self.cursor.pending_wrap = false;
+
+        let cols = self.cols();
+        if self.cursor.x + width > cols {
+            if !self.modes.autowrap || width > cols {
+                return;
+            }
+            // a wide character that does not fit wraps as a whole
+            self.erase_cells(self.cursor.y, self.cursor.x, cols);
+            self.cursor.x = 0;
+            self.index_down();
+        }
+
+        let (x, y) = (self.cursor.x, self.cursor.y);
+        if self.modes.insert {
+            self.insert_cells(width);
+        }
+        self.clear_wide_fragments(x, y, width);
+
+        let mut cell = Cell::default();
+        cell.set_char(ch);
+        self.apply_pen(&mut cell);
+        self.set_cell(x, y, cell);
+        if width == 2 {
+            let mut padding = Cell::default();
+            self.apply_pen(&mut padding);
+            self.set_cell(x + 1, y, padding);
+        }
+
+        if x + width >= cols {
+            self.cursor.x = cols - 1;
+            self.cursor.pending_wrap = self.modes.autowrap;
+        } else {
+            self.cursor.x = x + width;
+        }
+        self.last_printed = Some(ch);
+    }
+
+    /// Appends a zero-width character to the previously printed cell.
+    fn combine(&mut self, ch: char) {
+        let (x, y) = (self.cursor.x, self.cursor.y);
+        let x = match (self.cursor.pending_wrap, x.checked_sub(1)) {
+            (true, _) => x,
+            (false, Some(x)) => x,
+            (false, None) => return,
+        };
+        // step back over the padding of a wide character
+        let x = if x > 0 && self.grid[(x - 1, y)].symbol().width() == 2 {
+            x - 1
+        } else {
+            x
+        };
+
+        let idx = self.index(x, y);
+        let mut symbol = self.grid.content[idx].symbol().to_string();
+        symbol.push(ch);
+        self.grid.content[idx].set_symbol(&symbol);
+        self.damage[idx] = true;
+    }
+
+    /// Erases the halves of wide characters partially covered by writing
+    /// `width` cells at `x`.
+    fn clear_wide_fragments(&mut self, x: u16, y: u16, width: u16) {
+        if x > 0 && self.grid[(x - 1, y)].symbol().width() == 2 {
+            self.set_cell(x - 1, y, self.blank());
+        }
+
+        let last = x + width - 1;
+        if last + 1 < self.cols() && self.grid[(last, y)].symbol().width() == 2 {
+            self.set_cell(last + 1, y, self.blank());
+        }
+    }
+
+    fn apply_pen(&self, cell: &mut Cell) {
+        let pen = self.cursor.pen;
+        cell.fg = pen.fg;
+        cell.bg = pen.bg;
+        cell.modifier = pen.modifier;
+    }
+
+    /// Moves the cursor down, scrolling at the bottom of the scroll region.
+    fn index_down(&mut self) {
+        if self.cursor.y == self.scroll_bottom {
+            if self.scroll_top == 0 && self.primary.is_none() {
+                self.push_scrollback(0, 1);
+            }
+            self.scroll_up(self.scroll_top, self.scroll_bottom, 1);
+        } else if self.cursor.y + 1 < self.rows() {
+            self.cursor.y += 1;
+        }
+    }
+
+    /// Moves the cursor up, scrolling at the top of the scroll region.
+    fn reverse_index(&mut self) {
+        if self.cursor.y == self.scroll_top {
+            self.scroll_down(self.scroll_top, self.scroll_bottom, 1);
+        } else if self.cursor.y > 0 {
+            self.cursor.y -= 1;
+        }
+    }
+
+    /// Appends rows `start..start + n` to the scrollback history.
+    fn push_scrollback(&mut self, start: u16, n: u16) {
+        if self.scrollback_limit == 0 {
+            return;
+        }
+
+        for y in start..start + n {
+            let row = self.index(0, y)..self.index(0, y + 1);
+            self.scrollback.push_back(self.grid.content[row].to_vec());
+        }
+        self.trim_scrollback();
+    }
+
+    fn trim_scrollback(&mut self) {
+        let excess = self.scrollback.len().saturating_sub(self.scrollback_limit);
+        self.scrollback.drain(..excess);
+    }
+
+    /// Scrolls rows `top..=bottom` up by `n`, erasing the rows uncovered at the bottom.
+    fn scroll_up(&mut self, top: u16, bottom: u16, n: u16) {
+        let n = n.min(bottom + 1 - top);
+        let cols = self.cols() as usize;
+        let region = self.index(0, top)..self.index(0, bottom + 1);
+        self.grid.content[region].rotate_left(n as usize * cols);
+        self.erase_rows(bottom + 1 - n, bottom + 1);
+        self.damage_rows(top, bottom + 1);
+    }
+
+    /// Scrolls rows `top..=bottom` down by `n`, erasing the rows uncovered at the top.
+    fn scroll_down(&mut self, top: u16, bottom: u16, n: u16) {
+        let n = n.min(bottom + 1 - top);
+        let cols = self.cols() as usize;
+        let region = self.index(0, top)..self.index(0, bottom + 1);
+        self.grid.content[region].rotate_right(n as usize * cols);
+        self.erase_rows(top, top + n);
+        self.damage_rows(top, bottom + 1);
+    }
+
+    /// Inserts `n` blank cells at the cursor, shifting the rest of the row right.
+    fn insert_cells(&mut self, n: u16) {
+        let (x, y) = (self.cursor.x, self.cursor.y);
+        let n = n.min(self.cols() - x);
+        let row = self.index(x, y)..self.index(0, y + 1);
+        self.grid.content[row].rotate_right(n as usize);
+        self.erase_cells(y, x, x + n);
+        self.damage_rows(y, y + 1);
+    }
+
+    /// Deletes `n` cells at the cursor, shifting the rest of the row left.
+    fn delete_cells(&mut self, n: u16) {
+        let (x, y) = (self.cursor.x, self.cursor.y);
+        let cols = self.cols();
+        let n = n.min(cols - x);
+        let row = self.index(x, y)..self.index(0, y + 1);
+        self.grid.content[row].rotate_left(n as usize);
+        self.erase_cells(y, cols - n, cols);
+        self.damage_rows(y, y + 1);
+    }
+
+    fn in_scroll_region(&self) -> bool {
+        (self.scroll_top..=self.scroll_bottom).contains(&self.cursor.y)
+    }
+
+    /// Moves the cursor to `x`, `y`, relative to the scroll region in origin mode.
+    fn goto(&mut self, x: u16, y: u16) {
+        let (top, bottom) = if self.cursor.origin_mode {
+            (self.scroll_top, self.scroll_bottom)
+        } else {
+            (0, self.rows() - 1)
+        };
+
+        self.cursor.x = x.min(self.cols() - 1);
+        self.cursor.y = top.saturating_add(y).min(bottom);
+        self.cursor.pending_wrap = false;
+    }
+
+    fn goto_row(&mut self, y: u16) {
+        self.goto(self.cursor.x, y);
+    }
+
+    /// Returns the cursor row relative to the origin.
+    fn relative_row(&self) -> u16 {
+        if self.cursor.origin_mode {
+            self.cursor.y.saturating_sub(self.scroll_top)
+        } else {
+            self.cursor.y
+        }
+    }
+
+    fn tab_forward(&mut self, n: u16) {
+        for _ in 0..n {
+            let next = (self.cursor.x + 1..self.cols()).find(|&x| self.tab_stops[x as usize]);
+            self.cursor.x = next.unwrap_or(self.cols() - 1);
+        }
+        self.cursor.pending_wrap = false;
+    }
+
+    fn tab_backward(&mut self, n: u16) {
+        for _ in 0..n {
+            let prev = (0..self.cursor.x)
+                .rev()
+                .find(|&x| self.tab_stops[x as usize]);
+            self.cursor.x = prev.unwrap_or(0);
+        }
+        self.cursor.pending_wrap = false;
+    }
+
+    fn save_cursor(&mut self) {
+        self.saved_cursor = Some(self.cursor);
+    }
+
+    fn restore_cursor(&mut self) {
+        let saved = self.saved_cursor.unwrap_or_default();
+        self.cursor = saved;
+        self.cursor.x = saved.x.min(self.cols() - 1);
+        self.cursor.y = saved.y.min(self.rows() - 1);
+    }
+
+    fn enter_alternate_screen(&mut self, save_cursor: bool) {
+        if self.primary.is_some() {
+            return;
+        }
+
+        if save_cursor {
+            self.saved_primary_cursor = Some(self.cursor);
+        }
+        let area = self.grid.area;
+        self.primary = Some(std::mem::replace(&mut self.grid, Buffer::empty(area)));
+        self.damage.fill(true);
+    }
+
+    fn leave_alternate_screen(&mut self, restore_cursor: bool) {
+        let Some(primary) = self.primary.take() else {
+            return;
+        };
+
+        self.grid = primary;
+        if let Some(cursor) = self.saved_primary_cursor.take().filter(|_| restore_cursor) {
+            self.cursor = cursor;
+        }
+        self.damage.fill(true);
+    }
+
+    fn reset(&mut self) {
+        let responses = std::mem::take(&mut self.responses);
+        let scrollback_limit = self.scrollback_limit;
+        *self = Self::new(self.cols(), self.rows());
+        self.responses = responses;
+        self.scrollback_limit = scrollback_limit;
+    }
+
+    fn set_mode(&mut self, mode: u16, private: bool, enable: bool) {
+        match (private, mode) {
+            (false, 4) => self.modes.insert = enable,
+            (false, 20) => self.modes.linefeed_newline = enable,
+            (true, 1) => self.modes.application_cursor = enable,
+            (true, 6) => {
+                self.cursor.origin_mode = enable;
+                self.goto(0, 0);
+            }
+            (true, 7) => self.modes.autowrap = enable,
+            (true, 25) => self.modes.cursor_visible = enable,
+            (true, 47) => self.switch_screen(enable, false),
+            (true, 1047) => {
+                if !enable && self.primary.is_some() {
+                    self.erase_rows(0, self.rows());
+                }
+                self.switch_screen(enable, false);
+            }
+            (true, 1048) if enable => self.save_cursor(),
+            (true, 1048) => self.restore_cursor(),
+            (true, 1049) => self.switch_screen(enable, true),
+            (true, 2004) => self.modes.bracketed_paste = enable,
+            _ => {}
+        }
+    }
+
+    fn switch_screen(&mut self, alternate: bool, with_cursor: bool) {
+        if alternate {
+            self.enter_alternate_screen(with_cursor);
+            if with_cursor {
+                self.erase_rows(0, self.rows());
+            }
+        } else {
+            self.leave_alternate_screen(with_cursor);
+        }
+    }
+
+    fn sgr(&mut self, params: &[Vec<u16>]) {
+        if params.is_empty() {
+            self.cursor.pen = Pen::default();
+            return;
+        }
+
+        let pen = &mut self.cursor.pen;
+        let mut params = params.iter();
+        while let Some(param) = params.next() {
+            let code = param.first().copied().unwrap_or(0);
+            match code {
+                0 => *pen = Pen::default(),
+                1 => pen.modifier.insert(Modifier::BOLD),
+                2 => pen.modifier.insert(Modifier::DIM),
+                3 => pen.modifier.insert(Modifier::ITALIC),
+                4 | 21 => pen.modifier.insert(Modifier::UNDERLINED),
+                5 => pen.modifier.insert(Modifier::SLOW_BLINK),
+                6 => pen.modifier.insert(Modifier::RAPID_BLINK),
+                7 => pen.modifier.insert(Modifier::REVERSED),
+                8 => pen.modifier.insert(Modifier::HIDDEN),
+                9 => pen.modifier.insert(Modifier::CROSSED_OUT),
+                22 => pen.modifier.remove(Modifier::BOLD | Modifier::DIM),
+                23 => pen.modifier.remove(Modifier::ITALIC),
+                24 => pen.modifier.remove(Modifier::UNDERLINED),
+                25 => pen
+                    .modifier
+                    .remove(Modifier::SLOW_BLINK | Modifier::RAPID_BLINK),
+                27 => pen.modifier.remove(Modifier::REVERSED),
+                28 => pen.modifier.remove(Modifier::HIDDEN),
+                29 => pen.modifier.remove(Modifier::CROSSED_OUT),
+                30..=37 => pen.fg = Color::Indexed((code - 30) as u8),
+                38 => pen.fg = extended_color(param, &mut params),
+                39 => pen.fg = Color::Reset,
+                40..=47 => pen.bg = Color::Indexed((code - 40) as u8),
+                48 => pen.bg = extended_color(param, &mut params),
+                49 => pen.bg = Color::Reset,
+                90..=97 => pen.fg = Color::Indexed((code - 90 + 8) as u8),
+                100..=107 => pen.bg = Color::Indexed((code - 100 + 8) as u8),
+                _ => {}
+            }
+        }
+    }
+
+    fn device_status_report(&mut self, report: u16) {
+        match report {
+            5 => self.responses.extend_from_slice(b"\x1b[0n"),
+            6 => {
+                let row = self.relative_row() + 1;
+                let col = self.cursor.x + 1;
+                let mut reply = String::new();
+                let _ = write!(reply, "\x1b[{row};{col}R");
+                self.responses.extend_from_slice(reply.as_bytes());
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Perform for Term {
+    fn print(&mut self, ch: char) {
+        self.print_char(ch);
+    }
+
+    fn execute(&mut self, byte: u8) {
+        match byte {
+            0x08 => {
+                self.cursor.x = self.cursor.x.saturating_sub(1);
+                self.cursor.pending_wrap = false;
+            }
+            0x09 => self.tab_forward(1),
+            0x0a..=0x0c => {
+                if self.modes.linefeed_newline {
+                    self.cursor.x = 0;
+                }
+                self.index_down();
+                self.cursor.pending_wrap = false;
+            }
+            0x0d => {
+                self.cursor.x = 0;
+                self.cursor.pending_wrap = false;
+            }
+            0x0e => self.cursor.active_charset = 1,
+            0x0f => self.cursor.active_charset = 0,
+            _ => {}
+        }
+    }
+
+    fn csi_dispatch(&mut self, params: &[Vec<u16>], intermediates: &[u8], action: char) {
+        let param = |idx: usize| {
+            params
+                .get(idx)
+                .and_then(|p| p.first().copied())
+                .unwrap_or(0)
+        };
+        // the count of movement and editing functions, where 0 means 1
+        let count = param(0).max(1);
+
+        match (intermediates, action) {
+            ([], '@') => self.insert_cells(count),
+            ([], 'A') => {
+                let top = if self.in_scroll_region() {
+                    self.scroll_top
+                } else {
+                    0
+                };
+                self.cursor.y = self.cursor.y.saturating_sub(count).max(top);
+                self.cursor.pending_wrap = false;
+            }
+            ([], 'B' | 'e') => {
+                let bottom = if self.in_scroll_region() {
+                    self.scroll_bottom
+                } else {
+                    self.rows() - 1
+                };
+                self.cursor.y = self.cursor.y.saturating_add(count).min(bottom);
+                self.cursor.pending_wrap = false;
+            }
+            ([], 'C' | 'a') => {
+                self.cursor.x = self.cursor.x.saturating_add(count).min(self.cols() - 1);
+                self.cursor.pending_wrap = false;
+            }
+            ([], 'D') => {
+                self.cursor.x = self.cursor.x.saturating_sub(count);
+                self.cursor.pending_wrap = false;
+            }
+            ([], 'E') => {
+                self.csi_dispatch(params, intermediates, 'B');
+                self.cursor.x = 0;
+            }
+            ([], 'F') => {
+                self.csi_dispatch(params, intermediates, 'A');
+                self.cursor.x = 0;
+            }
+            ([], 'G' | '`') => {
+                self.cursor.x = (count - 1).min(self.cols() - 1);
+                self.cursor.pending_wrap = false;
+            }
+            ([], 'H' | 'f') => self.goto(param(1).max(1) - 1, count - 1),
+            ([], 'I') => self.tab_forward(count),
+            ([], 'J') => {
+                let (x, y) = (self.cursor.x, self.cursor.y);
+                match param(0) {
+                    0 => {
+                        self.erase_cells(y, x, self.cols());
+                        self.erase_rows(y + 1, self.rows());
+                    }
+                    1 => {
+                        self.erase_rows(0, y);
+                        self.erase_cells(y, 0, x + 1);
+                    }
+                    2 => self.erase_rows(0, self.rows()),
+                    3 => self.scrollback.clear(),
+                    _ => {}
+                }
+            }
+            ([], 'K') => {
+                let (x, y) = (self.cursor.x, self.cursor.y);
+                match param(0) {
+                    0 => self.erase_cells(y, x, self.cols()),
+                    1 => self.erase_cells(y, 0, x + 1),
+                    2 => self.erase_cells(y, 0, self.cols()),
+                    _ => {}
+                }
+            }
+            ([], 'L') if self.in_scroll_region() => {
+                self.scroll_down(self.cursor.y, self.scroll_bottom, count);
+                self.cursor.x = 0;
+                self.cursor.pending_wrap = false;
+            }
+            ([], 'M') if self.in_scroll_region() => {
+                self.scroll_up(self.cursor.y, self.scroll_bottom, count);
+                self.cursor.x = 0;
+                self.cursor.pending_wrap = false;
+            }
+            ([], 'P') => self.delete_cells(count),
+            ([], 'S') => self.scroll_up(self.scroll_top, self.scroll_bottom, count),
+            ([], 'T') => self.scroll_down(self.scroll_top, self.scroll_bottom, count),
+            ([], 'X') => {
+                let (x, y) = (self.cursor.x, self.cursor.y);
+                self.erase_cells(y, x, x.saturating_add(count));
+            }
+            ([], 'Z') => self.tab_backward(count),
+            ([], 'b') => {
+                if let Some(ch) = self.last_printed {
+                    for _ in 0..count {
+                        self.print_char(ch);
+                    }
+                }
+            }
+            ([], 'c') if param(0) == 0 => self.responses.extend_from_slice(b"\x1b[?1;2c"),
+            ([b'>'], 'c') if param(0) == 0 => self.responses.extend_from_slice(b"\x1b[>0;10;1c"),
+            ([], 'd') => self.goto_row(count - 1),
+            ([], 'g') => match param(0) {
+                0 => self.tab_stops[self.cursor.x as usize] = false,
+                3 => self.tab_stops.fill(false),
+                _ => {}
+            },
+            ([], 'h' | 'l') | ([b'?'], 'h' | 'l') => {
+                let private = intermediates == b"?";
+                for mode in params.iter().filter_map(|p| p.first()) {
+                    self.set_mode(*mode, private, action == 'h');
+                }
+            }
+            ([], 'm') => self.sgr(params),
+            ([], 'n') => self.device_status_report(param(0)),
+            ([], 'r') => {
+                let top = count - 1;
+                let bottom = match param(1) {
+                    0 => self.rows(),
+                    bottom => bottom.min(self.rows()),
+                } - 1;
+                if top < bottom {
+                    self.scroll_top = top;
+                    self.scroll_bottom = bottom;
+                    self.goto(0, 0);
+                }
+            }
+            ([], 's') => self.save_cursor(),
+            ([], 'u') => self.restore_cursor(),
+            _ => {}
+        }
+    }
+
+    fn esc_dispatch(&mut self, intermediates: &[u8], byte: u8) {
+        match (intermediates, byte) {
+            ([], b'7') => self.save_cursor(),
+            ([], b'8') => self.restore_cursor(),
+            ([], b'D') => {
+                self.index_down();
+                self.cursor.pending_wrap = false;
+            }
+            ([], b'E') => {
+                self.cursor.x = 0;
+                self.index_down();
+                self.cursor.pending_wrap = false;
+            }
+            ([], b'H') => self.tab_stops[self.cursor.x as usize] = true,
+            ([], b'M') => {
+                self.reverse_index();
+                self.cursor.pending_wrap = false;
+            }
+            ([], b'c') => self.reset(),
+            ([b'#'], b'8') => {
+                // DECALN: fill the screen with 'E'
+                self.scroll_top = 0;
+                self.scroll_bottom = self.rows() - 1;
+                self.cursor = Cursor {
+                    pen: self.cursor.pen,
+                    charsets: self.cursor.charsets,
+                    active_charset: self.cursor.active_charset,
+                    ..Cursor::default()
+                };
+                for idx in 0..self.grid.content.len() {
+                    let mut cell = Cell::default();
+                    cell.set_char('E');
+                    self.grid.content[idx] = cell;
+                }
+                self.damage.fill(true);
+            }
+            ([slot @ (b'(' | b')')], charset) => {
+                let slot = if *slot == b'(' { 0 } else { 1 };
+                self.cursor.charsets[slot] = match charset {
+                    b'0' => Charset::DecGraphics,
+                    _ => Charset::Ascii,
+                };
+            }
+            _ => {}
+        }
+    }
+
+    fn osc_dispatch(&mut self, params: &[&[u8]]) {
+        // OSC 0 and 2 set the window title
+        if let [b"0" | b"2", title @ ..] = params {
+            self.title = String::from_utf8_lossy(&title.join(&b';')).into_owned();
+        }
+    }
+}
+
+fn default_tab_stops(cols: u16) -> Vec<bool> {
+    (0..cols).map(|x| x > 0 && x % TAB_WIDTH == 0).collect()
+}
+
+/// Copies `buffer` into a buffer of the new size, dropping the first `shift` rows.
+fn resized(buffer: &Buffer, cols: u16, rows: u16, shift: u16) -> Buffer {
+    let area = Rect::new(0, 0, cols, rows);
+    let mut resized = Buffer::empty(area);
+    for position in area.positions() {
+        let source = Position::new(position.x, position.y + shift);
+        if buffer.area.contains(source) {
+            resized[position] = buffer[source].clone();
+        }
+    }
+
+    resized
+}
+
+/// Reads the color of an extended `38`/`48` SGR parameter, given either as
+/// sub-parameters (`38:2::r:g:b`) or as the following parameters (`38;2;r;g;b`).
+fn extended_color<'a>(param: &[u16], rest: &mut impl Iterator<Item = &'a Vec<u16>>) -> Color {
+    if param.len() > 1 {
+        return match param[1..] {
+            [5, index, ..] => Color::Indexed(index as u8),
+            // with the optional color space id
+            [2, _, r, g, b, ..] => Color::Rgb(r as u8, g as u8, b as u8),
+            [2, r, g, b] => Color::Rgb(r as u8, g as u8, b as u8),
+            _ => Color::Reset,
+        };
+    }
+
+    let mut next = || rest.next().and_then(|p| p.first().copied()).unwrap_or(0);
+    match next() {
+        5 => Color::Indexed(next() as u8),
+        2 => Color::Rgb(next() as u8, next() as u8, next() as u8),
+        _ => Color::Reset,
+    }
+}
+
+/// Maps ASCII to the DEC special graphics character set.
+fn dec_graphics(ch: char) -> char {
+    match ch {
+        '`' => '◆',
+        'a' => '▒',
+        'b' => '␉',
+        'c' => '␌',
+        'd' => '␍',
+        'e' => '␊',
+        'f' => '°',
+        'g' => '±',
+        'h' => '␤',
+        'i' => '␋',
+        'j' => '┘',
+        'k' => '┐',
+        'l' => '┌',
+        'm' => '└',
+        'n' => '┼',
+        'o' => '⎺',
+        'p' => '⎻',
+        'q' => '─',
+        'r' => '⎼',
+        's' => '⎽',
+        't' => '├',
+        'u' => '┤',
+        'v' => '┴',
+        'w' => '┬',
+        'x' => '│',
+        'y' => '≤',
+        'z' => '≥',
+        '{' => 'π',
+        '|' => '≠',
+        '}' => '£',
+        '~' => '·',
+        _ => ch,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::to_plain_text;
+
+    fn screen(input: &[u8]) -> VtScreen {
+        let mut screen = VtScreen::new(80, 24);
+        screen.process(input);
+        screen
+    }
+
+    fn assert_fixture(name: &str, input: &[u8], expected: &str) {
+        let actual = to_plain_text(screen(input).buffer());
+        assert_eq!(actual, expected, "fixture {name}");
+    }
+
+    macro_rules! fixture {
+        ($name:literal) => {
+            assert_fixture(
+                $name,
+                include_bytes!(concat!("../../tests/fixtures/vt/", $name, ".vt")),
+                include_str!(concat!("../../tests/fixtures/vt/", $name, ".txt")),
+            )
+        };
+    }
+
+    #[test]
+    fn conformance_fixtures() {
+        fixture!("cursor-movement");
+        fixture!("scroll-region");
+        fixture!("erase");
+        fixture!("autowrap");
+        fixture!("alternate-screen");
+        fixture!("dec-graphics");
+    }
+
+    #[test]
+    fn sgr_colors() {
+        let screen = screen(b"\x1b[1;31ma\x1b[38;5;200;48;2;1;2;3mb\x1b[38:2::4:5:6mc\x1b[0md");
+        let buffer = screen.buffer();
+
+        assert_eq!(buffer[(0, 0)].fg, Color::Indexed(1));
+        assert!(buffer[(0, 0)].modifier.contains(Modifier::BOLD));
+        assert_eq!(buffer[(1, 0)].fg, Color::Indexed(200));
+        assert_eq!(buffer[(1, 0)].bg, Color::Rgb(1, 2, 3));
+        assert_eq!(buffer[(2, 0)].fg, Color::Rgb(4, 5, 6));
+        assert_eq!(buffer[(3, 0)].fg, Color::Reset);
+        assert_eq!(buffer[(3, 0)].modifier, Modifier::empty());
+    }
+
+    #[test]
+    fn erase_uses_background_color() {
+        let screen = screen(b"\x1b[44m\x1b[2J");
+        assert_eq!(screen.buffer()[(40, 12)].bg, Color::Indexed(4));
+    }
+
+    #[test]
+    fn combining_characters_join_previous_cell() {
+        let screen = screen("e\u{301}x".as_bytes());
+        assert_eq!(screen.buffer()[(0, 0)].symbol(), "e\u{301}");
+        assert_eq!(screen.buffer()[(1, 0)].symbol(), "x");
+    }
+
+    #[test]
+    fn overwriting_half_of_wide_character() {
+        let screen = screen("中\x1b[1;2Hx".as_bytes());
+        assert_eq!(screen.buffer()[(0, 0)].symbol(), " ");
+        assert_eq!(screen.buffer()[(1, 0)].symbol(), "x");
+    }
+
+    #[test]
+    fn device_reports() {
+        let mut screen = screen(b"\x1b[5;10H\x1b[6n\x1b[c");
+        assert_eq!(screen.take_responses(), b"\x1b[5;10R\x1b[?1;2c");
+        assert!(screen.take_responses().is_empty());
+    }
+
+    #[test]
+    fn modes_and_title() {
+        let mut screen = screen(b"\x1b]2;vim\x07\x1b[?1h\x1b[?2004h\x1b[?25l\x1b[?1049h");
+        assert_eq!(screen.title(), "vim");
+        assert!(screen.application_cursor_keys());
+        assert!(screen.bracketed_paste());
+        assert!(screen.is_alternate_screen());
+        assert_eq!(screen.cursor(), None);
+
+        screen.process(b"\x1bc");
+        assert!(!screen.is_alternate_screen());
+        assert_eq!(screen.cursor(), Some(Position::ORIGIN));
+    }
+
+    #[test]
+    fn damage_tracks_changed_cells() {
+        let mut screen = VtScreen::new(10, 3);
+        assert_eq!(screen.take_damage().len(), 30);
+
+        screen.process(b"ab\x1b[3;5Hc");
+        assert_eq!(
+            screen.take_damage(),
+            [
+                Position::new(0, 0),
+                Position::new(1, 0),
+                Position::new(4, 2)
+            ]
+        );
+        assert!(screen.take_damage().is_empty());
+    }
+
+    #[test]
+    fn scrollback_keeps_lines_scrolled_off() {
+        let mut screen = VtScreen::new(4, 2);
+        screen.set_scrollback_limit(2);
+        screen.process(b"a\r\nb\r\nc\r\nd\r\ne\x1b[?1049h\r\n\r\n\r\n");
+
+        let line = |idx| screen.scrollback_line(idx).map(|cells: &[Cell]| cells[0].symbol());
+        assert_eq!(screen.scrollback_len(), 2);
+        assert_eq!((line(0), line(1)), (Some("b"), Some("c")));
+
+        screen.process(b"\x1b[?1049l\x1b[3J");
+        assert_eq!(screen.scrollback_len(), 0);
+    }
+
+    #[test]
+    fn resize_keeps_cursor_row_visible() {
+        let mut screen = VtScreen::new(10, 5);
+        screen.process(b"top\x1b[5;1Hbottom");
+        screen.resize(8, 3);
+
+        assert_eq!(screen.size(), (8, 3));
+        assert_eq!(to_plain_text(screen.buffer()), "\n\nbottom\n");
+        assert_eq!(screen.cursor(), Some(Position::new(6, 2)));
+    }
+
+    #[test]
+    fn resize_in_alternate_screen_clamps_saved_primary_cursor() {
+        let mut screen = VtScreen::new(10, 50);
+        screen.process(b"\x1b[40;1Hprompt\x1b[?1049h");
+        screen.resize(10, 20);
+        screen.process(b"\x1b[?1049lX");
+
+        assert_eq!(screen.cursor(), Some(Position::new(7, 19)));
+        assert_eq!(screen.buffer()[(6, 19)].symbol(), "X");
+    }
+}
@@ -0,0 +1,264 @@
+#!/usr/bin/env python3
+"""Writes ratbeam-test.ttf, the font the font and shaping tests load.
+
+A monospace TrueType font of rectangles, 600 units wide on a 1000 unit em,
+with just the tables the tests exercise:
+
+- `liga` ligatures of `->` and `fi`,
+- a `calt` chain turning `==` into a start and an end glyph,
+- an sbix PNG bitmap for U+1F600.
+
+Run it from this directory with any Python 3; it needs no packages.
+"""
+
+import struct
+import zlib
+
+UNITS_PER_EM = 1000
+ASCENT = 800
+DESCENT = -200
+ADVANCE = 600
+
+# name, code point, advance, outline rectangle (x0, y0, x1, y1)
+GLYPHS = [
+    (".notdef", None, ADVANCE, (50, 0, 550, 700)),
+    ("space", 0x20, ADVANCE, None),
+    ("hyphen", 0x2D, ADVANCE, (100, 300, 500, 380)),
+    ("equal", 0x3D, ADVANCE, (100, 250, 500, 450)),
+    ("greater", 0x3E, ADVANCE, (100, 100, 500, 600)),
+    ("M", 0x4D, ADVANCE, (50, 0, 550, 700)),
+    ("f", 0x66, ADVANCE, (150, 0, 450, 750)),
+    ("i", 0x69, ADVANCE, (250, 0, 350, 700)),
+    ("x", 0x78, ADVANCE, (100, 0, 500, 500)),
+    ("eacute", 0xE9, ADVANCE, (100, 0, 500, 700)),
+    ("hyphen_greater.liga", None, 2 * ADVANCE, (100, 100, 1100, 600)),
+    ("f_i.liga", None, 2 * ADVANCE, (150, 0, 950, 750)),
+    ("equal.start", None, ADVANCE, (100, 250, 600, 450)),
+    ("equal.end", None, ADVANCE, (0, 250, 500, 450)),
+    ("grinning", 0x1F600, 2 * ADVANCE, None),
+]
+ID = {name: index for index, (name, *_) in enumerate(GLYPHS)}
+
+COPYRIGHT = "Copyright 2026 The ratbeam Authors"
+LICENSE = "This Font Software is licensed under the SIL Open Font License, Version 1.1."
+
+
+def pad(data):
+    return data + b"\0" * (-len(data) % 4)
+
+
+def checksum(data):
+    data = pad(data)
+    return sum(struct.unpack(f">{len(data) // 4}I", data)) & 0xFFFFFFFF
+
+
+def glyf_and_loca():
+    glyf, offsets = b"", [0]
+    for _, _, _, rect in GLYPHS:
+        if rect:
+            x0, y0, x1, y1 = rect
+            points = [(x0, y0), (x0, y1), (x1, y1), (x1, y0)]
+            deltas, last = [], (0, 0)
+            for point in points:
+                deltas.append((point[0] - last[0], point[1] - last[1]))
+                last = point
+            glyf += struct.pack(">hhhhhH", 1, x0, y0, x1, y1, 3)
+            glyf += struct.pack(">H", 0)  # no instructions
+            glyf += bytes([0x01] * 4)  # on-curve points, 16-bit coordinates
+            glyf += b"".join(struct.pack(">h", dx) for dx, _ in deltas)
+            glyf += b"".join(struct.pack(">h", dy) for _, dy in deltas)
+            glyf = pad(glyf)
+        offsets.append(len(glyf))
+    return glyf, struct.pack(f">{len(offsets)}I", *offsets)
+
+
+def cmap():
+    mapped = sorted((code, ID[name]) for name, code, *_ in GLYPHS if code is not None)
+    groups = b"".join(struct.pack(">III", code, code, glyph) for code, glyph in mapped)
+    subtable = struct.pack(">HHIII", 12, 0, 16 + len(groups), 0, len(mapped)) + groups
+    return struct.pack(">HHHHI", 0, 1, 3, 10, 12) + subtable
+
+
+def coverage(*names):
+    ids = sorted(ID[name] for name in names)
+    return struct.pack(f">HH{len(ids)}H", 1, len(ids), *ids)
+
+
+def single(source, target):
+    # format 2, coverage right after the substitute
+    return struct.pack(">HHHH", 2, 8, 1, ID[target]) + coverage(source)
+
+
+def chain(backtrack, input, lookahead, lookup):
+    """A format 3 chained context substituting the first input glyph."""
+    groups = [backtrack, input, lookahead]
+    header_size = 2 + sum(2 + 2 * len(group) for group in groups) + 2 + 4
+    header, tables = struct.pack(">H", 3), b""
+    for group in groups:
+        header += struct.pack(">H", len(group))
+        for name in group:
+            header += struct.pack(">H", header_size + len(tables))
+            tables += coverage(name)
+    header += struct.pack(">HHH", 1, 0, lookup)
+    return header + tables
+
+
+def ligatures(rules):
+    """A ligature subtable of rules (first, rest, ligature), one per first glyph."""
+    firsts = [first for first, _, _ in rules]
+    header_size = 6 + 2 * len(rules)
+    sets = b""
+    offsets = []
+    for _, rest, ligature in rules:
+        offsets.append(header_size + len(sets))
+        components = [ID[name] for name in rest]
+        # one ligature per set, right after the set's offset
+        sets += struct.pack(">HH", 1, 4)
+        sets += struct.pack(f">HH{len(components)}H", ID[ligature], len(components) + 1, *components)
+    coverage_offset = header_size + len(sets)
+    ordered = sorted(zip(firsts, offsets), key=lambda pair: ID[pair[0]])
+    header = struct.pack(">HHH", 1, coverage_offset, len(rules))
+    header += b"".join(struct.pack(">H", offset) for _, offset in ordered)
+    return header + sets + coverage(*firsts)
+
+
+def lookup(kind, subtables):
+    header_size = 6 + 2 * len(subtables)
+    header = struct.pack(">HHH", kind, 0, len(subtables))
+    body = b""
+    for subtable in subtables:
+        header += struct.pack(">H", header_size + len(body))
+        body += subtable
+    return header + body
+
+
+def offset_list(items):
+    header = struct.pack(">H", len(items))
+    body = b""
+    for item in items:
+        header += struct.pack(">H", 2 + 2 * len(items) + len(body))
+        body += item
+    return header + body
+
+
+def gsub():
+    script = struct.pack(">HH", 4, 0) + struct.pack(">HHHHH", 0, 0xFFFF, 2, 0, 1)
+    scripts = struct.pack(">H4sH", 1, b"latn", 8) + script
+    calt = struct.pack(">HHH", 0, 1, 0)
+    liga = struct.pack(">HHH", 0, 1, 1)
+    features = struct.pack(">H4sH4sH", 2, b"calt", 14, b"liga", 14 + len(calt)) + calt + liga
+    lookups = offset_list([
+        lookup(6, [
+            chain([], ["equal"], ["equal"], 2),
+            chain(["equal.start"], ["equal"], [], 3),
+        ]),
+        lookup(4, [ligatures([
+            ("hyphen", ["greater"], "hyphen_greater.liga"),
+            ("f", ["i"], "f_i.liga"),
+        ])]),
+        lookup(1, [single("equal", "equal.start")]),
+        lookup(1, [single("equal", "equal.end")]),
+    ])
+    header_size = 10
+    return (
+        struct.pack(
+            ">HHHHH",
+            1,
+            0,
+            header_size,
+            header_size + len(scripts),
+            header_size + len(scripts) + len(features),
+        )
+        + scripts
+        + features
+        + lookups
+    )
+
+
+def png(width, height, rgba):
+    def chunk(tag, data):
+        return struct.pack(">I", len(data)) + tag + data + struct.pack(">I", zlib.crc32(tag + data))
+
+    rows = b"".join(b"\0" + bytes(rgba) * width for _ in range(height))
+    return (
+        b"\x89PNG\r\n\x1a\n"
+        + chunk(b"IHDR", struct.pack(">IIBBBBB", width, height, 8, 6, 0, 0, 0))
+        + chunk(b"IDAT", zlib.compress(rows))
+        + chunk(b"IEND", b"")
+    )
+
+
+def sbix():
+    image = struct.pack(">hh4s", 0, 0, b"png ") + png(4, 4, (255, 0, 0, 255))
+    strike_header = 4 + 4 * (len(GLYPHS) + 1)
+    offsets, data = [], b""
+    for name, *_ in GLYPHS:
+        offsets.append(strike_header + len(data))
+        if name == "grinning":
+            data += image
+    offsets.append(strike_header + len(data))
+    strike = struct.pack(f">HH{len(offsets)}I", 20, 72, *offsets) + data
+    return struct.pack(">HHII", 1, 1, 1, 12) + strike
+
+
+def name():
+    records = [(0, COPYRIGHT), (1, "Ratbeam Test"), (2, "Regular"), (4, "Ratbeam Test"), (13, LICENSE)]
+    strings, entries = b"", b""
+    for name_id, text in records:
+        encoded = text.encode("utf-16-be")
+        entries += struct.pack(">HHHHHH", 3, 1, 0x409, name_id, len(encoded), len(strings))
+        strings += encoded
+    return struct.pack(">HHH", 0, len(records), 6 + len(entries)) + entries + strings
+
+
+def font():
+    glyf, loca = glyf_and_loca()
+    tables = {
+        b"GSUB": gsub(),
+        b"OS/2": struct.pack(
+            ">HhHHHhhhhhhhhhhh10s4I4sHHHhhhHH2IHHHHH",
+            4, ADVANCE, 400, 5, 0, 650, 600, 0, 75, 650, 600, 0, 350, 50, 300, 0,
+            bytes(10), 1, 0, 0, 0, b"NONE", 0x40, 0x20, 0xFFFF,
+            ASCENT, DESCENT, 0, ASCENT, -DESCENT, 1, 0, 500, 700, 0, 0x20, 2,
+        ),
+        b"cmap": cmap(),
+        b"glyf": glyf,
+        b"head": struct.pack(
+            ">HHiIIHHqqhhhhHHhhh",
+            1, 0, 0x10000, 0, 0x5F0F3CF5, 0x000B, UNITS_PER_EM, 0, 0,
+            0, DESCENT, 2 * ADVANCE, ASCENT, 0, 8, 2, 1, 0,
+        ),
+        b"hhea": struct.pack(
+            ">HHhhhHhhhhhhhhhhhH",
+            1, 0, ASCENT, DESCENT, 0, 2 * ADVANCE, 0, 0, 2 * ADVANCE, 1, 0, 0, 0, 0, 0, 0, 0,
+            len(GLYPHS),
+        ),
+        b"hmtx": b"".join(
+            struct.pack(">Hh", advance, rect[0] if rect else 0) for _, _, advance, rect in GLYPHS
+        ),
+        b"loca": loca,
+        b"maxp": struct.pack(">IHHHHHHHHHHHHHH", 0x10000, len(GLYPHS), 4, 1, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0),
+        b"name": name(),
+        b"post": struct.pack(">Iihhiiiii", 0x30000, 0, -100, 50, 1, 0, 0, 0, 0),
+        b"sbix": sbix(),
+    }
+
+    count = len(tables)
+    power = 1 << (count.bit_length() - 1)
+    header = struct.pack(">IHHHH", 0x10000, count, 16 * power, power.bit_length() - 1, 16 * (count - power))
+    offset = 12 + 16 * count
+    directory, body = b"", b""
+    for tag, data in sorted(tables.items()):
+        directory += struct.pack(">4sIII", tag, checksum(data), offset + len(body), len(data))
+        body += pad(data)
+
+    font = header + directory + body
+    # head.checkSumAdjustment, at offset 8 of the head table
+    head_offset = offset + sum(len(pad(data)) for tag, data in sorted(tables.items()) if tag < b"head")
+    adjustment = (0xB1B0AFBA - checksum(font)) & 0xFFFFFFFF
+    return font[: head_offset + 8] + struct.pack(">I", adjustment) + font[head_offset + 12 :]
+
+
+if __name__ == "__main__":
+    with open("ratbeam-test.ttf", "wb") as out:
+        out.write(font())